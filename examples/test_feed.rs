@@ -1,6 +1,6 @@
 //! Simple test to verify feed fetching works.
 
-use ferrofeed::client;
+use ferrofeed::{client::FeedClient, config::HttpConfig};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -9,7 +9,8 @@ async fn main() -> anyhow::Result<()> {
 
     println!("Fetching feed from: {}", test_url);
 
-    let feed = client::fetch_feed(test_url).await?;
+    let client = FeedClient::new(&HttpConfig::default())?;
+    let feed = client.fetch_feed(test_url).await?;
 
     println!("\nFeed Title: {:?}", feed.title);
     println!("Number of items: {}", feed.items.len());
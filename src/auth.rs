@@ -0,0 +1,359 @@
+/*!
+Credentials for private feeds, loaded from a TOML auth file kept apart from the database.
+
+Each entry applies to the URLs with the same scheme, host and port as its `url` and a path
+within its path, the longest match winning, so one entry can cover all the feeds of a site. Credentials are only sent to matching URLs, never to
+where a feed redirects to. Secrets are written inline, read from an environment variable, or
+taken from the output of a command, so they don't have to be stored in plain text.
+
+```toml
+[[feed]]
+url = "https://confluence.example.com/"
+basic = { username = "me", password = { cmd = "pass show confluence" } }
+
+[[feed]]
+url = "https://github.com/me/private/commits/main.atom"
+bearer = { env = "GITHUB_TOKEN" }
+
+[[feed]]
+url = "https://newsletter.example.com/feed"
+headers = { "X-Api-Key" = { env = "NEWSLETTER_KEY" } }
+cookies = { session = { cmd = "cat ~/.newsletter-session" } }
+```
+*/
+use anyhow::{Context, Result};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use reqwest::header::{AUTHORIZATION, COOKIE, HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path, process::Command, sync::Mutex};
+use url::Url;
+
+use crate::config::AuthConfig;
+
+/// A secret as written in the auth file or the config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Secret {
+    /// The secret itself.
+    Value(String),
+    /// The value of an environment variable.
+    Env { env: String },
+    /// The output of a shell command, without the trailing newline.
+    Command { cmd: String },
+}
+
+impl Secret {
+    /// The value of the secret, running its command if it has one.
+    pub fn resolve(&self) -> Result<String> {
+        match self {
+            Secret::Value(value) => Ok(value.clone()),
+            Secret::Env { env } => std::env::var(env)
+                .with_context(|| format!("environment variable {} is not set", env)),
+            Secret::Command { cmd } => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(cmd)
+                    .output()
+                    .with_context(|| format!("failed to run `{}`", cmd))?;
+                if !output.status.success() {
+                    anyhow::bail!("`{}` failed with {}", cmd, output.status);
+                }
+                let value = String::from_utf8(output.stdout)
+                    .with_context(|| format!("`{}` printed invalid UTF-8", cmd))?;
+                Ok(value.trim_end_matches(['\r', '\n']).to_string())
+            }
+        }
+    }
+}
+
+/// HTTP Basic credentials.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BasicDef {
+    username: String,
+    password: Option<Secret>,
+}
+
+/// An entry as written in the auth file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FeedAuthDef {
+    url: String,
+    basic: Option<BasicDef>,
+    bearer: Option<Secret>,
+    #[serde(default)]
+    headers: BTreeMap<String, Secret>,
+    #[serde(default)]
+    cookies: BTreeMap<String, Secret>,
+}
+
+/// The auth file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AuthFile {
+    #[serde(default)]
+    feed: Vec<FeedAuthDef>,
+}
+
+/// Credentials for the URLs within a prefix.
+#[derive(Debug)]
+struct FeedAuth {
+    def: FeedAuthDef,
+    /// The parsed `url` of the entry.
+    prefix: Url,
+    /// Headers with the secrets resolved, once they're first needed.
+    headers: Mutex<Option<HeaderMap>>,
+}
+
+impl FeedAuth {
+    /// Whether the entry applies to `url`: the same scheme, host and port, and a path that is
+    /// the entry's path or below it. `https://example.com/team` applies to
+    /// `https://example.com/team/feed` but not to `https://example.com/teams` or
+    /// `https://example.com.evil.io/team`.
+    fn applies_to(&self, url: &Url) -> bool {
+        let prefix = &self.prefix;
+        if url.scheme() != prefix.scheme()
+            || url.host() != prefix.host()
+            || url.port_or_known_default() != prefix.port_or_known_default()
+        {
+            return false;
+        }
+        let within_path = url.path().strip_prefix(prefix.path()).is_some_and(|rest| {
+            rest.is_empty() || rest.starts_with('/') || prefix.path().ends_with('/')
+        });
+        within_path
+            && prefix
+                .query()
+                .is_none_or(|query| url.query() == Some(query))
+    }
+
+    /// The headers to send, resolving the secrets the first time.
+    fn headers(&self) -> Result<HeaderMap> {
+        let mut cached = self.headers.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(headers) = &*cached {
+            return Ok(headers.clone());
+        }
+        let headers = self
+            .resolve()
+            .with_context(|| format!("failed to resolve credentials for {}", self.def.url))?;
+        *cached = Some(headers.clone());
+        Ok(headers)
+    }
+
+    /// Resolve the secrets into headers.
+    fn resolve(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        if let Some(basic) = &self.def.basic {
+            let password = basic.password.as_ref().map(Secret::resolve).transpose()?;
+            let credentials = format!("{}:{}", basic.username, password.unwrap_or_default());
+            headers.insert(
+                AUTHORIZATION,
+                sensitive(&format!("Basic {}", STANDARD.encode(credentials)))?,
+            );
+        }
+        if let Some(token) = &self.def.bearer {
+            headers.insert(
+                AUTHORIZATION,
+                sensitive(&format!("Bearer {}", token.resolve()?))?,
+            );
+        }
+        for (name, value) in &self.def.headers {
+            let name = HeaderName::try_from(name.as_str())
+                .with_context(|| format!("invalid header name: {}", name))?;
+            headers.insert(name, sensitive(&value.resolve()?)?);
+        }
+        if !self.def.cookies.is_empty() {
+            let cookies = self
+                .def
+                .cookies
+                .iter()
+                .map(|(name, value)| Ok(format!("{}={}", name, value.resolve()?)))
+                .collect::<Result<Vec<_>>>()?;
+            headers.insert(COOKIE, sensitive(&cookies.join("; "))?);
+        }
+        Ok(headers)
+    }
+}
+
+/// A header value that is left out of debug output.
+fn sensitive(value: &str) -> Result<HeaderValue> {
+    let mut value = HeaderValue::from_str(value).context("invalid header value")?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// The credentials of the auth file.
+#[derive(Debug, Default)]
+pub struct AuthSet {
+    entries: Vec<FeedAuth>,
+}
+
+impl AuthSet {
+    /// Load the auth file at `path`. A missing file has no credentials.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("failed to read auth file at {}", path.display()))?;
+        Self::parse(&data)
+            .with_context(|| format!("failed to parse auth file at {}", path.display()))
+    }
+
+    /// Load the auth file set in the config, if any.
+    pub fn from_config(config: &AuthConfig) -> Result<Self> {
+        match &config.path {
+            Some(path) => Self::load(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Parse credentials from TOML.
+    pub fn parse(data: &str) -> Result<Self> {
+        let file: AuthFile = toml::from_str(data)?;
+        let entries = file
+            .feed
+            .into_iter()
+            .map(|def| {
+                let prefix =
+                    Url::parse(&def.url).with_context(|| format!("invalid url: {}", def.url))?;
+                Ok(FeedAuth {
+                    def,
+                    prefix,
+                    headers: Mutex::new(None),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { entries })
+    }
+
+    /// Whether there are no credentials.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The headers to send with a request to `url`, if any entry applies to it.
+    pub fn headers_for(&self, url: &str) -> Result<Option<HeaderMap>> {
+        let Ok(url) = Url::parse(url) else {
+            return Ok(None);
+        };
+        self.entries
+            .iter()
+            .filter(|entry| entry.applies_to(&url))
+            .max_by_key(|entry| entry.def.url.len())
+            .map(FeedAuth::headers)
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headers_for_longest_prefix() {
+        let auth = AuthSet::parse(
+            r#"
+            [[feed]]
+            url = "https://example.com/"
+            basic = { username = "me", password = "hunter2" }
+
+            [[feed]]
+            url = "https://example.com/private/"
+            bearer = { cmd = "echo token" }
+            headers = { "X-Api-Key" = "key" }
+            cookies = { session = "abc", theme = "dark" }
+            "#,
+        )
+        .expect("failed to parse auth");
+
+        let headers = auth
+            .headers_for("https://example.com/feed.xml")
+            .unwrap()
+            .expect("no credentials for prefix");
+        assert_eq!(headers[AUTHORIZATION], "Basic bWU6aHVudGVyMg==");
+        assert!(headers[AUTHORIZATION].is_sensitive());
+
+        let headers = auth
+            .headers_for("https://example.com/private/feed.xml")
+            .unwrap()
+            .expect("no credentials for longer prefix");
+        assert_eq!(headers[AUTHORIZATION], "Bearer token");
+        assert_eq!(headers["x-api-key"], "key");
+        assert_eq!(headers[COOKIE], "session=abc; theme=dark");
+
+        assert!(auth.headers_for("https://other.com/").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_headers_for_same_origin_only() {
+        let auth = AuthSet::parse(
+            r#"
+            [[feed]]
+            url = "https://example.com"
+            bearer = "site"
+
+            [[feed]]
+            url = "https://example.org/team"
+            bearer = "team"
+            "#,
+        )
+        .expect("failed to parse auth");
+        let token = |url: &str| {
+            auth.headers_for(url)
+                .unwrap()
+                .map(|headers| headers[AUTHORIZATION].to_str().unwrap().to_string())
+        };
+
+        assert_eq!(
+            token("https://example.com/feed.xml").as_deref(),
+            Some("Bearer site")
+        );
+        assert_eq!(
+            token("https://EXAMPLE.com:443/").as_deref(),
+            Some("Bearer site")
+        );
+        for url in [
+            "https://example.com.evil.io/",
+            "https://example.community/",
+            "https://example.com@evil.io/",
+            "http://example.com/",
+            "https://example.com:8443/",
+            "not a url",
+        ] {
+            assert_eq!(token(url), None, "credentials sent to {}", url);
+        }
+
+        // Paths match on segment boundaries
+        assert_eq!(
+            token("https://example.org/team").as_deref(),
+            Some("Bearer team")
+        );
+        assert_eq!(
+            token("https://example.org/team/feed").as_deref(),
+            Some("Bearer team")
+        );
+        assert_eq!(token("https://example.org/teams"), None);
+        assert_eq!(token("https://example.org/"), None);
+
+        assert!(AuthSet::parse("[[feed]]\nurl = \"example.com\"").is_err());
+    }
+
+    #[test]
+    fn test_unresolvable_secret() {
+        let auth = AuthSet::parse(
+            r#"
+            [[feed]]
+            url = "https://example.com/"
+            bearer = { env = "FERROFEED_TEST_UNSET_TOKEN" }
+
+            [[feed]]
+            url = "https://example.org/"
+            bearer = { cmd = "exit 1" }
+            "#,
+        )
+        .expect("failed to parse auth");
+        assert!(auth.headers_for("https://example.com/feed").is_err());
+        assert!(auth.headers_for("https://example.org/feed").is_err());
+    }
+}
@@ -6,54 +6,316 @@
 
 use anyhow::{Context, Result};
 use feed_rs::parser;
+use regex::Regex;
+use reqwest::{Certificate, NoProxy, Proxy, StatusCode, header::LOCATION, redirect::Policy};
+use std::{
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use crate::{
+    auth::AuthSet,
+    config::{Config, HttpConfig},
+};
+
+/// Maximum number of redirects followed when fetching a feed.
+const MAX_REDIRECTS: usize = 10;
 
 /// Parsed feed data containing metadata and items.
 #[derive(Debug)]
 pub struct ParsedFeed {
     /// The title of the feed.
     pub title: Option<String>,
+    /// Information about the feed and the site it belongs to.
+    pub metadata: FeedMetadata,
     /// The feed items/entries.
     pub items: Vec<ParsedFeedItem>,
+    /// Where the feed was fetched from, if it was redirected.
+    pub redirect: Option<Redirect>,
 }
 
-/// A single item from a feed.
+/// A redirect followed when fetching a feed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    /// The URL the feed was fetched from in the end.
+    pub url: String,
+    /// Whether every redirect on the way was permanent (301 or 308), meaning the feed moved.
+    pub permanent: bool,
+}
+
+/// Error for a feed that is permanently gone (`410 Gone`).
 #[derive(Debug)]
+pub struct FeedGone {
+    /// The URL of the feed.
+    pub url: String,
+}
+
+impl std::fmt::Display for FeedGone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "feed at {} is permanently gone (410 Gone)", self.url)
+    }
+}
+
+impl std::error::Error for FeedGone {}
+
+/// Information about a feed and the site it belongs to.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FeedMetadata {
+    /// Link to the site's homepage.
+    pub site_link: Option<String>,
+    /// Description of the feed.
+    pub description: Option<String>,
+    /// Language code, e.g. `en-us`.
+    pub language: Option<String>,
+    /// URL of a small icon for the feed, falling back to the site's favicon.
+    pub icon: Option<String>,
+    /// URL of a larger logo image for the feed.
+    pub logo: Option<String>,
+    /// The software that generated the feed.
+    pub generator: Option<String>,
+    /// The authors of the feed.
+    pub authors: Vec<String>,
+    /// The categories of the feed.
+    pub categories: Vec<String>,
+    /// When the feed was last updated, as a Unix timestamp.
+    pub updated: Option<i64>,
+}
+
+/// A single item from a feed.
+#[derive(Debug, Default, Clone)]
 pub struct ParsedFeedItem {
+    /// The entry ID (`<guid>` in RSS, `<id>` in Atom).
+    pub guid: Option<String>,
     /// The title of the item.
     pub title: Option<String>,
     /// The link to the full content.
     pub link: Option<String>,
+    /// The canonical link to the content, if the feed or the linked page declares one.
+    pub canonical_link: Option<String>,
     /// The description/summary.
     pub description: Option<String>,
     /// The author(s).
     pub authors: Vec<String>,
     /// Published date as Unix timestamp.
     pub published: Option<i64>,
+    /// Last updated date as Unix timestamp, if the feed gives one.
+    pub updated: Option<i64>,
+    /// The categories (tags, keywords) of the item.
+    pub categories: Vec<String>,
+}
+
+/// HTTP client for all of ferrofeed's fetches, configured by the `[http]` config section.
+/// Cloning is cheap and shares the connection pool.
+#[derive(Debug, Clone)]
+pub struct FeedClient {
+    /// The underlying client. Redirects are followed by hand, see [`FeedClient::get`].
+    client: reqwest::Client,
+    /// Maximum size of a response body in bytes.
+    max_response_bytes: u64,
+    /// Credentials sent to the URLs they apply to.
+    auth: Arc<AuthSet>,
 }
 
-/// Fetch and parse an RSS/Atom feed from a URL.
-pub async fn fetch_feed(url: &str) -> Result<ParsedFeed> {
-    // Fetch the feed content
-    let response = reqwest::get(url)
-        .await
-        .with_context(|| format!("failed to fetch feed from {}", url))?;
+impl FeedClient {
+    /// Build a client from the HTTP and auth sections of the config.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        Ok(Self::new(&config.http)?.with_auth(AuthSet::from_config(&config.auth)?))
+    }
+
+    /// Build a client from the HTTP config, without credentials.
+    pub fn new(config: &HttpConfig) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(&config.user_agent)
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(config.read_timeout_secs))
+            .gzip(config.compression)
+            .brotli(config.compression)
+            .redirect(Policy::none());
+        // Without a configured proxy, the proxy environment variables are used
+        if let Some(proxy) = &config.proxy {
+            let proxy = Proxy::all(proxy)
+                .with_context(|| format!("invalid proxy URL: {}", proxy))?
+                .no_proxy(config.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &config.ca_bundle {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("failed to read CA bundle at {}", path.display()))?;
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("invalid CA bundle at {}", path.display()))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        Ok(Self {
+            client: builder.build().context("failed to build HTTP client")?,
+            max_response_bytes: config.max_response_bytes,
+            auth: Arc::default(),
+        })
+    }
+
+    /// Send credentials from an auth file with requests to the URLs they apply to.
+    pub fn with_auth(mut self, auth: AuthSet) -> Self {
+        self.auth = Arc::new(auth);
+        self
+    }
+
+    /// Fetch and parse an RSS/Atom feed from a URL. Redirects are followed and reported in
+    /// [`ParsedFeed::redirect`]. A feed that is permanently gone fails with a [`FeedGone`]
+    /// error.
+    pub async fn fetch_feed(&self, url: &str) -> Result<ParsedFeed> {
+        let (response, redirect) = self
+            .get(url)
+            .await
+            .with_context(|| format!("failed to fetch feed from {}", url))?;
+        if response.status() == StatusCode::GONE {
+            let url = redirect.map_or_else(|| url.to_string(), |r| r.url);
+            return Err(FeedGone { url }.into());
+        }
+        let content = self.read_body(response).await?;
+
+        let mut feed =
+            parse_feed(&content).with_context(|| format!("failed to parse feed from {}", url))?;
+        feed.redirect = redirect;
+        Ok(feed)
+    }
+
+    /// Fetch the page at `url` and return the canonical URL it declares with
+    /// `<link rel="canonical">`, if any.
+    pub async fn fetch_canonical_url(&self, url: &str) -> Result<Option<String>> {
+        let body = self
+            .get_bytes(url)
+            .await
+            .with_context(|| format!("failed to fetch page from {}", url))?;
+        Ok(find_canonical_url(&String::from_utf8_lossy(&body), url))
+    }
+
+    /// Start a request to an API, sent with the configured user agent, timeouts and proxy but
+    /// without any credentials of the auth file.
+    pub fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Fetch the body of a URL, failing on error statuses.
+    pub async fn get_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        let (response, _) = self.get(url).await?;
+        self.read_body(response).await
+    }
+
+    /// Send a GET request, following redirects by hand to tell permanent ones apart.
+    async fn get(&self, url: &str) -> Result<(reqwest::Response, Option<Redirect>)> {
+        let mut current = url.to_string();
+        let mut permanent = true;
+        for _ in 0..=MAX_REDIRECTS {
+            let mut request = self.client.get(&current);
+            // Credentials go only to the URLs they're for, not wherever a feed redirects
+            if let Some(headers) = self.auth.headers_for(&current)? {
+                request = request.headers(headers);
+            }
+            let response = request.send().await?;
+            if !response.status().is_redirection() {
+                let redirect = (current != url).then_some(Redirect {
+                    url: current,
+                    permanent,
+                });
+                return Ok((response, redirect));
+            }
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|l| l.to_str().ok())
+                .with_context(|| format!("redirect without a location from {}", current))?;
+            let next = response
+                .url()
+                .join(location)
+                .with_context(|| format!("invalid redirect location: {}", location))?;
+            permanent &= matches!(
+                response.status(),
+                StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+            );
+            current = next.to_string();
+        }
+        anyhow::bail!("too many redirects from {}", url)
+    }
+
+    /// Read a response body, failing on error statuses and bodies over the size limit.
+    async fn read_body(&self, response: reqwest::Response) -> Result<Vec<u8>> {
+        let mut response = response.error_for_status()?;
+        let too_large = || anyhow::anyhow!("response is over {} bytes", self.max_response_bytes);
+        if response
+            .content_length()
+            .is_some_and(|len| len > self.max_response_bytes)
+        {
+            return Err(too_large());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .context("failed to read response body")?
+        {
+            body.extend_from_slice(&chunk);
+            if body.len() as u64 > self.max_response_bytes {
+                return Err(too_large());
+            }
+        }
+        Ok(body)
+    }
+}
 
-    let content = response
-        .bytes()
-        .await
-        .context("failed to read response body")?;
+/// Parse an RSS/Atom feed document.
+pub fn parse_feed(content: &[u8]) -> Result<ParsedFeed> {
+    // Leave missing entry IDs empty rather than generated, so they aren't mistaken for GUIDs
+    let feed = parser::Builder::new()
+        .id_generator(|_, _, _| String::new())
+        .build()
+        .parse(content)?;
 
-    // Parse the feed
-    let feed = parser::parse(&content[..])
-        .with_context(|| format!("failed to parse feed from {}", url))?;
+    let title = feed.title.as_ref().map(|t| t.content.clone());
 
-    let title = feed.title.map(|t| t.content);
+    // The site link is the feed's link to itself when there's no other
+    let site_link = feed
+        .links
+        .iter()
+        .find(|l| l.rel.as_deref() != Some("self"))
+        .map(|l| l.href.clone());
+    let favicon = site_link
+        .as_deref()
+        .and_then(|link| url::Url::parse(link).ok())
+        .and_then(|site| site.join("/favicon.ico").ok())
+        .map(|u| u.to_string());
+    let metadata = FeedMetadata {
+        description: feed
+            .description
+            .as_ref()
+            .map(|d| d.content.trim().to_string()),
+        language: feed.language.clone(),
+        icon: feed.icon.as_ref().map(|i| i.uri.clone()).or(favicon),
+        logo: feed.logo.as_ref().map(|i| i.uri.clone()),
+        generator: feed.generator.as_ref().map(|g| match &g.version {
+            Some(version) => format!("{} {}", g.content.trim(), version),
+            None => g.content.trim().to_string(),
+        }),
+        authors: feed
+            .authors
+            .iter()
+            .map(|a| a.name.trim().to_string())
+            .collect(),
+        categories: category_names(&feed.categories),
+        updated: feed.updated.or(feed.published).map(|dt| dt.timestamp()),
+        site_link,
+    };
     let items = feed
         .entries
         .into_iter()
         .map(|entry| {
             // Get the first link if available
             let link = entry.links.first().map(|l| l.href.clone());
+            let canonical_link = entry
+                .links
+                .iter()
+                .find(|l| l.rel.as_deref() == Some("canonical"))
+                .map(|l| l.href.clone());
 
             // Get description from summary or content
             let description = entry.summary.map(|s| s.content).or_else(|| {
@@ -72,15 +334,251 @@ pub async fn fetch_feed(url: &str) -> Result<ParsedFeed> {
             // Get published date as Unix timestamp
             let published = entry.published.or(entry.updated).map(|dt| dt.timestamp());
 
+            let guid = Some(entry.id).filter(|id| !id.is_empty());
+
             ParsedFeedItem {
+                guid,
                 title: entry.title.map(|t| t.content),
                 link,
+                canonical_link,
                 description,
                 authors,
                 published,
+                updated: entry.updated.map(|dt| dt.timestamp()),
+                categories: category_names(&entry.categories),
             }
         })
         .collect();
 
-    Ok(ParsedFeed { title, items })
+    Ok(ParsedFeed {
+        title,
+        metadata,
+        items,
+        redirect: None,
+    })
+}
+
+/// The names of categories, preferring their human-readable labels. Blank and repeated names
+/// are left out.
+fn category_names(categories: &[feed_rs::model::Category]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for category in categories {
+        let name = category.label.as_deref().unwrap_or(&category.term).trim();
+        if !name.is_empty() && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Matches a `<link rel="canonical">` tag in an HTML page.
+static CANONICAL_LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<link\s[^>]*rel\s*=\s*["']?canonical["']?[^>]*>"#).unwrap()
+});
+
+/// Matches the `href` attribute of a tag.
+static HREF_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)href\s*=\s*["']?([^"'\s>]+)"#).unwrap());
+
+/// Find the canonical URL declared in an HTML page, resolved against the page URL.
+fn find_canonical_url(html: &str, page_url: &str) -> Option<String> {
+    let tag = CANONICAL_LINK_RE.find(html)?;
+    let href = HREF_RE.captures(tag.as_str())?.get(1)?.as_str();
+    let href = html_escape::decode_html_entities(href);
+    url::Url::parse(page_url)
+        .and_then(|base| base.join(&href))
+        .ok()
+        .map(|u| u.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::Mutex,
+    };
+
+    /// Serve canned responses by request path on a local port, returning the base URL and the
+    /// heads of the requests received.
+    fn serve(routes: &[(&str, &str)]) -> (String, Arc<Mutex<Vec<String>>>) {
+        let routes: Vec<(String, String)> = routes
+            .iter()
+            .map(|(path, response)| (path.to_string(), response.to_string()))
+            .collect();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(&stream);
+                if reader.read_line(&mut request_line).is_err() {
+                    continue;
+                }
+                let mut head = request_line.clone();
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 0) && line != "\r\n" {
+                    head.push_str(&line);
+                    line.clear();
+                }
+                received.lock().unwrap().push(head);
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let response = routes
+                    .iter()
+                    .find(|(p, _)| p == path)
+                    .map_or("HTTP/1.1 404 Not Found\r\n", |(_, r)| r);
+                let _ = write!(&stream, "{}Connection: close\r\nContent-Length: ", response);
+                let body = "<rss version=\"2.0\"><channel><title>Moved</title></channel></rss>";
+                let body = if response.starts_with("HTTP/1.1 200") {
+                    body
+                } else {
+                    ""
+                };
+                let _ = write!(&stream, "{}\r\n\r\n{}", body.len(), body);
+            }
+        });
+        (base, requests)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_feed_redirects() {
+        let (base, _) = serve(&[
+            (
+                "/old",
+                "HTTP/1.1 301 Moved Permanently\r\nLocation: /new\r\n",
+            ),
+            ("/temp", "HTTP/1.1 302 Found\r\nLocation: /old\r\n"),
+            ("/new", "HTTP/1.1 200 OK\r\n"),
+            ("/gone", "HTTP/1.1 410 Gone\r\n"),
+        ]);
+        let client = FeedClient::new(&HttpConfig::default()).unwrap();
+
+        let feed = client.fetch_feed(&format!("{}/new", base)).await.unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Moved"));
+        assert_eq!(feed.redirect, None);
+
+        let feed = client.fetch_feed(&format!("{}/old", base)).await.unwrap();
+        assert_eq!(
+            feed.redirect,
+            Some(Redirect {
+                url: format!("{}/new", base),
+                permanent: true
+            })
+        );
+
+        // A temporary redirect on the way means the feed didn't move
+        let feed = client.fetch_feed(&format!("{}/temp", base)).await.unwrap();
+        assert!(!feed.redirect.unwrap().permanent);
+
+        let err = client
+            .fetch_feed(&format!("{}/gone", base))
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<FeedGone>().is_some());
+        assert!(
+            client
+                .fetch_feed(&format!("{}/missing", base))
+                .await
+                .is_err()
+        );
+
+        // Bodies over the size limit are rejected
+        let small = FeedClient::new(&HttpConfig {
+            max_response_bytes: 16,
+            ..HttpConfig::default()
+        })
+        .unwrap();
+        assert!(small.fetch_feed(&format!("{}/new", base)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_credentials_not_sent_across_hosts() {
+        let (other, other_requests) = serve(&[("/feed", "HTTP/1.1 200 OK\r\n")]);
+        // The same server under another host name
+        let other = other.replace("127.0.0.1", "localhost");
+        let redirect = format!("HTTP/1.1 302 Found\r\nLocation: {}/feed\r\n", other);
+        let (base, requests) = serve(&[("/feed", &redirect)]);
+        let auth = AuthSet::parse(&format!(
+            "[[feed]]\nurl = \"{}\"\nbearer = \"secret\"",
+            base
+        ))
+        .unwrap();
+        let client = FeedClient::new(&HttpConfig::default())
+            .unwrap()
+            .with_auth(auth);
+
+        let feed = client.fetch_feed(&format!("{}/feed", base)).await.unwrap();
+        assert_eq!(feed.title.as_deref(), Some("Moved"));
+        let has_credentials = |head: &String| head.to_lowercase().contains("authorization:");
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(has_credentials(&requests[0]));
+        let other_requests = other_requests.lock().unwrap();
+        assert_eq!(other_requests.len(), 1);
+        assert!(!other_requests.iter().any(has_credentials));
+    }
+
+    #[test]
+    fn test_parse_feed_metadata() {
+        let atom = br#"<?xml version="1.0" encoding="utf-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom" xml:lang="en-us">
+              <title>Example Blog</title>
+              <subtitle>Posts about examples</subtitle>
+              <link href="https://example.com/feed.xml" rel="self"/>
+              <link href="https://example.com/"/>
+              <updated>2024-01-02T00:00:00Z</updated>
+              <id>urn:uuid:60a76c80-d399-11d9-b91C-0003939e0af6</id>
+              <author><name>Jane Doe</name></author>
+              <category term="rust" label="Rust"/>
+              <generator version="0.1">Zola</generator>
+              <logo>https://example.com/logo.png</logo>
+              <entry>
+                <title>Post</title>
+                <link href="https://example.com/post"/>
+                <id>urn:uuid:1</id>
+                <updated>2024-01-02T00:00:00Z</updated>
+                <category term="rust"/>
+                <category term="async"/>
+                <category term=" "/>
+              </entry>
+            </feed>"#;
+        let feed = parse_feed(atom).expect("failed to parse feed");
+        assert_eq!(feed.title.as_deref(), Some("Example Blog"));
+        assert_eq!(
+            feed.metadata,
+            FeedMetadata {
+                site_link: Some("https://example.com/".to_string()),
+                description: Some("Posts about examples".to_string()),
+                language: Some("en-us".to_string()),
+                icon: Some("https://example.com/favicon.ico".to_string()),
+                logo: Some("https://example.com/logo.png".to_string()),
+                generator: Some("Zola 0.1".to_string()),
+                authors: vec!["Jane Doe".to_string()],
+                categories: vec!["Rust".to_string()],
+                updated: Some(1704153600),
+            }
+        );
+        assert_eq!(feed.items.len(), 1);
+        assert_eq!(feed.items[0].guid.as_deref(), Some("urn:uuid:1"));
+        assert_eq!(feed.items[0].categories, vec!["rust", "async"]);
+    }
+
+    #[test]
+    fn test_find_canonical_url() {
+        let html = r#"<html><head>
+            <link rel="stylesheet" href="/style.css">
+            <link href="/posts/1?a=1&amp;b=2" rel="canonical" />
+            </head></html>"#;
+        assert_eq!(
+            find_canonical_url(html, "https://example.com/p/1?utm_source=rss"),
+            Some("https://example.com/posts/1?a=1&b=2".to_string())
+        );
+        assert_eq!(
+            find_canonical_url("<p>no canonical</p>", "https://example.com"),
+            None
+        );
+    }
 }
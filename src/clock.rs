@@ -0,0 +1,87 @@
+/*!
+Dates and times: the local time, and Unix timestamps formatted for display.
+
+The local UTC offset can only be read while the process has a single thread, so it is read once
+at startup, before the async runtime starts, and used for the rest of the run.
+*/
+use std::sync::OnceLock;
+use time::{
+    OffsetDateTime, UtcOffset,
+    format_description::{
+        BorrowedFormatItem,
+        well_known::{Rfc2822, Rfc3339},
+    },
+    formatting::Formattable,
+    macros::format_description,
+};
+
+/// The local UTC offset, as read at startup.
+static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+
+/// Format of [`format_date`].
+const DATE: &[BorrowedFormatItem] = format_description!("[year]-[month]-[day]");
+
+/// Format of [`format_local`].
+const LOCAL: &[BorrowedFormatItem] = format_description!("[year]-[month]-[day] [hour]:[minute]");
+
+/// Read the local UTC offset. Must be called before any other thread is spawned; the offset is
+/// UTC if it can't be read.
+pub fn init_local_offset() {
+    let _ = LOCAL_OFFSET.set(UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
+}
+
+/// The local UTC offset, or UTC if it wasn't read at startup.
+pub fn local_offset() -> UtcOffset {
+    LOCAL_OFFSET.get().copied().unwrap_or(UtcOffset::UTC)
+}
+
+/// The current local time.
+pub fn now_local() -> OffsetDateTime {
+    OffsetDateTime::now_utc().to_offset(local_offset())
+}
+
+/// Format a Unix timestamp as a `YYYY-MM-DD` date in UTC.
+pub fn format_date(timestamp: i64) -> String {
+    format(timestamp, UtcOffset::UTC, DATE)
+}
+
+/// Format a Unix timestamp as an RFC 3339 time in UTC, e.g. `2024-05-01T12:00:00Z`.
+pub fn format_rfc3339(timestamp: i64) -> String {
+    format(timestamp, UtcOffset::UTC, &Rfc3339)
+}
+
+/// Format a Unix timestamp as an RFC 2822 time in UTC, e.g. `Wed, 01 May 2024 12:00:00 +0000`.
+pub fn format_rfc2822(timestamp: i64) -> String {
+    format(timestamp, UtcOffset::UTC, &Rfc2822)
+}
+
+/// Format a Unix timestamp as `YYYY-MM-DD HH:MM` in local time.
+pub fn format_local(timestamp: i64) -> String {
+    format(timestamp, local_offset(), LOCAL)
+}
+
+/// Format a Unix timestamp at a UTC offset, or as the number if it is out of range.
+fn format(timestamp: i64, offset: UtcOffset, format: &(impl Formattable + ?Sized)) -> String {
+    OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()
+        .and_then(|time| time.to_offset(offset).format(format).ok())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        // The local offset isn't read in tests, so local time is UTC
+        assert_eq!(format_date(1_714_564_800), "2024-05-01");
+        assert_eq!(format_rfc3339(1_714_564_800), "2024-05-01T12:00:00Z");
+        assert_eq!(
+            format_rfc2822(1_714_564_800),
+            "Wed, 01 May 2024 12:00:00 +0000"
+        );
+        assert_eq!(format_local(1_714_564_800), "2024-05-01 12:00");
+        assert_eq!(format_local(i64::MAX), i64::MAX.to_string());
+    }
+}
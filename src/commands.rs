@@ -1,13 +1,31 @@
 //! Business logic for CLI commands.
 
 mod add_feed;
+mod digest;
+mod edit_feed;
+mod export_items;
+mod info;
 mod list;
+mod opml;
+mod publish;
 mod remove_feed;
+mod rules;
 mod schedule;
+mod search;
+mod serve;
 mod sync;
 
 pub use add_feed::add_feed;
-pub use list::list_feeds;
+pub use digest::digest;
+pub use edit_feed::edit_feed;
+pub use export_items::{ExportSelection, export_items};
+pub use info::feed_info;
+pub use list::{list_feeds, list_items};
+pub use opml::{export_opml, import_opml};
+pub use publish::publish;
 pub use remove_feed::remove_feed;
+pub use rules::apply_rules;
 pub use schedule::schedule;
+pub use search::search;
+pub use serve::serve;
 pub use sync::sync_feeds;
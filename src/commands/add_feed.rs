@@ -2,14 +2,25 @@
 
 use anyhow::{Context, Result};
 
-use crate::{client, commands::sync_feeds, db::Db};
+use crate::{
+    client::FeedClient, commands::sync_feeds, config::SyncConfig, db::Db, hooks::HookSet,
+    output::OutputFormat, rules::RuleSet,
+};
 
 /// Add a feed to the database. Fetches the feed to validate and extract metadata.
-pub async fn add_feed(db: &Db, url: &str) -> Result<()> {
+pub async fn add_feed(
+    db: &Db,
+    url: &str,
+    client: &FeedClient,
+    config: &SyncConfig,
+    rules: &RuleSet,
+    hooks: &HookSet,
+) -> Result<()> {
     println!("Fetching feed from {}...", url);
 
     // Fetch and parse the feed to validate it
-    let parsed_feed = client::fetch_feed(url)
+    let parsed_feed = client
+        .fetch_feed(url)
         .await
         .with_context(|| format!("failed to fetch and parse feed from {}", url))?;
 
@@ -26,7 +37,7 @@ pub async fn add_feed(db: &Db, url: &str) -> Result<()> {
 
     // Sync the feed to fetch new items
     println!("Syncing feed...");
-    sync_feeds(db).await?;
+    sync_feeds(db, client, config, rules, hooks, OutputFormat::Text).await?;
 
     Ok(())
 }
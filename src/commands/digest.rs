@@ -0,0 +1,72 @@
+//! Email digest command implementation.
+
+use anyhow::{Context, Result};
+use std::{io::Write as _, path::Path, time::SystemTime};
+
+use crate::{
+    config::DigestConfig,
+    db::Db,
+    digest::{self, Digest, DigestGroup, RenderedDigest},
+};
+
+/// Render the unread items published within `since` (e.g. `24h`) into an email digest. The
+/// digest is sent with the SMTP server of the config, written to `output`, or else printed.
+/// Nothing is sent when there are no unread items.
+pub async fn digest(
+    db: &Db,
+    config: &DigestConfig,
+    since: &str,
+    group_by: Option<DigestGroup>,
+    output: Option<&Path>,
+    send: bool,
+    mark_read: bool,
+) -> Result<()> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    let digest = Digest::collect(
+        db,
+        now - digest::parse_duration(since)?,
+        group_by.unwrap_or(config.group_by),
+    )
+    .context("failed to collect unread items")?;
+    if digest.is_empty() {
+        eprintln!("No unread items in the last {}.", since);
+        return Ok(());
+    }
+
+    let message = RenderedDigest::new(config, &digest)?.message(config)?;
+    if send {
+        let smtp = config
+            .smtp
+            .as_ref()
+            .context("set [digest.smtp] in the config to send digests")?;
+        digest::send(smtp, message).await?;
+        eprintln!(
+            "Sent digest of {} items to {}.",
+            digest.len(),
+            config.to.join(", ")
+        );
+    } else if let Some(output) = output {
+        if output.extension().is_some_and(|ext| ext == "mbox") {
+            digest::append_mbox(output, &message)?;
+        } else {
+            digest::write_eml(output, &message)?;
+        }
+        eprintln!(
+            "Wrote digest of {} items to {}.",
+            digest.len(),
+            output.display()
+        );
+    } else {
+        std::io::stdout().write_all(&message.formatted())?;
+    }
+
+    if mark_read {
+        for &item_id in digest.item_ids() {
+            db.mark_item_read(item_id)?;
+        }
+    }
+    Ok(())
+}
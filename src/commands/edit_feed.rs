@@ -0,0 +1,43 @@
+//! Edit feed command implementation.
+
+use anyhow::{Context, Result};
+
+use crate::db::Db;
+
+/// Edit the properties of a feed, keeping its items. Properties that aren't given are left
+/// unchanged, and an empty title restores the title given by the feed.
+pub fn edit_feed(
+    db: &Db,
+    url: &str,
+    new_url: Option<&str>,
+    title: Option<&str>,
+    paused: Option<bool>,
+    notify: Option<bool>,
+) -> Result<()> {
+    let feeds = db.list_feeds().context("failed to list feeds")?;
+    let Some(feed) = feeds.into_iter().find(|f| f.url == url) else {
+        println!("Feed not found: {}", url);
+        return Ok(());
+    };
+
+    let mut settings = db
+        .get_feed_settings(feed.id)
+        .context("failed to get feed settings")?;
+    if let Some(new_url) = new_url {
+        settings.url = new_url.to_string();
+    }
+    if let Some(title) = title {
+        settings.custom_title = Some(title.to_string()).filter(|t| !t.trim().is_empty());
+    }
+    if let Some(paused) = paused {
+        settings.is_paused = paused;
+    }
+    if let Some(notify) = notify {
+        settings.notify = notify;
+    }
+    db.update_feed(feed.id, &settings)
+        .context("failed to update feed")?;
+
+    println!("Updated feed: {}", settings.url);
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! Item export command implementation.
+
+use anyhow::{Context, Result};
+
+use crate::{
+    client::FeedClient,
+    db::{self, Db, ItemSelection},
+    export::{self, ExportFormat, ExportOptions, Images},
+};
+
+/// Which items to export, as given on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct ExportSelection<'a> {
+    /// Only items of the feeds with these URLs, or of all feeds if empty.
+    pub feeds: &'a [String],
+    /// Only items with this tag.
+    pub tag: Option<&'a str>,
+    /// Only starred items.
+    pub starred: bool,
+    /// Only items published on or after this `YYYY-MM-DD` date.
+    pub since: Option<&'a str>,
+    /// Only items published on or before this `YYYY-MM-DD` date.
+    pub until: Option<&'a str>,
+}
+
+/// Export the selected items as Markdown files, a static HTML site or an EPUB book.
+pub async fn export_items(
+    db: &Db,
+    client: &FeedClient,
+    selection: &ExportSelection<'_>,
+    options: &ExportOptions,
+) -> Result<()> {
+    let feed_ids = if selection.feeds.is_empty() {
+        None
+    } else {
+        let feeds = db.list_feeds()?;
+        let ids = selection
+            .feeds
+            .iter()
+            .map(|url| {
+                feeds
+                    .iter()
+                    .find(|f| &f.url == url)
+                    .map(|f| f.id)
+                    .with_context(|| format!("no feed with URL {}", url))
+            })
+            .collect::<Result<_>>()?;
+        Some(ids)
+    };
+    let selection = ItemSelection {
+        feed_ids,
+        tag: selection.tag.map(|t| t.to_string()),
+        is_starred: selection.starred.then_some(true),
+        since: selection.since.map(db::parse_date).transpose()?,
+        // Include the whole last day
+        until: selection
+            .until
+            .map(|date| db::parse_date(date).map(|ts| ts + 24 * 60 * 60))
+            .transpose()?,
+        ..Default::default()
+    };
+
+    let items = export::collect_items(db, &selection).context("failed to collect items")?;
+    if items.is_empty() {
+        eprintln!("No items to export.");
+        return Ok(());
+    }
+    let images = if options.images && options.format != ExportFormat::Markdown {
+        Images::fetch(client, &items).await
+    } else {
+        Images::default()
+    };
+
+    match options.format {
+        ExportFormat::Markdown => export::write_markdown(&items, &options.output)?,
+        ExportFormat::Html => export::write_html(&items, &images, &options.output, &options.title)?,
+        ExportFormat::Epub => export::write_epub(&items, &images, &options.output, &options.title)?,
+    }
+    eprintln!(
+        "Exported {} item{} to {}.",
+        items.len(),
+        if items.len() == 1 { "" } else { "s" },
+        options.output.display()
+    );
+    if images.failed() > 0 {
+        eprintln!(
+            "{} image{} could not be downloaded and link to the web instead.",
+            images.failed(),
+            if images.failed() == 1 { "" } else { "s" }
+        );
+    }
+    Ok(())
+}
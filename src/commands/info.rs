@@ -0,0 +1,59 @@
+//! Feed info command implementation.
+
+use crate::{clock::format_local, db::Db};
+use anyhow::{Context, Result};
+
+/// Show the metadata of a feed, as of its last sync, and its log of events.
+pub fn feed_info(db: &Db, url: &str) -> Result<()> {
+    let feeds = db.list_feeds().context("failed to list feeds")?;
+    let Some(feed) = feeds.into_iter().find(|f| f.url == url) else {
+        println!("Feed not found: {}", url);
+        return Ok(());
+    };
+    let metadata = db
+        .get_feed_metadata(feed.id)
+        .context("failed to get feed metadata")?;
+
+    println!("{}", feed.title.as_deref().unwrap_or("(no title)"));
+    println!();
+    println!("  URL: {}", feed.url);
+    let fields = [
+        ("Homepage", metadata.site_link),
+        ("Description", metadata.description),
+        ("Language", metadata.language),
+        ("Icon", metadata.icon),
+        ("Logo", metadata.logo),
+        ("Generator", metadata.generator),
+        (
+            "Authors",
+            Some(metadata.authors.join(", ")).filter(|a| !a.is_empty()),
+        ),
+        (
+            "Categories",
+            Some(metadata.categories.join(", ")).filter(|c| !c.is_empty()),
+        ),
+        ("Last updated", metadata.updated.map(format_local)),
+    ];
+    for (label, value) in fields {
+        if let Some(value) = value {
+            println!("  {}: {}", label, value);
+        }
+    }
+    println!("  Unread items: {}", feed.unread_count);
+    if feed.is_gone {
+        println!("  Status: gone (no longer synced)");
+    } else if feed.is_paused {
+        println!("  Status: paused");
+    }
+
+    let log = db.get_feed_log(feed.id).context("failed to get feed log")?;
+    if !log.is_empty() {
+        println!();
+        println!("  Log:");
+        for entry in log {
+            println!("    {}  {}", format_local(entry.created_at), entry.message);
+        }
+    }
+
+    Ok(())
+}
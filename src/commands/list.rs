@@ -1,13 +1,21 @@
-//! List feeds command implementation.
+//! List feeds and items command implementations.
 
 use anyhow::{Context, Result};
 
-use crate::db::Db;
+use crate::{
+    db::{Db, FeedItem, ItemFilter, ItemQuery},
+    output::{self, FeedRecord, ItemRecord, OutputFormat},
+};
 
 /// List all feeds in the database.
-pub fn list_feeds(db: &Db) -> Result<()> {
+pub fn list_feeds(db: &Db, format: OutputFormat) -> Result<()> {
     let feeds = db.list_feeds().context("failed to list feeds")?;
 
+    if format != OutputFormat::Text {
+        let records: Vec<FeedRecord> = feeds.iter().map(FeedRecord::from).collect();
+        return output::print_records(format, &records);
+    }
+
     if feeds.is_empty() {
         println!("No feeds found. Add one with: ferrofeed add-feed <url>");
         return Ok(());
@@ -28,3 +36,84 @@ pub fn list_feeds(db: &Db) -> Result<()> {
 
     Ok(())
 }
+
+/// List the items of a feed, or of all feeds, newest first. The filter is written as in the
+/// TUI, e.g. `unread since:2024-05-01`.
+pub fn list_items(
+    db: &Db,
+    feed_url: Option<&str>,
+    filter: Option<&str>,
+    limit: Option<usize>,
+    format: OutputFormat,
+) -> Result<()> {
+    let query = ItemQuery {
+        filter: filter
+            .map(ItemFilter::parse)
+            .transpose()?
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+    let mut items = match feed_url {
+        Some(url) => {
+            let feed = db
+                .list_feeds()
+                .context("failed to list feeds")?
+                .into_iter()
+                .find(|f| f.url == url)
+                .with_context(|| format!("no feed with URL {}", url))?;
+            db.get_feed_items_with(feed.id, &query)
+        }
+        None => db.get_all_items_with(&query),
+    }
+    .context("failed to list items")?;
+    if let Some(limit) = limit {
+        items.truncate(limit);
+    }
+    print_items(db, &items, "Items", "No items found.", format)
+}
+
+/// Print items as text under a heading, or as records in the other formats.
+pub(super) fn print_items(
+    db: &Db,
+    items: &[FeedItem],
+    heading: &str,
+    empty_message: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let feeds = db.list_feeds().context("failed to list feeds")?;
+    let records: Vec<ItemRecord> = items
+        .iter()
+        .map(|item| ItemRecord::new(item, feeds.iter().find(|f| f.id == item.feed_id)))
+        .collect();
+    if format != OutputFormat::Text {
+        return output::print_records(format, &records);
+    }
+
+    if records.is_empty() {
+        println!("{}", empty_message);
+        return Ok(());
+    }
+
+    println!("{} ({})", heading, records.len());
+    println!();
+
+    for item in records {
+        println!(
+            "  [{}] {}",
+            item.id,
+            item.title.as_deref().unwrap_or("(no title)")
+        );
+        if let Some(feed) = &item.feed {
+            println!("      Feed: {}", feed);
+        }
+        if let Some(link) = &item.link {
+            println!("      Link: {}", link);
+        }
+        if !item.categories.is_empty() {
+            println!("      Categories: {}", item.categories.join(", "));
+        }
+        println!();
+    }
+
+    Ok(())
+}
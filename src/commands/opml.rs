@@ -0,0 +1,29 @@
+//! OPML export and import command implementations.
+
+use anyhow::{Context, Result};
+use std::{fs, path::Path};
+
+use crate::{db::Db, opml};
+
+/// Print feeds as OPML, nested in their folders. With no URLs, every feed is exported.
+pub fn export_opml(db: &Db, urls: &[String]) -> Result<()> {
+    print!("{}", opml::export(db, urls)?);
+    Ok(())
+}
+
+/// Import the feeds and folders of an OPML file.
+pub fn import_opml(db: &Db, path: &Path) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("failed to read OPML file at {}", path.display()))?;
+    let summary = opml::import(db, &content)
+        .with_context(|| format!("failed to import OPML file at {}", path.display()))?;
+
+    println!(
+        "Imported {} new feeds ({} already present)",
+        summary.added, summary.existing
+    );
+    if summary.added > 0 {
+        println!("Run `ferrofeed sync` to fetch their items.");
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Publish command implementation.
+
+use anyhow::{Context, Result};
+use std::{fs, io::Write as _, path::Path};
+
+use crate::{
+    config::PublishConfig,
+    db::Db,
+    publish::{self, PublishFormat},
+    rules::RuleSet,
+};
+
+/// Render the items matching the filters of the config as a feed, written to `output` or else
+/// printed.
+pub fn publish(
+    db: &Db,
+    config: &PublishConfig,
+    rules: &RuleSet,
+    format: PublishFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let entries = publish::collect(db, config, rules).context("failed to collect items")?;
+    let document = publish::render(format, config, &entries);
+    match output {
+        Some(output) => {
+            fs::write(output, document)
+                .with_context(|| format!("failed to write {}", output.display()))?;
+            eprintln!(
+                "Published {} item{} to {}.",
+                entries.len(),
+                if entries.len() == 1 { "" } else { "s" },
+                output.display()
+            );
+        }
+        None => std::io::stdout().write_all(document.as_bytes())?,
+    }
+    Ok(())
+}
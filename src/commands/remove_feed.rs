@@ -2,10 +2,20 @@
 
 use anyhow::{Context, Result};
 
-use crate::{commands::sync_feeds, db::Db};
+use crate::{
+    client::FeedClient, commands::sync_feeds, config::SyncConfig, db::Db, hooks::HookSet,
+    output::OutputFormat, rules::RuleSet,
+};
 
 /// Remove a feed from the database.
-pub async fn remove_feed(db: &Db, url: &str) -> Result<()> {
+pub async fn remove_feed(
+    db: &Db,
+    url: &str,
+    client: &FeedClient,
+    config: &SyncConfig,
+    rules: &RuleSet,
+    hooks: &HookSet,
+) -> Result<()> {
     let deleted = db
         .remove_feed(url)
         .context("failed to remove feed from database")?;
@@ -17,7 +27,7 @@ pub async fn remove_feed(db: &Db, url: &str) -> Result<()> {
     }
 
     // Re-sync
-    sync_feeds(db).await?;
+    sync_feeds(db, client, config, rules, hooks, OutputFormat::Text).await?;
 
     Ok(())
 }
@@ -0,0 +1,43 @@
+//! Rules command implementation.
+
+use anyhow::{Context, Result};
+
+use crate::{db::Db, rules::RuleSet};
+
+/// Apply the rules retroactively to every item in the database.
+pub fn apply_rules(db: &Db, rules: &RuleSet) -> Result<()> {
+    if rules.is_empty() {
+        println!("No rules to apply. Add some to your rules file first.");
+        return Ok(());
+    }
+
+    let feeds = db.list_feeds().context("failed to list feeds")?;
+    let mut matched_items = 0;
+    let mut deleted_items = 0;
+
+    for feed in feeds {
+        let items = db
+            .get_feed_items(feed.id)
+            .with_context(|| format!("failed to get items for {}", feed.url))?;
+        for item in items {
+            let outcome = rules
+                .apply(db, &feed, &item)
+                .with_context(|| format!("failed to apply rules to item {}", item.id))?;
+            if outcome.matched > 0 {
+                matched_items += 1;
+            }
+            if outcome.deleted {
+                deleted_items += 1;
+            }
+        }
+    }
+
+    println!(
+        "Applied {} rules: {} items matched, {} deleted.",
+        rules.rules.len(),
+        matched_items,
+        deleted_items
+    );
+
+    Ok(())
+}
@@ -0,0 +1,33 @@
+//! Search command implementation.
+
+use anyhow::{Context, Result};
+
+use super::list::print_items;
+use crate::{
+    db::{Db, ItemFilter, ItemQuery},
+    output::OutputFormat,
+};
+
+/// Search the items of all feeds for text in their title, authors or description, optionally
+/// only those in a category.
+pub fn search(
+    db: &Db,
+    text: Option<&str>,
+    category: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let query = ItemQuery {
+        filter: ItemFilter {
+            category: category.map(|c| c.to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let items = match text {
+        Some(text) => db.search_items(text, &query),
+        None => db.get_all_items_with(&query),
+    }
+    .context("failed to search items")?;
+
+    print_items(db, &items, "Results", "No matching items found.", format)
+}
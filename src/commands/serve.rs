@@ -0,0 +1,63 @@
+//! Serve command implementation.
+
+use anyhow::{Context, Result};
+use axum::Router;
+
+use crate::{
+    config::Config,
+    db::Db,
+    publish,
+    rules::RuleSet,
+    server::{self, Api, Credentials},
+};
+
+/// Serve a sync API for the database until interrupted, and the published feed if enabled.
+/// The API and address given override the ones in the config.
+pub async fn serve(db: Db, config: &Config, api: Option<Api>, address: Option<&str>) -> Result<()> {
+    let credentials = match (&config.serve.username, &config.serve.password) {
+        (Some(username), Some(password)) => Some(Credentials {
+            username: username.clone(),
+            password: password
+                .resolve()
+                .context("failed to resolve the serve password")?,
+        }),
+        _ if config.publish.serve => None,
+        _ => anyhow::bail!(
+            "set a username and password under [serve] in the config to serve the API"
+        ),
+    };
+    let api = api.unwrap_or(config.serve.api);
+    let address = address.unwrap_or(&config.serve.address);
+
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .with_context(|| format!("failed to listen on {}", address))?;
+    let local_addr = listener.local_addr()?;
+    let mut app = Router::new();
+    if let Some(credentials) = credentials {
+        match api {
+            Api::Fever => println!("Serving the Fever API at http://{}/fever/", local_addr),
+            Api::Greader => println!("Serving the Google Reader API at http://{}", local_addr),
+        }
+        app = app.merge(server::router(db, api, credentials));
+    }
+    if config.publish.serve {
+        // The published feed reads the database over its own connection
+        let path = config
+            .database_path
+            .as_deref()
+            .and_then(|path| path.to_str())
+            .context("no database path specified")?;
+        let rules = RuleSet::from_config(&config.rules)?;
+        println!(
+            "Serving the published feed at http://{0}/publish/atom.xml and http://{0}/publish/rss.xml",
+            local_addr
+        );
+        app = app.merge(publish::router(
+            Db::open(path)?,
+            config.publish.clone(),
+            rules,
+        ));
+    }
+    axum::serve(listener, app).await.context("server failed")
+}
@@ -1,65 +1,291 @@
 //! Sync feeds command implementation.
 
 use anyhow::{Context, Result};
+use std::io::Write as _;
 
-use crate::{client, db::Db};
+use crate::{
+    client::{FeedClient, FeedGone, Redirect},
+    config::SyncConfig,
+    db::{Db, Feed, ItemChange},
+    hooks::HookSet,
+    notifications,
+    output::{self, FeedSyncRecord, OutputFormat, SyncStatus},
+    remote::{self, SyncSummary},
+    rules::RuleSet,
+};
+
+/// Sync all feeds - fetch new items for all feeds in the database, applying the rules to each
+/// new item and then firing the hooks and showing notifications for the new items. Paused and
+/// gone feeds are skipped, and feeds that permanently moved take on their new URL unless
+/// disabled in the config. With a remote server in the config, everything is synced with the
+/// server instead.
+///
+/// Progress is printed as text, or the result of each feed is printed as a record in the other
+/// formats. Returns the number of feeds that failed to sync.
+pub async fn sync_feeds(
+    db: &Db,
+    client: &FeedClient,
+    config: &SyncConfig,
+    rules: &RuleSet,
+    hooks: &HookSet,
+    format: OutputFormat,
+) -> Result<usize> {
+    let text = format == OutputFormat::Text;
+    if let Some(remote) = &config.remote {
+        if text {
+            println!("Syncing with {}...", remote.url);
+        }
+        let summary = remote::sync(db, client, remote, rules)
+            .await
+            .context("failed to sync with remote server")?;
+        if text {
+            print_remote_summary(&summary);
+        } else {
+            output::print_records(format, std::slice::from_ref(&summary))?;
+        }
+        announce_new_items(db, client, config, hooks, &summary.new_items, text).await?;
+        return Ok(summary.failed.len());
+    }
 
-/// Sync all feeds - fetch new items for all feeds in the database.
-pub async fn sync_feeds(db: &Db) -> Result<()> {
     let feeds = db.list_feeds().context("failed to list feeds")?;
 
-    if feeds.is_empty() {
+    if feeds.is_empty() && text {
         println!("No feeds to sync. Add one with: ferrofeed add-feed <url>");
-        return Ok(());
+        return Ok(0);
     }
 
-    println!("Syncing {} feeds...", feeds.len());
-    println!();
+    if text {
+        println!("Syncing {} feeds...", feeds.len());
+        println!();
+    }
 
-    let mut total_new_items = 0;
+    let mut new_item_ids = Vec::new();
+    let mut records = Vec::new();
 
     for feed in feeds {
-        print!("{} ... ", feed.title.as_deref().unwrap_or(&feed.url));
-
-        match client::fetch_feed(&feed.url).await {
-            Ok(parsed_feed) => {
-                let mut new_items = 0;
-
-                for item in parsed_feed.items {
-                    // Convert Vec<String> to Vec<&str> for add_feed_item
-                    let authors_refs: Vec<&str> = item.authors.iter().map(|s| s.as_str()).collect();
-
-                    // add_feed_item returns true if inserted, false if duplicate
-                    match db.add_feed_item(
-                        feed.id,
-                        item.title.as_deref(),
-                        item.link.as_deref(),
-                        item.description.as_deref(),
-                        Some(&authors_refs),
-                        item.published,
-                    ) {
-                        Ok(true) => new_items += 1,
-                        Ok(false) => {
-                            // Duplicate, skip silently
-                        }
-                        Err(e) => {
-                            // Log error but continue with other items
-                            eprintln!("Warning: failed to add item: {}", e);
-                        }
-                    }
-                }
-
-                total_new_items += new_items;
-                println!("({} new items)", new_items);
+        if text {
+            print!("{} ... ", feed.title.as_deref().unwrap_or(&feed.url));
+            // Show which feed is being fetched
+            std::io::stdout().flush()?;
+        }
+        let record = sync_feed(db, client, config, rules, &feed, &mut new_item_ids).await?;
+        if text {
+            println!("{}", describe(&record));
+        }
+        records.push(record);
+    }
+
+    let failed = records
+        .iter()
+        .filter(|r| r.status == SyncStatus::Failed)
+        .count();
+    if text {
+        println!();
+        println!(
+            "Sync complete. {} new items added, {} updated.",
+            new_item_ids.len(),
+            records.iter().map(|r| r.updated_items).sum::<usize>()
+        );
+    } else {
+        output::print_records(format, &records)?;
+    }
+
+    announce_new_items(db, client, config, hooks, &new_item_ids, text).await?;
+    Ok(failed)
+}
+
+/// Fetch the new items of a feed, adding the IDs of those the rules kept to `new_item_ids`.
+async fn sync_feed(
+    db: &Db,
+    client: &FeedClient,
+    config: &SyncConfig,
+    rules: &RuleSet,
+    feed: &Feed,
+    new_item_ids: &mut Vec<usize>,
+) -> Result<FeedSyncRecord> {
+    let mut record = FeedSyncRecord {
+        feed_id: feed.id,
+        url: feed.url.clone(),
+        title: feed.title.clone(),
+        status: SyncStatus::Ok,
+        new_items: 0,
+        updated_items: 0,
+        moved_to: None,
+        error: None,
+    };
+    if feed.is_paused {
+        record.status = SyncStatus::Paused;
+        return Ok(record);
+    }
+    if feed.is_gone {
+        record.status = SyncStatus::Gone;
+        return Ok(record);
+    }
+
+    let parsed_feed = match client.fetch_feed(&feed.url).await {
+        Ok(parsed_feed) => parsed_feed,
+        Err(e) if e.downcast_ref::<FeedGone>().is_some() => {
+            record.status = SyncStatus::Gone;
+            record.error = Some(e.to_string());
+            if let Err(e) = db
+                .set_feed_gone(feed.id, true)
+                .and_then(|_| db.add_feed_log(feed.id, "Feed is gone (410 Gone)"))
+            {
+                eprintln!("Warning: failed to mark feed as gone: {}", e);
+            }
+            return Ok(record);
+        }
+        Err(e) => {
+            record.status = SyncStatus::Failed;
+            record.error = Some(e.to_string());
+            return Ok(record);
+        }
+    };
+
+    if let Some(redirect) = &parsed_feed.redirect
+        && handle_redirect(db, config, feed, redirect)
+    {
+        record.moved_to = Some(redirect.url.clone());
+    }
+
+    if let Err(e) =
+        db.update_feed_metadata(feed.id, parsed_feed.title.as_deref(), &parsed_feed.metadata)
+    {
+        eprintln!("Warning: failed to update feed metadata: {}", e);
+    }
+
+    for mut item in parsed_feed.items {
+        if config.resolve_canonical_urls
+            && item.canonical_link.is_none()
+            && let Some(link) = &item.link
+            && !db.has_feed_item_link(feed.id, link)?
+        {
+            // Best effort; the item link is normalized on its own otherwise
+            item.canonical_link = client.fetch_canonical_url(link).await.unwrap_or(None);
+        }
+
+        let item_id = match db.add_feed_item(feed.id, &item) {
+            Ok(ItemChange::New(item_id)) => item_id,
+            Ok(ItemChange::Updated(_)) => {
+                record.updated_items += 1;
+                continue;
+            }
+            Ok(ItemChange::Unchanged) => {
+                // Already stored, skip silently
+                continue;
             }
             Err(e) => {
-                println!("failed: {}", e);
+                // Log error but continue with other items
+                eprintln!("Warning: failed to add item: {}", e);
+                continue;
             }
+        };
+
+        // Items deleted by a rule don't count as new
+        match rules.apply_by_id(db, feed, item_id) {
+            Ok(outcome) if outcome.deleted => continue,
+            Ok(_) => {}
+            Err(e) => eprintln!("Warning: failed to apply rules to item: {}", e),
         }
+        new_item_ids.push(item_id);
+        record.new_items += 1;
     }
+    Ok(record)
+}
 
-    println!();
-    println!("Sync complete. {} new items added.", total_new_items);
+/// The text describing how syncing a feed went.
+fn describe(record: &FeedSyncRecord) -> String {
+    let moved = match &record.moved_to {
+        Some(url) => format!("moved to {} ... ", url),
+        None => String::new(),
+    };
+    match (record.status, &record.error) {
+        (SyncStatus::Ok, _) => format!(
+            "{}({} new items, {} updated)",
+            moved, record.new_items, record.updated_items
+        ),
+        (SyncStatus::Paused, _) => "paused".to_string(),
+        (SyncStatus::Gone, Some(error)) => format!("gone: {}", error),
+        (SyncStatus::Gone, None) => "gone (edit the feed URL to sync it again)".to_string(),
+        (SyncStatus::Failed, error) => {
+            format!("failed: {}", error.as_deref().unwrap_or_default())
+        }
+    }
+}
 
+/// Fire the hooks and show notifications for the new items of a sync. Failing to show
+/// notifications is only a warning, since there may be no desktop session.
+async fn announce_new_items(
+    db: &Db,
+    client: &FeedClient,
+    config: &SyncConfig,
+    hooks: &HookSet,
+    item_ids: &[usize],
+    text: bool,
+) -> Result<()> {
+    let outcome = hooks
+        .fire(db, client, item_ids)
+        .await
+        .context("failed to fire hooks")?;
+    if outcome.delivered + outcome.failed > 0 {
+        let message = format!(
+            "Hooks: {} delivered, {} failed.",
+            outcome.delivered, outcome.failed
+        );
+        // Keep stdout to the records otherwise
+        if text {
+            println!("{}", message);
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+    if let Err(e) = notifications::notify_new_items(db, &config.notifications, item_ids).await {
+        eprintln!("Warning: failed to show notifications: {:#}", e);
+    }
     Ok(())
 }
+
+/// Print what a sync with the remote server did.
+fn print_remote_summary(summary: &SyncSummary) {
+    for failure in &summary.failed {
+        eprintln!("Warning: failed to subscribe on server: {}", failure);
+    }
+    println!(
+        "Subscriptions: {} sent to server, {} removed from server, {} added, {} removed.",
+        summary.subscribed, summary.unsubscribed, summary.feeds_added, summary.feeds_removed
+    );
+    println!(
+        "Sync complete. {} new items added, {} items sent to server, {} updated from server.",
+        summary.new_items.len(),
+        summary.pushed,
+        summary.pulled
+    );
+}
+
+/// Update the URL of a feed that permanently moved, recording the move in the feed log.
+/// Temporary redirects are left alone. Returns whether the URL was updated.
+fn handle_redirect(db: &Db, config: &SyncConfig, feed: &Feed, redirect: &Redirect) -> bool {
+    if !redirect.permanent || config.ignore_permanent_redirects {
+        return false;
+    }
+    match db.set_feed_url(feed.id, &redirect.url) {
+        Ok(true) => {
+            let message = format!("Moved from {} to {}", feed.url, redirect.url);
+            if let Err(e) = db.add_feed_log(feed.id, &message) {
+                eprintln!("Warning: failed to log feed move: {}", e);
+            }
+            true
+        }
+        Ok(false) => {
+            eprintln!(
+                "Warning: feed moved to {}, which is already another feed",
+                redirect.url
+            );
+            false
+        }
+        Err(e) => {
+            eprintln!("Warning: failed to update feed URL: {}", e);
+            false
+        }
+    }
+}
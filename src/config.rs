@@ -3,6 +3,12 @@ use directories::BaseDirs;
 use serde::{Deserialize, Serialize};
 use std::{fs, path::PathBuf};
 
+use crate::{
+    auth::Secret,
+    digest::{DigestGroup, SmtpSecurity},
+    server::Api,
+};
+
 /// The default app config TOML file.
 const APP_CONFIG_FILE: &str = "ferrofeed.toml";
 
@@ -15,6 +21,36 @@ pub struct Config {
     pub database_path: Option<PathBuf>,
     /// Sync section of the config file.
     pub sync: SyncConfig,
+    /// Images section of the config file.
+    #[serde(default)]
+    pub images: ImagesConfig,
+    /// Reader section of the config file.
+    #[serde(default)]
+    pub reader: ReaderConfig,
+    /// UI section of the config file.
+    #[serde(default)]
+    pub ui: UiConfig,
+    /// Rules section of the config file.
+    #[serde(default)]
+    pub rules: RulesConfig,
+    /// HTTP section of the config file.
+    #[serde(default)]
+    pub http: HttpConfig,
+    /// Auth section of the config file.
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Serve section of the config file.
+    #[serde(default)]
+    pub serve: ServeConfig,
+    /// Hooks section of the config file.
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Digest section of the config file.
+    #[serde(default)]
+    pub digest: DigestConfig,
+    /// Publish section of the config file.
+    #[serde(default)]
+    pub publish: PublishConfig,
 }
 
 /// Sync section of the config file.
@@ -24,6 +60,314 @@ pub struct SyncConfig {
     pub allow_background_sync: bool,
     /// Schedule for when to sync feeds in database.
     pub schedule_mins: usize,
+    /// Whether to fetch the page of each new item to find the canonical URL it declares, for
+    /// recognizing the same article across feeds. Slows down syncing.
+    #[serde(default)]
+    pub resolve_canonical_urls: bool,
+    /// Whether to keep the stored URL of feeds that permanently redirect elsewhere, instead of
+    /// updating it to where they moved.
+    #[serde(default)]
+    pub ignore_permanent_redirects: bool,
+    /// Miniflux server to sync subscriptions, items and read state with, instead of fetching
+    /// feeds directly.
+    #[serde(default)]
+    pub remote: Option<RemoteConfig>,
+    /// Desktop notifications for new items after each sync.
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+}
+
+/// Notifications of the sync section, summarizing the new items of the feeds that opted in
+/// with `ferrofeed edit-feed --notify true`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// Whether to show notifications.
+    pub enabled: bool,
+    /// Local times between which no notifications are shown, as `HH:MM-HH:MM`, e.g.
+    /// `22:00-07:00`.
+    pub quiet_hours: Option<String>,
+    /// Maximum number of item titles listed in the notification of a feed.
+    pub max_titles: usize,
+}
+
+/// Remote server of the sync section, for using a Miniflux server as the backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteConfig {
+    /// Base URL of the server, e.g. `https://miniflux.example.com`.
+    pub url: String,
+    /// API token, created in the settings of the server. Used instead of the username and
+    /// password when set.
+    pub token: Option<Secret>,
+    /// Username to log in with, without a token.
+    pub username: Option<String>,
+    /// Password to log in with, without a token.
+    pub password: Option<Secret>,
+}
+
+/// Images section of the config file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImagesConfig {
+    /// Whether to download and render images inline in the post view.
+    pub enabled: bool,
+    /// Directory to cache downloaded images in.
+    pub cache_dir: Option<PathBuf>,
+    /// Maximum number of terminal rows an inline image may take up.
+    pub max_height: u16,
+}
+
+/// Reader section of the config file, for the layout of the post view.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReaderConfig {
+    /// Maximum width of the post text column. The column is centered when the terminal is
+    /// wider than this.
+    pub max_width: Option<u16>,
+    /// Number of blank lines between lines of post text.
+    pub line_spacing: u16,
+    /// Whether to open posts in distraction-free mode, which hides the metadata header.
+    pub distraction_free: bool,
+}
+
+/// UI section of the config file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Page layout of the TUI.
+    pub layout: LayoutMode,
+}
+
+/// Rules section of the config file, for acting on items automatically.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RulesConfig {
+    /// Path to the TOML rules file. Rules are disabled when the file doesn't exist.
+    pub path: Option<PathBuf>,
+}
+
+/// HTTP section of the config file, for the client used for every fetch.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// `User-Agent` header sent with every request.
+    pub user_agent: String,
+    /// Seconds to wait for a connection to be established.
+    pub connect_timeout_secs: u64,
+    /// Seconds to wait for each read of a response.
+    pub read_timeout_secs: u64,
+    /// HTTP, HTTPS or SOCKS5 proxy URL for all requests, e.g. `socks5://localhost:1080`. When
+    /// unset, the `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` environment variables
+    /// are used.
+    pub proxy: Option<String>,
+    /// Comma-separated hosts to connect to without the configured proxy.
+    pub no_proxy: Option<String>,
+    /// PEM file of extra CA certificates to trust, e.g. for a corporate proxy.
+    pub ca_bundle: Option<PathBuf>,
+    /// Largest response body to accept, in bytes.
+    pub max_response_bytes: u64,
+    /// Whether to ask for gzip and brotli compressed responses.
+    pub compression: bool,
+}
+
+/// Auth section of the config file, for credentials of private feeds.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Path to the TOML auth file. No credentials are sent when the file doesn't exist.
+    pub path: Option<PathBuf>,
+}
+
+/// Hooks section of the config file, for notifying other programs of new items.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Path to the TOML hooks file. Hooks are disabled when the file doesn't exist.
+    pub path: Option<PathBuf>,
+    /// Path to the JSON Lines file logging deliveries that failed after every retry.
+    pub dead_letter_path: Option<PathBuf>,
+}
+
+/// Digest section of the config file, for the email digests of `ferrofeed digest`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// Sender of the email, e.g. `ferrofeed <ferrofeed@example.com>`.
+    pub from: String,
+    /// Recipients of the email.
+    pub to: Vec<String>,
+    /// Subject of the email, a template given the same values as the body templates.
+    pub subject: String,
+    /// Whether to group items by feed or by tag.
+    pub group_by: DigestGroup,
+    /// Path to a template replacing the built-in HTML body.
+    pub html_template: Option<PathBuf>,
+    /// Path to a template replacing the built-in plain text body.
+    pub text_template: Option<PathBuf>,
+    /// SMTP server to send digests with.
+    pub smtp: Option<SmtpConfig>,
+}
+
+/// Publish section of the config file, for the feed generated by `ferrofeed publish`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PublishConfig {
+    /// Title of the feed.
+    pub title: String,
+    /// Description of the feed.
+    pub description: Option<String>,
+    /// URL of the site the feed is published for, e.g. the team's planet page. Used as the ID
+    /// and link of the feed.
+    pub url: Option<String>,
+    /// Author of the feed, credited for entries without authors.
+    pub author: Option<String>,
+    /// Only publish items with this tag.
+    pub tag: Option<String>,
+    /// Only publish starred items.
+    pub starred: bool,
+    /// Only publish items matching the conditions of the rule with this name.
+    pub rule: Option<String>,
+    /// Maximum number of entries, newest first.
+    pub limit: usize,
+    /// Whether `ferrofeed serve` also serves the feed, at `/publish/atom.xml` and
+    /// `/publish/rss.xml`, without login.
+    pub serve: bool,
+}
+
+/// SMTP server of the digest section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    /// Host name of the server.
+    pub host: String,
+    /// Port of the server, if not the default port of the security mode.
+    pub port: Option<u16>,
+    /// How the connection is secured.
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    /// Username to log in with, if the server needs one.
+    pub username: Option<String>,
+    /// Password to log in with.
+    pub password: Option<Secret>,
+}
+
+/// Serve section of the config file, for the API served by `ferrofeed serve`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServeConfig {
+    /// The sync API to serve.
+    pub api: Api,
+    /// Address to listen on.
+    pub address: String,
+    /// Username (the email, for the Fever API) that API clients log in with.
+    pub username: Option<String>,
+    /// Password that API clients log in with.
+    pub password: Option<Secret>,
+}
+
+/// Page layout of the TUI.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutMode {
+    /// One page at a time: feeds, then items, then a post.
+    #[default]
+    Stack,
+    /// The focused page next to its items or post preview.
+    TwoPane,
+    /// Feeds, items and a post preview side by side.
+    ThreePane,
+}
+
+impl LayoutMode {
+    /// The next layout, cycling back to [`LayoutMode::Stack`] after the last.
+    pub fn next(self) -> Self {
+        match self {
+            LayoutMode::Stack => LayoutMode::TwoPane,
+            LayoutMode::TwoPane => LayoutMode::ThreePane,
+            LayoutMode::ThreePane => LayoutMode::Stack,
+        }
+    }
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        let base_dirs = BaseDirs::new().expect("couldn't get base directories, HOME not set?");
+        Self {
+            enabled: false,
+            cache_dir: Some(base_dirs.home_dir().join(".cache/ferrofeed/images")),
+            max_height: 15,
+        }
+    }
+}
+
+impl Default for RulesConfig {
+    fn default() -> Self {
+        let base_dirs = BaseDirs::new().expect("couldn't get base directories, HOME not set?");
+        Self {
+            path: Some(base_dirs.home_dir().join(".config/ferrofeed/rules.toml")),
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        let base_dirs = BaseDirs::new().expect("couldn't get base directories, HOME not set?");
+        Self {
+            path: Some(base_dirs.home_dir().join(".config/ferrofeed/auth.toml")),
+        }
+    }
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        let base_dirs = BaseDirs::new().expect("couldn't get base directories, HOME not set?");
+        let home_dir = base_dirs.home_dir();
+        Self {
+            path: Some(home_dir.join(".config/ferrofeed/hooks.toml")),
+            dead_letter_path: Some(home_dir.join(".local/share/ferrofeed/hooks-failed.jsonl")),
+        }
+    }
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            from: "ferrofeed <ferrofeed@localhost>".to_string(),
+            to: Vec::new(),
+            subject: "ferrofeed digest: {{ count }} unread item{% if count != 1 %}s{% endif %}"
+                .to_string(),
+            group_by: DigestGroup::default(),
+            html_template: None,
+            text_template: None,
+            smtp: None,
+        }
+    }
+}
+
+impl Default for PublishConfig {
+    fn default() -> Self {
+        Self {
+            title: "ferrofeed".to_string(),
+            description: None,
+            url: None,
+            author: None,
+            tag: None,
+            starred: false,
+            rule: None,
+            limit: 50,
+            serve: false,
+        }
+    }
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self {
+            api: Api::default(),
+            address: "127.0.0.1:8642".to_string(),
+            username: None,
+            password: None,
+        }
+    }
 }
 
 impl Default for SyncConfig {
@@ -31,6 +375,40 @@ impl Default for SyncConfig {
         Self {
             allow_background_sync: false,
             schedule_mins: 60,
+            resolve_canonical_urls: false,
+            ignore_permanent_redirects: false,
+            remote: None,
+            notifications: NotificationsConfig::default(),
+        }
+    }
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            quiet_hours: None,
+            max_titles: 5,
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: concat!(
+                "ferrofeed/",
+                env!("CARGO_PKG_VERSION"),
+                " (+https://github.com/micahkepe/ferrofeed)"
+            )
+            .to_string(),
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            proxy: None,
+            no_proxy: None,
+            ca_bundle: None,
+            max_response_bytes: 20 * 1024 * 1024,
+            compression: true,
         }
     }
 }
@@ -42,6 +420,16 @@ impl Default for Config {
         Self {
             database_path: Some(data_dir.join(DEFAULT_DB_NAME)),
             sync: SyncConfig::default(),
+            images: ImagesConfig::default(),
+            reader: ReaderConfig::default(),
+            ui: UiConfig::default(),
+            rules: RulesConfig::default(),
+            http: HttpConfig::default(),
+            auth: AuthConfig::default(),
+            serve: ServeConfig::default(),
+            hooks: HooksConfig::default(),
+            digest: DigestConfig::default(),
+            publish: PublishConfig::default(),
         }
     }
 }
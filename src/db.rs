@@ -4,10 +4,55 @@ Database management and CRUD operations.
 * Uses `rusqlite` for SQLite-bindings.
 
 */
-use anyhow::Result;
-use rusqlite::{Connection, params};
-use std::fs;
-use time::OffsetDateTime;
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension, Row, params, params_from_iter, types::Value};
+use std::{fmt, fs};
+use time::{Date, Month, OffsetDateTime};
+
+use crate::{
+    client::{FeedMetadata, ParsedFeedItem},
+    clock::format_date,
+    dedup::{canonical_url, content_hash},
+};
+
+/// Definition of the `feed_item` table, with `{table}` standing in for the table name so that
+/// the table can be rebuilt under a temporary name.
+const FEED_ITEM_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS {table} (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        feed_id INTEGER NOT NULL,
+        title TEXT,
+        link TEXT,
+        description TEXT,
+        authors TEXT,
+        published INTEGER,
+        is_read INTEGER NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL,
+        scroll_position INTEGER NOT NULL DEFAULT 0,
+        is_starred INTEGER NOT NULL DEFAULT 0,
+        is_hidden INTEGER NOT NULL DEFAULT 0,
+        guid TEXT,
+        canonical_url TEXT,
+        content_hash TEXT,
+        duplicate_of INTEGER REFERENCES feed_item(id) ON DELETE SET NULL,
+        updated INTEGER,
+        is_updated INTEGER NOT NULL DEFAULT 0,
+        remote_id INTEGER,
+        remote_is_read INTEGER,
+        remote_is_starred INTEGER,
+        item_key TEXT NOT NULL,
+        FOREIGN KEY (feed_id) REFERENCES feed(id) ON DELETE CASCADE,
+        UNIQUE(feed_id, item_key)
+    );
+"#;
+
+/// Columns selected for a [`FeedItem`], in the order expected by [`row_to_feed_item`].
+const FEED_ITEM_COLUMNS: &str = "id, feed_id, title, link, description, authors, published, \
+    is_read, created_at, scroll_position, is_starred, is_hidden, \
+    (SELECT group_concat(tag, char(31)) FROM item_tag WHERE item_id = feed_item.id), \
+    duplicate_of, is_updated, \
+    (SELECT group_concat(category, char(31)) FROM \
+        (SELECT category FROM item_category WHERE item_id = feed_item.id ORDER BY rowid))";
 
 /// Represents a feed entry in the `feed` table.
 #[derive(Debug, Clone)]
@@ -16,10 +61,57 @@ pub struct Feed {
     pub id: usize,
     /// The URL of the feed.
     pub url: String,
-    /// Optional title for the feed.
+    /// Optional title for the feed: the custom title given by the user if any, or else the
+    /// title given by the feed.
     pub title: Option<String>,
     /// Creation time (Unix timestamp)
     pub created_at: i64,
+    /// Number of unread items in the feed.
+    pub unread_count: usize,
+    /// Publish date of the newest item in the feed (Unix timestamp).
+    pub last_updated: Option<i64>,
+    /// The folder the feed is in, if any.
+    pub folder_id: Option<usize>,
+    /// Whether syncing the feed is paused.
+    pub is_paused: bool,
+    /// Whether the feed is permanently gone (`410 Gone`). Gone feeds aren't synced until their
+    /// URL is changed.
+    pub is_gone: bool,
+}
+
+/// An entry in the log of events of a feed, such as it moving to a new URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedLogEntry {
+    /// When the event happened (Unix timestamp).
+    pub created_at: i64,
+    /// Description of the event.
+    pub message: String,
+}
+
+/// The properties of a feed that the user can edit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedSettings {
+    /// The URL of the feed.
+    pub url: String,
+    /// Display title replacing the title given by the feed.
+    pub custom_title: Option<String>,
+    /// Whether syncing the feed is paused.
+    pub is_paused: bool,
+    /// Whether to show desktop notifications for new items of the feed.
+    pub notify: bool,
+}
+
+/// Represents a folder of feeds in the `folder` table. Folders can be nested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Folder {
+    /// Unique identifier primary key.
+    pub id: usize,
+    /// The name of the folder.
+    pub name: String,
+    /// The folder this folder is in, if any.
+    pub parent_id: Option<usize>,
+    /// Whether the folder is collapsed in the feeds list.
+    pub is_collapsed: bool,
 }
 
 /// Represents a feed item (post/article) in the `feed_item` table.
@@ -43,6 +135,402 @@ pub struct FeedItem {
     pub is_read: bool,
     /// When this item was added to the database (Unix timestamp).
     pub created_at: i64,
+    /// Last scroll position in the post view, as a fraction of the post in ten-thousandths,
+    /// to resume reading at whatever width the post is wrapped to next.
+    pub scroll_position: u16,
+    /// Whether the item has been starred.
+    pub is_starred: bool,
+    /// Whether the item is hidden from item lists.
+    pub is_hidden: bool,
+    /// Tags added to the item.
+    pub tags: Vec<String>,
+    /// ID of the first item seen of the same article in another feed, if this item is a
+    /// duplicate of it.
+    pub duplicate_of: Option<usize>,
+    /// Whether the entry changed in the feed since the item was last read.
+    pub is_updated: bool,
+    /// Categories (tags, keywords) given to the item by its feed.
+    pub categories: Vec<String>,
+}
+
+/// What [`Db::add_feed_item`] did with an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemChange {
+    /// The item is new and was added with this ID.
+    New(usize),
+    /// The item was already stored with this ID and its entry changed, so it was updated.
+    Updated(usize),
+    /// The item was already stored and hasn't changed.
+    Unchanged,
+}
+
+impl ItemChange {
+    /// The ID of the item, if it was new.
+    pub fn new_id(self) -> Option<usize> {
+        match self {
+            ItemChange::New(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
+/// Sort order for feeds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeedSort {
+    /// Alphabetically by title.
+    #[default]
+    Title,
+    /// Most unread items first.
+    UnreadCount,
+    /// Most recently updated first.
+    LastUpdated,
+}
+
+impl FeedSort {
+    /// The next sort order, cycling back to the first after the last.
+    pub fn next(self) -> Self {
+        match self {
+            FeedSort::Title => FeedSort::UnreadCount,
+            FeedSort::UnreadCount => FeedSort::LastUpdated,
+            FeedSort::LastUpdated => FeedSort::Title,
+        }
+    }
+
+    /// Short human-readable label.
+    pub fn label(self) -> &'static str {
+        match self {
+            FeedSort::Title => "title",
+            FeedSort::UnreadCount => "unread count",
+            FeedSort::LastUpdated => "last updated",
+        }
+    }
+
+    /// SQL `ORDER BY` clause, in terms of the columns selected by [`Db::list_feeds_with`].
+    fn order_by(self) -> &'static str {
+        match self {
+            FeedSort::Title => "title IS NULL, title COLLATE NOCASE, url",
+            FeedSort::UnreadCount => "unread_count DESC, title COLLATE NOCASE",
+            FeedSort::LastUpdated => {
+                "last_updated IS NULL, last_updated DESC, title COLLATE NOCASE"
+            }
+        }
+    }
+}
+
+/// Options for listing feeds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedQuery {
+    /// Sort order.
+    pub sort: FeedSort,
+    /// Only include feeds with unread items.
+    pub unread_only: bool,
+}
+
+/// Sort order for feed items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ItemSort {
+    /// Newest first.
+    #[default]
+    DateDesc,
+    /// Oldest first.
+    DateAsc,
+    /// Alphabetically by title.
+    Title,
+    /// Alphabetically by author.
+    Author,
+    /// Unread items first, then newest first.
+    UnreadFirst,
+}
+
+impl ItemSort {
+    /// The next sort order, cycling back to the first after the last.
+    pub fn next(self) -> Self {
+        match self {
+            ItemSort::DateDesc => ItemSort::DateAsc,
+            ItemSort::DateAsc => ItemSort::Title,
+            ItemSort::Title => ItemSort::Author,
+            ItemSort::Author => ItemSort::UnreadFirst,
+            ItemSort::UnreadFirst => ItemSort::DateDesc,
+        }
+    }
+
+    /// Short human-readable label.
+    pub fn label(self) -> &'static str {
+        match self {
+            ItemSort::DateDesc => "newest",
+            ItemSort::DateAsc => "oldest",
+            ItemSort::Title => "title",
+            ItemSort::Author => "author",
+            ItemSort::UnreadFirst => "unread first",
+        }
+    }
+
+    /// SQL `ORDER BY` clause for the `feed_item` table.
+    fn order_by(self) -> &'static str {
+        match self {
+            ItemSort::DateDesc => "published DESC, id DESC",
+            ItemSort::DateAsc => "published IS NULL, published ASC, id ASC",
+            ItemSort::Title => "title IS NULL, title COLLATE NOCASE, published DESC",
+            ItemSort::Author => "authors IS NULL, authors COLLATE NOCASE, published DESC",
+            ItemSort::UnreadFirst => "is_read ASC, published DESC, id DESC",
+        }
+    }
+}
+
+/// Filters for listing feed items. Empty filters match every item.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemFilter {
+    /// Only include unread items.
+    pub unread_only: bool,
+    /// Only include starred items.
+    pub starred_only: bool,
+    /// Only include items published at or after this time (Unix timestamp).
+    pub since: Option<i64>,
+    /// Only include items published at or before this time (Unix timestamp).
+    pub until: Option<i64>,
+    /// Only include items whose authors contain this text (case-insensitive).
+    pub author: Option<String>,
+    /// Only include items in this category (case-insensitive).
+    pub category: Option<String>,
+}
+
+impl ItemFilter {
+    /// Whether the filter matches every item.
+    pub fn is_empty(&self) -> bool {
+        *self == ItemFilter::default()
+    }
+
+    /// Parse a filter from space-separated terms: `unread`, `starred`, `author:<text>`,
+    /// `category:<name>`, `since:<YYYY-MM-DD>` and `until:<YYYY-MM-DD>` (both inclusive).
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut filter = ItemFilter::default();
+        for term in input.split_whitespace() {
+            match term.split_once(':') {
+                None if term == "unread" => filter.unread_only = true,
+                None if term == "starred" => filter.starred_only = true,
+                Some(("author", author)) if !author.is_empty() => {
+                    filter.author = Some(author.to_string())
+                }
+                Some(("category", category)) if !category.is_empty() => {
+                    filter.category = Some(category.to_string())
+                }
+                Some(("since", date)) => filter.since = Some(parse_date(date)?),
+                Some(("until", date)) => {
+                    // Include the whole day
+                    filter.until = Some(parse_date(date)? + 24 * 60 * 60 - 1)
+                }
+                _ => anyhow::bail!("unknown filter term: {}", term),
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for ItemFilter {
+    /// Format the filter as terms accepted by [`ItemFilter::parse`].
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = Vec::new();
+        if self.unread_only {
+            terms.push("unread".to_string());
+        }
+        if self.starred_only {
+            terms.push("starred".to_string());
+        }
+        if let Some(author) = &self.author {
+            terms.push(format!("author:{}", author));
+        }
+        if let Some(category) = &self.category {
+            terms.push(format!("category:{}", category));
+        }
+        if let Some(since) = self.since {
+            terms.push(format!("since:{}", format_date(since)));
+        }
+        if let Some(until) = self.until {
+            terms.push(format!("until:{}", format_date(until)));
+        }
+        write!(f, "{}", terms.join(" "))
+    }
+}
+
+/// Options for listing feed items.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemQuery {
+    /// Sort order.
+    pub sort: ItemSort,
+    /// Filters.
+    pub filter: ItemFilter,
+}
+
+/// The read and starred state of an item that is also on a remote server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteItemState {
+    /// ID of the item.
+    pub item_id: usize,
+    /// ID of the item on the server.
+    pub remote_id: usize,
+    /// Whether the item is read here.
+    pub is_read: bool,
+    /// Whether the item is starred here.
+    pub is_starred: bool,
+    /// Whether the item was read on the server when last synced.
+    pub remote_is_read: bool,
+    /// Whether the item was starred on the server when last synced.
+    pub remote_is_starred: bool,
+}
+
+impl RemoteItemState {
+    /// Whether the item was read or starred here since it was last synced.
+    pub fn has_local_changes(&self) -> bool {
+        self.is_read != self.remote_is_read || self.is_starred != self.remote_is_starred
+    }
+}
+
+/// A page of items by ID, for clients that keep their own copy of the items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemPage<'a> {
+    /// Items with a higher ID, lowest ID first.
+    After(usize),
+    /// Items with a lower ID, highest ID first.
+    Before(usize),
+    /// The newest items, highest ID first.
+    Latest,
+    /// The items with these IDs, lowest ID first.
+    Ids(&'a [usize]),
+}
+
+/// Which items to select for clients that keep their own copy of the items. Hidden items are
+/// never selected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemSelection {
+    /// Only items of these feeds.
+    pub feed_ids: Option<Vec<usize>>,
+    /// Only items with this tag.
+    pub tag: Option<String>,
+    /// Only read (true) or unread (false) items.
+    pub is_read: Option<bool>,
+    /// Only starred (true) or unstarred (false) items.
+    pub is_starred: Option<bool>,
+    /// Only items published at or after this time (Unix timestamp).
+    pub since: Option<i64>,
+    /// Only items published before this time (Unix timestamp).
+    pub until: Option<i64>,
+}
+
+impl ItemSelection {
+    /// SQL conditions on `feed_item` selecting the items, with their parameters.
+    fn conditions(&self) -> (Vec<String>, Vec<Value>) {
+        let mut conditions = vec!["is_hidden = 0".to_string()];
+        let mut values = Vec::new();
+        if let Some(feed_ids) = &self.feed_ids {
+            conditions.push(format!(
+                "feed_id IN ({})",
+                vec!["?"; feed_ids.len()].join(", ")
+            ));
+            values.extend(feed_ids.iter().map(|id| Value::Integer(*id as i64)));
+        }
+        if let Some(tag) = &self.tag {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM item_tag WHERE item_id = feed_item.id AND tag = ?)"
+                    .to_string(),
+            );
+            values.push(Value::Text(tag.clone()));
+        }
+        if let Some(is_read) = self.is_read {
+            conditions.push("is_read = ?".to_string());
+            values.push(Value::Integer(is_read.into()));
+        }
+        if let Some(is_starred) = self.is_starred {
+            conditions.push("is_starred = ?".to_string());
+            values.push(Value::Integer(is_starred.into()));
+        }
+        if let Some(since) = self.since {
+            conditions.push("COALESCE(published, created_at) >= ?".to_string());
+            values.push(Value::Integer(since));
+        }
+        if let Some(until) = self.until {
+            conditions.push("COALESCE(published, created_at) < ?".to_string());
+            values.push(Value::Integer(until));
+        }
+        (conditions, values)
+    }
+}
+
+/// The fields of a stored item compared to detect changed entries.
+struct StoredItem {
+    id: usize,
+    title: Option<String>,
+    description: Option<String>,
+    updated: Option<i64>,
+}
+
+/// The key identifying an item within its feed: its GUID, link, content hash or title, in
+/// order of preference.
+fn item_key(item: &ParsedFeedItem, content_hash: Option<&str>) -> String {
+    match (&item.guid, &item.link, content_hash) {
+        (Some(guid), _, _) => format!("guid:{}", guid),
+        (None, Some(link), _) => format!("link:{}", link),
+        (None, None, Some(hash)) => format!("hash:{}", hash),
+        (None, None, None) => format!("title:{}", item.title.as_deref().unwrap_or_default()),
+    }
+}
+
+/// Parse a `YYYY-MM-DD` date to a Unix timestamp at midnight UTC.
+pub(crate) fn parse_date(date: &str) -> Result<i64> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        anyhow::bail!("invalid date, expected YYYY-MM-DD: {}", date);
+    };
+    let date = Date::from_calendar_date(
+        year.parse().context("invalid year")?,
+        Month::try_from(month.parse::<u8>().context("invalid month")?)?,
+        day.parse().context("invalid day")?,
+    )?;
+    Ok(date.midnight().assume_utc().unix_timestamp())
+}
+
+/// Escape the wildcards of a `LIKE` pattern, for use with `ESCAPE '\'`.
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Convert a row selected with [`FEED_ITEM_COLUMNS`] to a [`FeedItem`].
+fn row_to_feed_item(row: &Row) -> rusqlite::Result<FeedItem> {
+    let authors_str: Option<String> = row.get(5)?;
+    let authors = authors_str
+        .map(|s| s.split(", ").map(|a| a.to_string()).collect())
+        .unwrap_or_default();
+
+    Ok(FeedItem {
+        id: row.get(0)?,
+        feed_id: row.get(1)?,
+        title: row.get(2)?,
+        link: row.get(3)?,
+        description: row.get(4)?,
+        authors,
+        published: row.get(6)?,
+        is_read: row.get::<_, i64>(7)? != 0,
+        created_at: row.get(8)?,
+        scroll_position: row.get(9)?,
+        is_starred: row.get::<_, i64>(10)? != 0,
+        is_hidden: row.get::<_, i64>(11)? != 0,
+        tags: row
+            .get::<_, Option<String>>(12)?
+            .map(|s| s.split('\u{1f}').map(|t| t.to_string()).collect())
+            .unwrap_or_default(),
+        duplicate_of: row.get(13)?,
+        is_updated: row.get::<_, i64>(14)? != 0,
+        categories: row
+            .get::<_, Option<String>>(15)?
+            .map(|s| s.split('\u{1f}').map(|c| c.to_string()).collect())
+            .unwrap_or_default(),
+    })
 }
 
 /// The database object.
@@ -65,152 +553,1151 @@ impl Db {
         Ok(Self { conn })
     }
 
-    /// Initialize the `feed` table for holding the user's RSS/Atom feeds.
+    /// Initialize the `feed` table for holding the user's RSS/Atom feeds, and the `folder`
+    /// table for grouping them.
     pub fn init_feed_table(&self) -> Result<()> {
         self.conn.execute_batch(
             r#"
+            CREATE TABLE IF NOT EXISTS folder (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                parent_id INTEGER REFERENCES folder(id) ON DELETE CASCADE,
+                is_collapsed INTEGER NOT NULL DEFAULT 0
+            );
             CREATE TABLE IF NOT EXISTS feed (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 url TEXT NOT NULL UNIQUE,
                 title TEXT,
-                created_at INTEGER NOT NULL
-            )
+                created_at INTEGER NOT NULL,
+                site_link TEXT,
+                description TEXT,
+                language TEXT,
+                icon TEXT,
+                logo TEXT,
+                generator TEXT,
+                authors TEXT,
+                categories TEXT,
+                updated INTEGER,
+                folder_id INTEGER REFERENCES folder(id) ON DELETE SET NULL,
+                custom_title TEXT,
+                is_paused INTEGER NOT NULL DEFAULT 0,
+                is_gone INTEGER NOT NULL DEFAULT 0,
+                remote_id INTEGER,
+                notify INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE IF NOT EXISTS removed_remote_feed (
+                remote_id INTEGER PRIMARY KEY
+            );
+            CREATE TABLE IF NOT EXISTS feed_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                feed_id INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                message TEXT NOT NULL,
+                FOREIGN KEY (feed_id) REFERENCES feed(id) ON DELETE CASCADE
+            );
+            "#,
+        )?;
+        for column in [
+            "site_link",
+            "description",
+            "language",
+            "icon",
+            "logo",
+            "generator",
+            "authors",
+            "categories",
+        ] {
+            self.ensure_column("feed", column, "TEXT")?;
+        }
+        self.ensure_column("feed", "updated", "INTEGER")?;
+        self.ensure_column(
+            "feed",
+            "folder_id",
+            "INTEGER REFERENCES folder(id) ON DELETE SET NULL",
+        )?;
+        self.ensure_column("feed", "custom_title", "TEXT")?;
+        self.ensure_column("feed", "is_paused", "INTEGER NOT NULL DEFAULT 0")?;
+        self.ensure_column("feed", "is_gone", "INTEGER NOT NULL DEFAULT 0")?;
+        self.ensure_column("feed", "remote_id", "INTEGER")?;
+        self.ensure_column("feed", "notify", "INTEGER NOT NULL DEFAULT 0")?;
+        Ok(())
+    }
+
+    /// Initialize the `feed_item` table for holding individual posts/articles from feeds.
+    pub fn init_feed_item_table(&self) -> Result<()> {
+        self.conn
+            .execute_batch(&FEED_ITEM_TABLE.replace("{table}", "feed_item"))?;
+        self.conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS item_tag (
+                item_id INTEGER NOT NULL,
+                tag TEXT NOT NULL,
+                FOREIGN KEY (item_id) REFERENCES feed_item(id) ON DELETE CASCADE,
+                UNIQUE(item_id, tag)
+            );
+            CREATE TABLE IF NOT EXISTS item_category (
+                item_id INTEGER NOT NULL,
+                category TEXT NOT NULL,
+                FOREIGN KEY (item_id) REFERENCES feed_item(id) ON DELETE CASCADE,
+                UNIQUE(item_id, category)
+            );
+            CREATE INDEX IF NOT EXISTS item_category_category
+                ON item_category(category COLLATE NOCASE);
+            CREATE TABLE IF NOT EXISTS deleted_item (
+                feed_id INTEGER NOT NULL,
+                item_key TEXT NOT NULL,
+                FOREIGN KEY (feed_id) REFERENCES feed(id) ON DELETE CASCADE,
+                UNIQUE(feed_id, item_key)
+            );
             "#,
         )?;
+        self.ensure_column("feed_item", "scroll_position", "INTEGER NOT NULL DEFAULT 0")?;
+        self.ensure_column("feed_item", "is_starred", "INTEGER NOT NULL DEFAULT 0")?;
+        self.ensure_column("feed_item", "is_hidden", "INTEGER NOT NULL DEFAULT 0")?;
+        self.ensure_column("feed_item", "guid", "TEXT")?;
+        self.ensure_column("feed_item", "canonical_url", "TEXT")?;
+        self.ensure_column("feed_item", "content_hash", "TEXT")?;
+        self.ensure_column(
+            "feed_item",
+            "duplicate_of",
+            "INTEGER REFERENCES feed_item(id) ON DELETE SET NULL",
+        )?;
+        self.ensure_column("feed_item", "updated", "INTEGER")?;
+        self.ensure_column("feed_item", "is_updated", "INTEGER NOT NULL DEFAULT 0")?;
+        for column in ["remote_id", "remote_is_read", "remote_is_starred"] {
+            self.ensure_column("feed_item", column, "INTEGER")?;
+        }
+        self.migrate_item_identity()?;
+        self.conn.execute_batch(
+            r#"
+            CREATE INDEX IF NOT EXISTS feed_item_guid ON feed_item(guid);
+            CREATE INDEX IF NOT EXISTS feed_item_canonical_url ON feed_item(canonical_url);
+            CREATE INDEX IF NOT EXISTS feed_item_content_hash ON feed_item(content_hash);
+            CREATE INDEX IF NOT EXISTS feed_item_remote_id ON feed_item(remote_id);
+            "#,
+        )?;
+        Ok(())
+    }
+
+    /// Rebuild a `feed_item` table from before items were identified by `item_key`, which was
+    /// unique by link instead. SQLite can't drop a constraint in place.
+    fn migrate_item_identity(&self) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT 1 FROM pragma_table_info('feed_item') WHERE name = 'item_key'")?;
+        if stmt.exists([])? {
+            return Ok(());
+        }
+
+        let columns = "id, feed_id, title, link, description, authors, published, is_read, \
+            created_at, scroll_position, is_starred, is_hidden, guid, canonical_url, \
+            content_hash, duplicate_of, updated, is_updated";
+        // Foreign keys can't be turned off inside a transaction, so they're turned off around
+        // it, and back on whether or not the rebuild succeeds
+        self.conn.execute_batch("PRAGMA foreign_keys=OFF")?;
+        let rebuilt = (|| -> Result<()> {
+            let tx = self.conn.unchecked_transaction()?;
+            // Keys match those of `item_key`, except for linkless items, which could have been
+            // stored more than once before
+            tx.execute_batch(&format!(
+                "{}
+                 INSERT INTO feed_item_new ({columns}, item_key)
+                     SELECT {columns}, COALESCE('guid:' || guid, 'link:' || link, 'id:' || id)
+                     FROM feed_item;
+                 DROP TABLE feed_item;
+                 ALTER TABLE feed_item_new RENAME TO feed_item;",
+                FEED_ITEM_TABLE.replace("{table}", "feed_item_new"),
+            ))?;
+            tx.commit()?;
+            Ok(())
+        })();
+        self.conn.execute_batch("PRAGMA foreign_keys=ON")?;
+        rebuilt
+    }
+
+    /// Add a column to an existing table if it is missing, for databases created before the
+    /// column was introduced.
+    fn ensure_column(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1",
+            table
+        ))?;
+        if !stmt.exists(params![column])? {
+            self.conn.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Add a feed specified by URL and optional title the to database.
+    pub fn add_feed(&self, url: &str, title: Option<&str>) -> Result<()> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        self.conn.execute(
+            "INSERT INTO feed (url, title, created_at) VALUES (?1, ?2, ?3)",
+            params![url, title, now],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a feed by URL. Returns true if a feed was deleted, false if not found. A feed
+    /// subscribed on a remote server is remembered until the server is told of the removal.
+    pub fn remove_feed(&self, url: &str) -> Result<bool> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO removed_remote_feed (remote_id)
+             SELECT remote_id FROM feed WHERE url = ?1 AND remote_id IS NOT NULL",
+            params![url],
+        )?;
+        let rows_affected = self
+            .conn
+            .execute("DELETE FROM feed WHERE url = ?1", params![url])?;
+        Ok(rows_affected > 0)
+    }
+
+    /// List the feeds in the database, sorted by title.
+    pub fn list_feeds(&self) -> Result<Vec<Feed>> {
+        self.list_feeds_with(&FeedQuery::default())
+    }
+
+    /// List the feeds in the database with the given sort order and filters.
+    pub fn list_feeds_with(&self, query: &FeedQuery) -> Result<Vec<Feed>> {
+        let sql = format!(
+            "SELECT * FROM (
+                SELECT f.id, f.url, COALESCE(f.custom_title, f.title) AS title, f.created_at,
+                    f.folder_id, f.is_paused, f.is_gone,
+                    (SELECT COUNT(*) FROM feed_item i
+                     WHERE i.feed_id = f.id AND i.is_read = 0 AND i.is_hidden = 0)
+                        AS unread_count,
+                    (SELECT MAX(COALESCE(i.published, i.created_at)) FROM feed_item i
+                     WHERE i.feed_id = f.id) AS last_updated
+                FROM feed f
+             )
+             {}
+             ORDER BY {}",
+            if query.unread_only {
+                "WHERE unread_count > 0"
+            } else {
+                ""
+            },
+            query.sort.order_by()
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Feed {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                title: row.get(2)?,
+                created_at: row.get(3)?,
+                folder_id: row.get(4)?,
+                is_paused: row.get::<_, i64>(5)? != 0,
+                is_gone: row.get::<_, i64>(6)? != 0,
+                unread_count: row.get(7)?,
+                last_updated: row.get(8)?,
+            })
+        })?;
+        let mut feeds = Vec::new();
+        for f in rows {
+            feeds.push(f?);
+        }
+        Ok(feeds)
+    }
+
+    /// Get the editable properties of a feed.
+    pub fn get_feed_settings(&self, feed_id: usize) -> Result<FeedSettings> {
+        Ok(self.conn.query_row(
+            "SELECT url, custom_title, is_paused, notify FROM feed WHERE id = ?1",
+            params![feed_id],
+            |row| {
+                Ok(FeedSettings {
+                    url: row.get(0)?,
+                    custom_title: row.get(1)?,
+                    is_paused: row.get::<_, i64>(2)? != 0,
+                    notify: row.get::<_, i64>(3)? != 0,
+                })
+            },
+        )?)
+    }
+
+    /// Update the editable properties of a feed, keeping its items. Changing the URL of a gone
+    /// feed revives it. Fails if another feed already has the new URL.
+    pub fn update_feed(&self, feed_id: usize, settings: &FeedSettings) -> Result<()> {
+        let url = settings.url.trim();
+        anyhow::ensure!(!url.is_empty(), "feed URL can't be empty");
+        if self.feed_id_for_url(url)?.is_some_and(|id| id != feed_id) {
+            anyhow::bail!("a feed with URL {} already exists", url);
+        }
+        let custom_title = settings
+            .custom_title
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty());
+        let rows_affected = self.conn.execute(
+            "UPDATE feed
+             SET is_gone = is_gone AND url = ?1, url = ?1, custom_title = ?2, is_paused = ?3,
+                 notify = ?4
+             WHERE id = ?5",
+            params![
+                url,
+                custom_title,
+                settings.is_paused,
+                settings.notify,
+                feed_id
+            ],
+        )?;
+        anyhow::ensure!(rows_affected > 0, "feed not found");
+        Ok(())
+    }
+
+    /// Change the URL of a feed that moved, keeping its items. Returns false without changing
+    /// anything if another feed already has the new URL.
+    pub fn set_feed_url(&self, feed_id: usize, url: &str) -> Result<bool> {
+        if self.feed_id_for_url(url)?.is_some() {
+            return Ok(false);
+        }
+        self.conn.execute(
+            "UPDATE feed SET url = ?1 WHERE id = ?2",
+            params![url, feed_id],
+        )?;
+        Ok(true)
+    }
+
+    /// Mark a feed as permanently gone, or not.
+    pub fn set_feed_gone(&self, feed_id: usize, gone: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE feed SET is_gone = ?1 WHERE id = ?2",
+            params![gone, feed_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record an event in the log of a feed.
+    pub fn add_feed_log(&self, feed_id: usize, message: &str) -> Result<()> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        self.conn.execute(
+            "INSERT INTO feed_log (feed_id, created_at, message) VALUES (?1, ?2, ?3)",
+            params![feed_id, now, message],
+        )?;
+        Ok(())
+    }
+
+    /// Get the log of events of a feed, newest first.
+    pub fn get_feed_log(&self, feed_id: usize) -> Result<Vec<FeedLogEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT created_at, message FROM feed_log WHERE feed_id = ?1
+             ORDER BY created_at DESC, id DESC",
+        )?;
+        let rows = stmt.query_map(params![feed_id], |row| {
+            Ok(FeedLogEntry {
+                created_at: row.get(0)?,
+                message: row.get(1)?,
+            })
+        })?;
+        let mut entries = Vec::new();
+        for entry in rows {
+            entries.push(entry?);
+        }
+        Ok(entries)
+    }
+
+    /// The ID of the feed with a URL.
+    fn feed_id_for_url(&self, url: &str) -> Result<Option<usize>> {
+        Ok(self
+            .conn
+            .query_row("SELECT id FROM feed WHERE url = ?1", params![url], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    /// List all folders, sorted by name.
+    pub fn list_folders(&self) -> Result<Vec<Folder>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, parent_id, is_collapsed FROM folder ORDER BY name COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Folder {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+                is_collapsed: row.get::<_, i64>(3)? != 0,
+            })
+        })?;
+        let mut folders = Vec::new();
+        for folder in rows {
+            folders.push(folder?);
+        }
+        Ok(folders)
+    }
+
+    /// Get the ID of the folder with a name in a parent folder (or at the top level), creating
+    /// the folder if it doesn't exist.
+    pub fn create_folder(&self, name: &str, parent_id: Option<usize>) -> Result<usize> {
+        let existing = self
+            .conn
+            .query_row(
+                "SELECT id FROM folder WHERE name = ?1 AND parent_id IS ?2",
+                params![name, parent_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+        self.conn.execute(
+            "INSERT INTO folder (name, parent_id) VALUES (?1, ?2)",
+            params![name, parent_id],
+        )?;
+        Ok(self.conn.last_insert_rowid() as usize)
+    }
+
+    /// Get the ID of the folder at a `/`-separated path of folder names, such as `Tech/Rust`,
+    /// creating any missing folders. An empty path is the top level, with no folder.
+    pub fn create_folder_path(&self, path: &str) -> Result<Option<usize>> {
+        let mut folder_id = None;
+        for name in path.split('/').map(str::trim).filter(|n| !n.is_empty()) {
+            folder_id = Some(self.create_folder(name, folder_id)?);
+        }
+        Ok(folder_id)
+    }
+
+    /// The `/`-separated path of folder names to a folder.
+    pub fn folder_path(&self, folder_id: usize) -> Result<String> {
+        let folders = self.list_folders()?;
+        let mut names = Vec::new();
+        let mut current = Some(folder_id);
+        while let Some(id) = current {
+            let Some(folder) = folders.iter().find(|f| f.id == id) else {
+                break;
+            };
+            names.push(folder.name.as_str());
+            current = folder.parent_id;
+        }
+        names.reverse();
+        Ok(names.join("/"))
+    }
+
+    /// Move a feed into a folder, or out of any folder.
+    pub fn set_feed_folder(&self, feed_id: usize, folder_id: Option<usize>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE feed SET folder_id = ?1 WHERE id = ?2",
+            params![folder_id, feed_id],
+        )?;
+        Ok(())
+    }
+
+    /// Collapse or expand a folder in the feeds list.
+    pub fn set_folder_collapsed(&self, folder_id: usize, collapsed: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE folder SET is_collapsed = ?1 WHERE id = ?2",
+            params![collapsed, folder_id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a folder along with the folders in it. Their feeds are moved out of any folder.
+    /// Returns true if a folder was deleted, false if not found.
+    pub fn delete_folder(&self, folder_id: usize) -> Result<bool> {
+        let rows_affected = self
+            .conn
+            .execute("DELETE FROM folder WHERE id = ?1", params![folder_id])?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Refresh the title and metadata of a feed from a fetch of it. A missing title keeps the
+    /// stored one.
+    pub fn update_feed_metadata(
+        &self,
+        feed_id: usize,
+        title: Option<&str>,
+        metadata: &FeedMetadata,
+    ) -> Result<()> {
+        let join = |list: &[String]| (!list.is_empty()).then(|| list.join(", "));
+        self.conn.execute(
+            "UPDATE feed
+             SET title = COALESCE(?1, title), site_link = ?2, description = ?3, language = ?4,
+                 icon = ?5, logo = ?6, generator = ?7, authors = ?8, categories = ?9,
+                 updated = ?10
+             WHERE id = ?11",
+            params![
+                title,
+                metadata.site_link,
+                metadata.description,
+                metadata.language,
+                metadata.icon,
+                metadata.logo,
+                metadata.generator,
+                join(&metadata.authors),
+                join(&metadata.categories),
+                metadata.updated,
+                feed_id
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the metadata of a feed, as of its last sync.
+    pub fn get_feed_metadata(&self, feed_id: usize) -> Result<FeedMetadata> {
+        let split = |list: Option<String>| -> Vec<String> {
+            list.map(|s| s.split(", ").map(|v| v.to_string()).collect())
+                .unwrap_or_default()
+        };
+        Ok(self.conn.query_row(
+            "SELECT site_link, description, language, icon, logo, generator, authors,
+                 categories, updated
+             FROM feed WHERE id = ?1",
+            params![feed_id],
+            |row| {
+                Ok(FeedMetadata {
+                    site_link: row.get(0)?,
+                    description: row.get(1)?,
+                    language: row.get(2)?,
+                    icon: row.get(3)?,
+                    logo: row.get(4)?,
+                    generator: row.get(5)?,
+                    authors: split(row.get(6)?),
+                    categories: split(row.get(7)?),
+                    updated: row.get(8)?,
+                })
+            },
+        )?)
+    }
+
+    /// Add a feed item to the database, or update the stored item if its entry changed.
+    ///
+    /// Items are identified within a feed by GUID, falling back to link, then content for
+    /// entries without either. An item with the same canonical URL, global GUID or content as an
+    /// item in another feed is recorded as a duplicate of it, and starts out read if that item
+    /// was read.
+    pub fn add_feed_item(&self, feed_id: usize, item: &ParsedFeedItem) -> Result<ItemChange> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let authors_str = (!item.authors.is_empty()).then(|| item.authors.join(", "));
+        let canonical = item
+            .canonical_link
+            .as_deref()
+            .or(item.link.as_deref())
+            .and_then(canonical_url);
+        let hash = content_hash(item.title.as_deref(), item.description.as_deref());
+        let key = item_key(item, hash.as_deref());
+
+        // Deleted items stay deleted, even though the feed still lists them
+        let mut deleted = self
+            .conn
+            .prepare_cached("SELECT 1 FROM deleted_item WHERE feed_id = ?1 AND item_key = ?2")?;
+        if deleted.exists(params![feed_id, key])? {
+            return Ok(ItemChange::Unchanged);
+        }
+
+        if let Some(existing) = self.find_stored_item(feed_id, item, &key)? {
+            // Also fills in the categories of items stored before categories were kept
+            self.set_item_categories(existing.id, &item.categories)?;
+            if existing.title == item.title
+                && existing.description == item.description
+                && existing.updated == item.updated
+            {
+                return Ok(ItemChange::Unchanged);
+            }
+            self.conn.execute(
+                "UPDATE feed_item
+                 SET title = ?1, link = ?2, description = ?3, authors = ?4, published = ?5,
+                     updated = ?6, guid = ?7, canonical_url = ?8, content_hash = ?9,
+                     item_key = ?10, is_updated = 1
+                 WHERE id = ?11",
+                params![
+                    item.title,
+                    item.link,
+                    item.description,
+                    authors_str,
+                    item.published,
+                    item.updated,
+                    item.guid,
+                    canonical,
+                    hash,
+                    key,
+                    existing.id
+                ],
+            )?;
+            return Ok(ItemChange::Updated(existing.id));
+        }
+
+        // GUIDs are only unique within a feed unless they're URLs or URNs
+        let global_guid = item.guid.as_deref().filter(|guid| guid.contains(':'));
+        let original: Option<(usize, bool)> = self
+            .conn
+            .query_row(
+                "SELECT COALESCE(duplicate_of, id), is_read FROM feed_item
+                 WHERE feed_id != ?1
+                   AND (canonical_url = ?2 OR guid = ?3 OR content_hash = ?4)
+                 ORDER BY id
+                 LIMIT 1",
+                params![feed_id, canonical, global_guid, hash],
+                |row| Ok((row.get(0)?, row.get::<_, i64>(1)? != 0)),
+            )
+            .optional()?;
+        let (duplicate_of, is_read) = match original {
+            Some((id, is_read)) => (Some(id), is_read),
+            None => (None, false),
+        };
+
+        self.conn.execute(
+            "INSERT INTO feed_item (feed_id, title, link, description, authors, published,
+                 is_read, created_at, guid, canonical_url, content_hash, duplicate_of, updated,
+                 item_key)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                feed_id,
+                item.title,
+                item.link,
+                item.description,
+                authors_str,
+                item.published,
+                is_read,
+                now,
+                item.guid,
+                canonical,
+                hash,
+                duplicate_of,
+                item.updated,
+                key
+            ],
+        )?;
+        let item_id = self.conn.last_insert_rowid() as usize;
+        self.set_item_categories(item_id, &item.categories)?;
+        Ok(ItemChange::New(item_id))
+    }
+
+    /// Replace the categories of a feed item.
+    fn set_item_categories(&self, item_id: usize, categories: &[String]) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM item_category WHERE item_id = ?1",
+            params![item_id],
+        )?;
+        let mut stmt = self
+            .conn
+            .prepare("INSERT OR IGNORE INTO item_category (item_id, category) VALUES (?1, ?2)")?;
+        for category in categories {
+            stmt.execute(params![item_id, category])?;
+        }
+        Ok(())
+    }
+
+    /// Find the stored version of an item in a feed by its key. Items stored before their feed
+    /// had GUIDs are found by link instead, and take on the GUID.
+    fn find_stored_item(
+        &self,
+        feed_id: usize,
+        item: &ParsedFeedItem,
+        key: &str,
+    ) -> Result<Option<StoredItem>> {
+        let map_row = |row: &Row| {
+            Ok(StoredItem {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                description: row.get(2)?,
+                updated: row.get(3)?,
+            })
+        };
+        let stored = self
+            .conn
+            .query_row(
+                "SELECT id, title, description, updated FROM feed_item
+                 WHERE feed_id = ?1 AND item_key = ?2",
+                params![feed_id, key],
+                map_row,
+            )
+            .optional()?;
+        if stored.is_some() || item.guid.is_none() {
+            return Ok(stored);
+        }
+        let legacy = self
+            .conn
+            .query_row(
+                "SELECT id, title, description, updated FROM feed_item
+                 WHERE feed_id = ?1 AND guid IS NULL AND link = ?2",
+                params![feed_id, item.link],
+                map_row,
+            )
+            .optional()?;
+        if let Some(legacy) = &legacy {
+            // Adopt the GUID so that the link is free for other entries
+            self.conn.execute(
+                "UPDATE feed_item SET guid = ?1, item_key = ?2 WHERE id = ?3",
+                params![item.guid, key, legacy.id],
+            )?;
+        }
+        Ok(legacy)
+    }
+
+    /// Whether a feed already has an item with the given link.
+    pub fn has_feed_item_link(&self, feed_id: usize, link: &str) -> Result<bool> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT 1 FROM feed_item WHERE feed_id = ?1 AND link = ?2")?;
+        Ok(stmt.exists(params![feed_id, link])?)
+    }
+
+    /// Get a feed item by ID.
+    pub fn get_feed_item(&self, item_id: usize) -> Result<Option<FeedItem>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM feed_item WHERE id = ?1",
+            FEED_ITEM_COLUMNS
+        ))?;
+        let mut rows = stmt.query_map(params![item_id], row_to_feed_item)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// The GUID and last update time (Unix timestamp) the feed gave an item, for re-publishing
+    /// it. `None` if there is no such item.
+    pub fn get_item_origin(&self, item_id: usize) -> Result<Option<(Option<String>, Option<i64>)>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT guid, updated FROM feed_item WHERE id = ?1",
+                params![item_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?)
+    }
+
+    /// Get all items for a specific feed, newest first. Hidden items are left out.
+    pub fn get_feed_items(&self, feed_id: usize) -> Result<Vec<FeedItem>> {
+        self.get_feed_items_with(feed_id, &ItemQuery::default())
+    }
+
+    /// Get the items for a specific feed with the given sort order and filters. Hidden items
+    /// are left out.
+    pub fn get_feed_items_with(&self, feed_id: usize, query: &ItemQuery) -> Result<Vec<FeedItem>> {
+        self.query_items(Some(feed_id), query, None)
+    }
+
+    /// Get the items of all feeds with the given sort order and filters. Hidden items are left
+    /// out, and articles that appear in several feeds are only included once.
+    pub fn get_all_items_with(&self, query: &ItemQuery) -> Result<Vec<FeedItem>> {
+        self.query_items(None, query, None)
+    }
+
+    /// Get the items of all feeds in a category (case-insensitive), newest first.
+    pub fn items_by_category(&self, category: &str) -> Result<Vec<FeedItem>> {
+        let query = ItemQuery {
+            filter: ItemFilter {
+                category: Some(category.to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        self.get_all_items_with(&query)
+    }
+
+    /// Search the items of all feeds for text in their title, authors or description
+    /// (case-insensitive), with the given sort order and filters.
+    pub fn search_items(&self, text: &str, query: &ItemQuery) -> Result<Vec<FeedItem>> {
+        self.query_items(None, query, Some(text))
+    }
+
+    /// Get the items of one feed, or of all feeds without duplicates, optionally only those
+    /// containing some text.
+    fn query_items(
+        &self,
+        feed_id: Option<usize>,
+        query: &ItemQuery,
+        text: Option<&str>,
+    ) -> Result<Vec<FeedItem>> {
+        let filter = &query.filter;
+        let mut conditions = vec!["is_hidden = 0".to_string()];
+        let mut values = Vec::new();
+        match feed_id {
+            Some(feed_id) => {
+                conditions.push("feed_id = ?".to_string());
+                values.push(Value::Integer(feed_id as i64));
+            }
+            None => conditions.push("duplicate_of IS NULL".to_string()),
+        }
+        if filter.unread_only {
+            conditions.push("is_read = 0".to_string());
+        }
+        if filter.starred_only {
+            conditions.push("is_starred = 1".to_string());
+        }
+        if let Some(since) = filter.since {
+            conditions.push("COALESCE(published, created_at) >= ?".to_string());
+            values.push(Value::Integer(since));
+        }
+        if let Some(until) = filter.until {
+            conditions.push("COALESCE(published, created_at) <= ?".to_string());
+            values.push(Value::Integer(until));
+        }
+        if let Some(author) = &filter.author {
+            conditions.push("authors LIKE '%' || ? || '%' ESCAPE '\\'".to_string());
+            values.push(Value::Text(escape_like(author)));
+        }
+        if let Some(category) = &filter.category {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM item_category WHERE item_id = feed_item.id \
+                 AND category = ? COLLATE NOCASE)"
+                    .to_string(),
+            );
+            values.push(Value::Text(category.clone()));
+        }
+        if let Some(text) = text {
+            conditions.push(
+                "(title LIKE '%' || ? || '%' ESCAPE '\\' \
+                 OR authors LIKE '%' || ? || '%' ESCAPE '\\' \
+                 OR description LIKE '%' || ? || '%' ESCAPE '\\')"
+                    .to_string(),
+            );
+            for _ in 0..3 {
+                values.push(Value::Text(escape_like(text)));
+            }
+        }
+
+        let sql = format!(
+            "SELECT {} FROM feed_item WHERE {} ORDER BY {}",
+            FEED_ITEM_COLUMNS,
+            conditions.join(" AND "),
+            query.sort.order_by()
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), row_to_feed_item)?;
+        let mut items = Vec::new();
+        for item in rows {
+            items.push(item?);
+        }
+        Ok(items)
+    }
+
+    /// Mark a feed item as read, along with its duplicates in other feeds. This also clears the
+    /// marker of updated items.
+    pub fn mark_item_read(&self, item_id: usize) -> Result<()> {
+        self.conn.execute(
+            "WITH original AS (SELECT COALESCE(duplicate_of, id) AS id FROM feed_item WHERE id = ?1)
+             UPDATE feed_item SET is_read = 1, is_updated = 0
+             WHERE id = (SELECT id FROM original) OR duplicate_of = (SELECT id FROM original)",
+            params![item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Mark a feed item as unread, along with its copies in other feeds.
+    pub fn mark_item_unread(&self, item_id: usize) -> Result<()> {
+        self.conn.execute(
+            "WITH original AS (SELECT COALESCE(duplicate_of, id) AS id FROM feed_item WHERE id = ?1)
+             UPDATE feed_item SET is_read = 0
+             WHERE id = (SELECT id FROM original) OR duplicate_of = (SELECT id FROM original)",
+            params![item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Mark the items of a feed as read, or only those added before a time (Unix timestamp),
+    /// along with their copies in other feeds. Returns the number of items of the feed marked.
+    pub fn mark_feed_read(&self, feed_id: usize, before: Option<i64>) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE feed_item SET is_read = 1, is_updated = 0
+             WHERE feed_id != ?1 AND is_read = 0 AND COALESCE(duplicate_of, id) IN (
+                 SELECT COALESCE(duplicate_of, id) FROM feed_item
+                 WHERE feed_id = ?1 AND is_read = 0 AND (?2 IS NULL OR created_at < ?2))",
+            params![feed_id, before],
+        )?;
+        let rows_affected = self.conn.execute(
+            "UPDATE feed_item SET is_read = 1, is_updated = 0
+             WHERE feed_id = ?1 AND is_read = 0 AND (?2 IS NULL OR created_at < ?2)",
+            params![feed_id, before],
+        )?;
+        Ok(rows_affected)
+    }
+
+    /// IDs of the unread items of all feeds, lowest first. Hidden items are left out.
+    pub fn unread_item_ids(&self) -> Result<Vec<usize>> {
+        self.select_item_ids(&ItemSelection {
+            is_read: Some(false),
+            ..Default::default()
+        })
+    }
+
+    /// IDs of the starred items of all feeds, lowest first. Hidden items are left out.
+    pub fn starred_item_ids(&self) -> Result<Vec<usize>> {
+        self.select_item_ids(&ItemSelection {
+            is_starred: Some(true),
+            ..Default::default()
+        })
+    }
+
+    /// IDs of the selected items, lowest first.
+    pub fn select_item_ids(&self, selection: &ItemSelection) -> Result<Vec<usize>> {
+        let (conditions, values) = selection.conditions();
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id FROM feed_item WHERE {} ORDER BY id",
+            conditions.join(" AND ")
+        ))?;
+        let ids = stmt.query_map(params_from_iter(values), |row| row.get(0))?;
+        Ok(ids.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Number of visible items of all feeds.
+    pub fn count_items(&self) -> Result<usize> {
+        Ok(self.conn.query_row(
+            "SELECT COUNT(*) FROM feed_item WHERE is_hidden = 0",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Get up to `limit` of the selected items by ID.
+    pub fn get_items_page(
+        &self,
+        selection: &ItemSelection,
+        page: ItemPage,
+        limit: usize,
+    ) -> Result<Vec<FeedItem>> {
+        let (mut conditions, mut values) = selection.conditions();
+        let order = match page {
+            ItemPage::After(id) => {
+                conditions.push("id > ?".to_string());
+                values.push(Value::Integer(id as i64));
+                "ASC"
+            }
+            ItemPage::Before(id) => {
+                conditions.push("id < ?".to_string());
+                values.push(Value::Integer(id as i64));
+                "DESC"
+            }
+            ItemPage::Latest => "DESC",
+            ItemPage::Ids(ids) => {
+                conditions.push(format!("id IN ({})", vec!["?"; ids.len()].join(", ")));
+                values.extend(ids.iter().map(|id| Value::Integer(*id as i64)));
+                "ASC"
+            }
+        };
+        let sql = format!(
+            "SELECT {} FROM feed_item WHERE {} ORDER BY id {} LIMIT {}",
+            FEED_ITEM_COLUMNS,
+            conditions.join(" AND "),
+            order,
+            limit
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), row_to_feed_item)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Get up to `limit` of the selected items, newest first, after skipping the `offset` newest.
+    /// Items that duplicate an item of another feed are left out.
+    pub fn get_newest_items(
+        &self,
+        selection: &ItemSelection,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<FeedItem>> {
+        let (conditions, values) = selection.conditions();
+        let sql = format!(
+            "SELECT {} FROM feed_item WHERE {} AND duplicate_of IS NULL
+             ORDER BY COALESCE(published, created_at) DESC, id DESC LIMIT {} OFFSET {}",
+            FEED_ITEM_COLUMNS,
+            conditions.join(" AND "),
+            limit,
+            offset
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), row_to_feed_item)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Star or unstar a feed item.
+    pub fn set_item_starred(&self, item_id: usize, starred: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE feed_item SET is_starred = ?1 WHERE id = ?2",
+            params![starred, item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Hide or unhide a feed item.
+    pub fn set_item_hidden(&self, item_id: usize, hidden: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE feed_item SET is_hidden = ?1 WHERE id = ?2",
+            params![hidden, item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Add a tag to a feed item. Adding a tag the item already has does nothing.
+    pub fn add_item_tag(&self, item_id: usize, tag: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO item_tag (item_id, tag) VALUES (?1, ?2)",
+            params![item_id, tag],
+        )?;
+        Ok(())
+    }
+
+    /// Remove a tag from a feed item.
+    pub fn remove_item_tag(&self, item_id: usize, tag: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM item_tag WHERE item_id = ?1 AND tag = ?2",
+            params![item_id, tag],
+        )?;
+        Ok(())
+    }
+
+    /// List the tags given to any item, sorted by name.
+    pub fn list_item_tags(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT tag FROM item_tag ORDER BY tag COLLATE NOCASE")?;
+        let tags = stmt.query_map([], |row| row.get(0))?;
+        Ok(tags.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Delete a feed item, remembering its key so that syncing doesn't add it back. Returns
+    /// true if an item was deleted, false if not found.
+    pub fn delete_feed_item(&self, item_id: usize) -> Result<bool> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO deleted_item (feed_id, item_key)
+             SELECT feed_id, item_key FROM feed_item WHERE id = ?1",
+            params![item_id],
+        )?;
+        let rows_affected = self
+            .conn
+            .execute("DELETE FROM feed_item WHERE id = ?1", params![item_id])?;
+        Ok(rows_affected > 0)
+    }
+
+    /// The ID on the remote server of a feed, if it's subscribed there.
+    pub fn feed_remote_id(&self, feed_id: usize) -> Result<Option<usize>> {
+        Ok(self.conn.query_row(
+            "SELECT remote_id FROM feed WHERE id = ?1",
+            params![feed_id],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// The feed with an ID on the remote server, if any.
+    pub fn feed_for_remote_id(&self, remote_id: usize) -> Result<Option<usize>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT id FROM feed WHERE remote_id = ?1",
+                params![remote_id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Set the ID on the remote server of a feed.
+    pub fn set_feed_remote_id(&self, feed_id: usize, remote_id: Option<usize>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE feed SET remote_id = ?1 WHERE id = ?2",
+            params![remote_id, feed_id],
+        )?;
         Ok(())
     }
 
-    /// Initialize the `feed_item` table for holding individual posts/articles from feeds.
-    pub fn init_feed_item_table(&self) -> Result<()> {
-        self.conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS feed_item (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                feed_id INTEGER NOT NULL,
-                title TEXT,
-                link TEXT,
-                description TEXT,
-                authors TEXT,
-                published INTEGER,
-                is_read INTEGER NOT NULL DEFAULT 0,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY (feed_id) REFERENCES feed(id) ON DELETE CASCADE,
-                UNIQUE(feed_id, link)
-            )
-            "#,
-        )?;
-        Ok(())
+    /// IDs on the remote server of the feeds removed here, which the server hasn't been told of.
+    pub fn removed_remote_feeds(&self) -> Result<Vec<usize>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT remote_id FROM removed_remote_feed ORDER BY remote_id")?;
+        let ids = stmt.query_map([], |row| row.get(0))?;
+        Ok(ids.collect::<rusqlite::Result<_>>()?)
     }
 
-    /// Add a feed specified by URL and optional title the to database.
-    pub fn add_feed(&self, url: &str, title: Option<&str>) -> Result<()> {
-        let now = OffsetDateTime::now_utc().unix_timestamp();
+    /// Forget a feed removed here, once the server was told of the removal.
+    pub fn clear_removed_remote_feed(&self, remote_id: usize) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO feed (url, title, created_at) VALUES (?1, ?2, ?3)",
-            params![url, title, now],
+            "DELETE FROM removed_remote_feed WHERE remote_id = ?1",
+            params![remote_id],
         )?;
         Ok(())
     }
 
-    /// Remove a feed by URL. Returns true if a feed was deleted, false if not found.
-    pub fn remove_feed(&self, url: &str) -> Result<bool> {
-        let rows_affected = self
+    /// The highest ID on the remote server of any item, to pull only newer items.
+    pub fn max_item_remote_id(&self) -> Result<Option<usize>> {
+        Ok(self
             .conn
-            .execute("DELETE FROM feed WHERE url = ?1", params![url])?;
-        Ok(rows_affected > 0)
+            .query_row("SELECT MAX(remote_id) FROM feed_item", [], |row| row.get(0))?)
     }
 
-    /// List the feeds in the database.
-    pub fn list_feeds(&self) -> Result<Vec<Feed>> {
-        let mut stmt = self
+    /// The item of a feed with a link, if any.
+    pub fn find_feed_item_by_link(&self, feed_id: usize, link: &str) -> Result<Option<usize>> {
+        Ok(self
             .conn
-            .prepare("SELECT id, url, title, created_at FROM feed")?;
-        let rows = stmt.query_map([], |row| {
-            Ok(Feed {
-                id: row.get(0)?,
-                url: row.get(1)?,
-                title: row.get(2)?,
-                created_at: row.get(3)?,
-            })
-        })?;
-        let mut feeds = Vec::new();
-        for f in rows {
-            feeds.push(f?);
-        }
-        Ok(feeds)
+            .query_row(
+                "SELECT id FROM feed_item WHERE feed_id = ?1 AND link = ?2",
+                params![feed_id, link],
+                |row| row.get(0),
+            )
+            .optional()?)
     }
 
-    /// Add a feed item to the database. Uses INSERT OR IGNORE to skip duplicates.
-    /// Returns true if the item was inserted, false if it was a duplicate.
-    pub fn add_feed_item(
+    /// Link an item to its ID on the remote server, and set its read and starred state both
+    /// here and as last synced.
+    pub fn set_item_remote(
         &self,
-        feed_id: usize,
-        title: Option<&str>,
-        link: Option<&str>,
-        description: Option<&str>,
-        authors: Option<&[&str]>,
-        published: Option<i64>,
-    ) -> Result<bool> {
-        let now = OffsetDateTime::now_utc().unix_timestamp();
-        let authors_str = authors.map(|a| a.join(", "));
-
-        let rows_affected = self.conn.execute(
-            "INSERT OR IGNORE INTO feed_item (feed_id, title, link, description, authors, published, is_read, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, ?7)",
-            params![feed_id, title, link, description, authors_str, published, now],
+        item_id: usize,
+        remote_id: usize,
+        is_read: bool,
+        is_starred: bool,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE feed_item SET remote_id = ?1 WHERE id = ?2",
+            params![remote_id, item_id],
         )?;
+        self.set_item_synced_state(item_id, is_read, is_starred)
+    }
 
-        Ok(rows_affected > 0)
+    /// Set the read and starred state of an item both here and as last synced with the remote
+    /// server, once they agree.
+    pub fn set_item_synced_state(
+        &self,
+        item_id: usize,
+        is_read: bool,
+        is_starred: bool,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE feed_item SET is_read = ?1, is_starred = ?2, remote_is_read = ?1,
+                remote_is_starred = ?2
+             WHERE id = ?3",
+            params![is_read, is_starred, item_id],
+        )?;
+        Ok(())
     }
 
-    /// Get all items for a specific feed.
-    pub fn get_feed_items(&self, feed_id: usize) -> Result<Vec<FeedItem>> {
+    /// The state of every item that is also on the remote server.
+    pub fn remote_item_states(&self) -> Result<Vec<RemoteItemState>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, feed_id, title, link, description, authors, published, is_read, created_at
-             FROM feed_item
-             WHERE feed_id = ?1
-             ORDER BY published DESC",
+            "SELECT id, remote_id, is_read, is_starred, remote_is_read, remote_is_starred
+             FROM feed_item WHERE remote_id IS NOT NULL ORDER BY remote_id",
         )?;
-        let rows = stmt.query_map(params![feed_id], |row| {
-            let authors_str: Option<String> = row.get(5)?;
-            let authors = authors_str
-                .map(|s| s.split(", ").map(|a| a.to_string()).collect())
-                .unwrap_or_default();
-
-            Ok(FeedItem {
-                id: row.get(0)?,
-                feed_id: row.get(1)?,
-                title: row.get(2)?,
-                link: row.get(3)?,
-                description: row.get(4)?,
-                authors,
-                published: row.get(6)?,
-                is_read: row.get::<_, i64>(7)? != 0,
-                created_at: row.get(8)?,
+        let states = stmt.query_map([], |row| {
+            Ok(RemoteItemState {
+                item_id: row.get(0)?,
+                remote_id: row.get(1)?,
+                is_read: row.get::<_, i64>(2)? != 0,
+                is_starred: row.get::<_, i64>(3)? != 0,
+                remote_is_read: row.get::<_, Option<i64>>(4)?.unwrap_or_default() != 0,
+                remote_is_starred: row.get::<_, Option<i64>>(5)?.unwrap_or_default() != 0,
             })
         })?;
-        let mut items = Vec::new();
-        for item in rows {
-            items.push(item?);
-        }
-        Ok(items)
+        Ok(states.collect::<rusqlite::Result<_>>()?)
     }
 
-    /// Mark a feed item as read.
-    pub fn mark_item_read(&self, item_id: usize) -> Result<()> {
+    /// Save the post view scroll position of a feed item.
+    pub fn set_item_scroll_position(&self, item_id: usize, scroll_position: u16) -> Result<()> {
         self.conn.execute(
-            "UPDATE feed_item SET is_read = 1 WHERE id = ?1",
-            params![item_id],
+            "UPDATE feed_item SET scroll_position = ?1 WHERE id = ?2",
+            params![scroll_position, item_id],
         )?;
         Ok(())
     }
 }
 
+/// Helpers for the tests of modules using the database.
 #[cfg(test)]
-mod tests {
-    use super::*;
+pub(crate) mod test_util {
+    use super::Db;
+    use crate::client::ParsedFeedItem;
 
-    fn create_test_db() -> Db {
+    /// Open an in-memory database with all its tables.
+    pub(crate) fn test_db() -> Db {
         let db = Db::open(":memory:").expect("failed to create test db");
         db.init_feed_table().expect("failed to init feed table");
         db.init_feed_item_table()
@@ -218,9 +1705,42 @@ mod tests {
         db
     }
 
+    /// Add a feed with a title, returning its ID.
+    pub(crate) fn add_feed(db: &Db, url: &str, title: &str) -> usize {
+        db.add_feed(url, Some(title)).expect("failed to add feed");
+        db.list_feeds()
+            .expect("failed to list feeds")
+            .into_iter()
+            .find(|f| f.url == url)
+            .expect("feed not found")
+            .id
+    }
+
+    /// Add a new item to a feed, returning its ID.
+    pub(crate) fn add_item(db: &Db, feed_id: usize, item: &ParsedFeedItem) -> usize {
+        db.add_feed_item(feed_id, item)
+            .expect("failed to add item")
+            .new_id()
+            .expect("item was not new")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{test_util::test_db, *};
+
+    /// Build a parsed feed item with a title and link.
+    fn parsed_item(title: &str, link: &str) -> ParsedFeedItem {
+        ParsedFeedItem {
+            title: Some(title.to_string()),
+            link: Some(link.to_string()),
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_add_and_list_feeds() {
-        let db = create_test_db();
+        let db = test_db();
 
         // Add a feed
         db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
@@ -235,7 +1755,7 @@ mod tests {
 
     #[test]
     fn test_add_duplicate_feed_fails() {
-        let db = create_test_db();
+        let db = test_db();
 
         db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
             .expect("failed to add feed");
@@ -247,7 +1767,7 @@ mod tests {
 
     #[test]
     fn test_remove_feed() {
-        let db = create_test_db();
+        let db = test_db();
 
         db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
             .expect("failed to add feed");
@@ -265,7 +1785,7 @@ mod tests {
 
     #[test]
     fn test_remove_nonexistent_feed() {
-        let db = create_test_db();
+        let db = test_db();
 
         let deleted = db
             .remove_feed("https://nonexistent.com/feed.xml")
@@ -275,7 +1795,7 @@ mod tests {
 
     #[test]
     fn test_add_feed_items() {
-        let db = create_test_db();
+        let db = test_db();
 
         db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
             .expect("failed to add feed");
@@ -287,14 +1807,15 @@ mod tests {
         let inserted = db
             .add_feed_item(
                 feed_id,
-                Some("Test Item"),
-                Some("https://example.com/item1"),
-                Some("Item description"),
-                Some(&["Author"]),
-                Some(1234567890),
+                &ParsedFeedItem {
+                    description: Some("Item description".to_string()),
+                    authors: vec!["Author".to_string()],
+                    published: Some(1234567890),
+                    ..parsed_item("Test Item", "https://example.com/item1")
+                },
             )
             .expect("failed to add item");
-        assert!(inserted);
+        assert!(matches!(inserted, ItemChange::New(_)));
 
         // Get items for feed
         let items = db.get_feed_items(feed_id).expect("failed to get items");
@@ -305,7 +1826,7 @@ mod tests {
 
     #[test]
     fn test_add_duplicate_feed_item() {
-        let db = create_test_db();
+        let db = test_db();
 
         db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
             .expect("failed to add feed");
@@ -317,27 +1838,30 @@ mod tests {
         let inserted = db
             .add_feed_item(
                 feed_id,
-                Some("Test Item"),
-                Some("https://example.com/item1"),
-                Some("Description"),
-                Some(&["Author"]),
-                Some(1234567890),
+                &ParsedFeedItem {
+                    description: Some("Description".to_string()),
+                    authors: vec!["Author".to_string()],
+                    published: Some(1234567890),
+                    ..parsed_item("Test Item", "https://example.com/item1")
+                },
             )
             .expect("failed to add item");
-        assert!(inserted);
+        assert!(matches!(inserted, ItemChange::New(_)));
 
         // Try to add duplicate (same feed_id and link)
         let inserted = db
             .add_feed_item(
                 feed_id,
-                Some("Different Title"),
-                Some("https://example.com/item1"),
-                Some("Different Description"),
-                Some(&["Author"]),
-                Some(1234567890),
+                &ParsedFeedItem {
+                    description: Some("Different Description".to_string()),
+                    authors: vec!["Author".to_string()],
+                    published: Some(1234567890),
+                    ..parsed_item("Different Title", "https://example.com/item1")
+                },
             )
             .expect("failed to add duplicate item");
-        assert!(!inserted);
+        // Same link without a GUID is the same item, with changed content
+        assert!(matches!(inserted, ItemChange::Updated(_)));
 
         // Verify only one item exists
         let items = db.get_feed_items(feed_id).expect("failed to get items");
@@ -346,7 +1870,7 @@ mod tests {
 
     #[test]
     fn test_cascade_delete_feed_items() {
-        let db = create_test_db();
+        let db = test_db();
 
         db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
             .expect("failed to add feed");
@@ -355,25 +1879,11 @@ mod tests {
         let feed_id = feeds[0].id;
 
         // Add items
-        db.add_feed_item(
-            feed_id,
-            Some("Item 1"),
-            Some("https://example.com/item1"),
-            None,
-            None,
-            None,
-        )
-        .expect("failed to add item");
+        db.add_feed_item(feed_id, &parsed_item("Item 1", "https://example.com/item1"))
+            .expect("failed to add item");
 
-        db.add_feed_item(
-            feed_id,
-            Some("Item 2"),
-            Some("https://example.com/item2"),
-            None,
-            None,
-            None,
-        )
-        .expect("failed to add item");
+        db.add_feed_item(feed_id, &parsed_item("Item 2", "https://example.com/item2"))
+            .expect("failed to add item");
 
         // Verify items exist
         let items = db.get_feed_items(feed_id).expect("failed to get items");
@@ -390,7 +1900,7 @@ mod tests {
 
     #[test]
     fn test_mark_item_read() {
-        let db = create_test_db();
+        let db = test_db();
 
         db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
             .expect("failed to add feed");
@@ -400,11 +1910,7 @@ mod tests {
 
         db.add_feed_item(
             feed_id,
-            Some("Test Item"),
-            Some("https://example.com/item1"),
-            None,
-            None,
-            None,
+            &parsed_item("Test Item", "https://example.com/item1"),
         )
         .expect("failed to add item");
 
@@ -419,4 +1925,848 @@ mod tests {
         let items = db.get_feed_items(feed_id).expect("failed to get items");
         assert!(items[0].is_read);
     }
+
+    #[test]
+    fn test_items_page() {
+        let db = test_db();
+        db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
+            .expect("failed to add feed");
+        let feed_id = db.list_feeds().expect("failed to list feeds")[0].id;
+        let ids: Vec<usize> = (1..=4)
+            .map(|i| {
+                db.add_feed_item(
+                    feed_id,
+                    &parsed_item(
+                        &format!("Item {}", i),
+                        &format!("https://example.com/{}", i),
+                    ),
+                )
+                .expect("failed to add item")
+                .new_id()
+                .expect("item was a duplicate")
+            })
+            .collect();
+        db.set_item_hidden(ids[3], true).unwrap();
+        assert_eq!(db.count_items().unwrap(), 3);
+
+        let page_ids = |page| -> Vec<usize> {
+            db.get_items_page(&ItemSelection::default(), page, 2)
+                .unwrap()
+                .into_iter()
+                .map(|i| i.id)
+                .collect()
+        };
+        assert_eq!(page_ids(ItemPage::After(0)), vec![ids[0], ids[1]]);
+        assert_eq!(page_ids(ItemPage::After(ids[1])), vec![ids[2]]);
+        assert_eq!(page_ids(ItemPage::Before(ids[3])), vec![ids[2], ids[1]]);
+        assert_eq!(page_ids(ItemPage::Latest), vec![ids[2], ids[1]]);
+        assert_eq!(
+            page_ids(ItemPage::Ids(&[ids[2], ids[0]])),
+            vec![ids[0], ids[2]]
+        );
+
+        db.mark_item_read(ids[0]).unwrap();
+        db.set_item_starred(ids[1], true).unwrap();
+        assert_eq!(db.unread_item_ids().unwrap(), vec![ids[1], ids[2]]);
+        assert_eq!(db.starred_item_ids().unwrap(), vec![ids[1]]);
+        db.add_item_tag(ids[2], "later").unwrap();
+        let selection = ItemSelection {
+            tag: Some("later".to_string()),
+            is_read: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(db.select_item_ids(&selection).unwrap(), vec![ids[2]]);
+        db.remove_item_tag(ids[2], "later").unwrap();
+        assert!(db.select_item_ids(&selection).unwrap().is_empty());
+
+        db.mark_item_unread(ids[0]).unwrap();
+        assert_eq!(db.mark_feed_read(feed_id, None).unwrap(), 4);
+        assert!(db.unread_item_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_set_item_scroll_position() {
+        let db = test_db();
+
+        db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
+            .expect("failed to add feed");
+
+        let feeds = db.list_feeds().expect("failed to list feeds");
+        let feed_id = feeds[0].id;
+
+        db.add_feed_item(
+            feed_id,
+            &parsed_item("Test Item", "https://example.com/item1"),
+        )
+        .expect("failed to add item");
+
+        let items = db.get_feed_items(feed_id).expect("failed to get items");
+        assert_eq!(items[0].scroll_position, 0);
+
+        db.set_item_scroll_position(items[0].id, 42)
+            .expect("failed to set scroll position");
+
+        let items = db.get_feed_items(feed_id).expect("failed to get items");
+        assert_eq!(items[0].scroll_position, 42);
+    }
+
+    #[test]
+    fn test_init_adds_missing_columns() {
+        let db = Db::open(":memory:").expect("failed to create test db");
+        db.init_feed_table().expect("failed to init feed table");
+        // A `feed_item` table from before `scroll_position` was added
+        db.conn
+            .execute_batch(
+                "CREATE TABLE feed_item (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    feed_id INTEGER NOT NULL,
+                    title TEXT,
+                    link TEXT,
+                    description TEXT,
+                    authors TEXT,
+                    published INTEGER,
+                    is_read INTEGER NOT NULL DEFAULT 0,
+                    created_at INTEGER NOT NULL,
+                    FOREIGN KEY (feed_id) REFERENCES feed(id) ON DELETE CASCADE,
+                    UNIQUE(feed_id, link)
+                )",
+            )
+            .expect("failed to create old feed_item table");
+
+        db.init_feed_item_table()
+            .expect("failed to migrate feed_item table");
+        db.add_feed("https://example.com/feed.xml", None)
+            .expect("failed to add feed");
+        let feed_id = db.list_feeds().expect("failed to list feeds")[0].id;
+        db.add_feed_item(
+            feed_id,
+            &ParsedFeedItem {
+                link: Some("https://example.com/1".to_string()),
+                ..Default::default()
+            },
+        )
+        .expect("failed to add item");
+        let items = db.get_feed_items(feed_id).expect("failed to get items");
+        assert_eq!(items[0].scroll_position, 0);
+
+        // The old link uniqueness is replaced by GUIDs, adopting the item stored by link
+        let adopted = db
+            .add_feed_item(
+                feed_id,
+                &ParsedFeedItem {
+                    guid: Some("1".to_string()),
+                    link: Some("https://example.com/1".to_string()),
+                    ..Default::default()
+                },
+            )
+            .expect("failed to add item");
+        assert_eq!(adopted, ItemChange::Unchanged);
+        let reused = db
+            .add_feed_item(
+                feed_id,
+                &ParsedFeedItem {
+                    guid: Some("2".to_string()),
+                    link: Some("https://example.com/1".to_string()),
+                    ..Default::default()
+                },
+            )
+            .expect("failed to add item");
+        assert!(matches!(reused, ItemChange::New(_)));
+    }
+
+    #[test]
+    fn test_failed_item_identity_migration() {
+        let db = Db::open(":memory:").expect("failed to create test db");
+        db.init_feed_table().expect("failed to init feed table");
+        db.add_feed("https://example.com/feed.xml", None)
+            .expect("failed to add feed");
+        // Two items of a `feed_item` table from before `item_key` with the same GUID, which
+        // can't both be kept
+        db.conn
+            .execute_batch(
+                "CREATE TABLE feed_item (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    feed_id INTEGER NOT NULL,
+                    title TEXT,
+                    link TEXT,
+                    description TEXT,
+                    authors TEXT,
+                    published INTEGER,
+                    is_read INTEGER NOT NULL DEFAULT 0,
+                    created_at INTEGER NOT NULL,
+                    guid TEXT,
+                    FOREIGN KEY (feed_id) REFERENCES feed(id) ON DELETE CASCADE,
+                    UNIQUE(feed_id, link)
+                );
+                INSERT INTO feed_item (feed_id, link, created_at, guid)
+                    VALUES (1, 'https://example.com/1', 0, 'a'),
+                           (1, 'https://example.com/2', 0, 'a');",
+            )
+            .expect("failed to create old feed_item table");
+
+        assert!(db.init_feed_item_table().is_err());
+        // The rebuild is rolled back, and foreign keys are enforced again
+        assert!(db.conn.is_autocommit());
+        let foreign_keys: i64 = db
+            .conn
+            .query_row("PRAGMA foreign_keys", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(foreign_keys, 1);
+        let count: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM feed_item", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+        assert!(
+            db.conn
+                .execute(
+                    "INSERT INTO feed_item (feed_id, created_at) VALUES (42, 0)",
+                    []
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_items_identified_by_guid() {
+        let db = test_db();
+        let feed_id = add_feed_with_items(&db, "https://example.com/feed.xml", &[]);
+        let entry = ParsedFeedItem {
+            guid: Some("tag:example.com,2024:1".to_string()),
+            updated: Some(100),
+            ..parsed_item("Post", "https://example.com/1?session=a")
+        };
+        let id = db.add_feed_item(feed_id, &entry).unwrap().new_id().unwrap();
+
+        // A rotated link is still the same item
+        let rotated = ParsedFeedItem {
+            link: Some("https://example.com/1?session=b".to_string()),
+            ..entry.clone()
+        };
+        assert_eq!(
+            db.add_feed_item(feed_id, &rotated).unwrap(),
+            ItemChange::Unchanged
+        );
+
+        // A reused link with another GUID is a new item
+        let reused = ParsedFeedItem {
+            guid: Some("tag:example.com,2024:2".to_string()),
+            ..entry.clone()
+        };
+        assert!(
+            db.add_feed_item(feed_id, &reused)
+                .unwrap()
+                .new_id()
+                .is_some()
+        );
+        assert_eq!(db.get_feed_items(feed_id).unwrap().len(), 2);
+
+        // A correction updates the stored item and marks it until read
+        db.mark_item_read(id).unwrap();
+        let corrected = ParsedFeedItem {
+            description: Some("Corrected".to_string()),
+            updated: Some(200),
+            ..entry.clone()
+        };
+        assert_eq!(
+            db.add_feed_item(feed_id, &corrected).unwrap(),
+            ItemChange::Updated(id)
+        );
+        let item = db.get_feed_item(id).unwrap().unwrap();
+        assert!(item.is_updated);
+        assert_eq!(item.description.as_deref(), Some("Corrected"));
+        assert_eq!(
+            db.add_feed_item(feed_id, &corrected).unwrap(),
+            ItemChange::Unchanged
+        );
+
+        db.mark_item_read(id).unwrap();
+        assert!(!db.get_feed_item(id).unwrap().unwrap().is_updated);
+    }
+
+    /// Add a feed with the given items as `(title, author, published, is_read)` and return
+    /// its ID.
+    fn add_feed_with_items(db: &Db, url: &str, items: &[(&str, &str, i64, bool)]) -> usize {
+        db.add_feed(url, Some(url)).expect("failed to add feed");
+        let feed_id = db
+            .list_feeds()
+            .expect("failed to list feeds")
+            .into_iter()
+            .find(|f| f.url == url)
+            .expect("feed not found")
+            .id;
+        for (title, author, published, is_read) in items {
+            db.add_feed_item(
+                feed_id,
+                &ParsedFeedItem {
+                    authors: vec![author.to_string()],
+                    published: Some(*published),
+                    ..parsed_item(title, &format!("{}/{}", url, title))
+                },
+            )
+            .expect("failed to add item");
+            if *is_read {
+                let items = db.get_feed_items(feed_id).expect("failed to get items");
+                let item = items.iter().find(|i| i.title.as_deref() == Some(title));
+                db.mark_item_read(item.unwrap().id)
+                    .expect("failed to mark item read");
+            }
+        }
+        feed_id
+    }
+
+    #[test]
+    fn test_sort_feed_items() {
+        let db = test_db();
+        let feed_id = add_feed_with_items(
+            &db,
+            "https://example.com/feed.xml",
+            &[
+                ("Bravo", "Zed", 200, true),
+                ("alpha", "Amy", 100, false),
+                ("Charlie", "Max", 300, true),
+            ],
+        );
+
+        let titles = |sort: ItemSort| -> Vec<String> {
+            let query = ItemQuery {
+                sort,
+                ..Default::default()
+            };
+            db.get_feed_items_with(feed_id, &query)
+                .expect("failed to get items")
+                .into_iter()
+                .filter_map(|i| i.title)
+                .collect()
+        };
+
+        assert_eq!(
+            titles(ItemSort::DateDesc),
+            vec!["Charlie", "Bravo", "alpha"]
+        );
+        assert_eq!(titles(ItemSort::DateAsc), vec!["alpha", "Bravo", "Charlie"]);
+        assert_eq!(titles(ItemSort::Title), vec!["alpha", "Bravo", "Charlie"]);
+        assert_eq!(titles(ItemSort::Author), vec!["alpha", "Charlie", "Bravo"]);
+        assert_eq!(
+            titles(ItemSort::UnreadFirst),
+            vec!["alpha", "Charlie", "Bravo"]
+        );
+    }
+
+    #[test]
+    fn test_filter_feed_items() {
+        let db = test_db();
+        let feed_id = add_feed_with_items(
+            &db,
+            "https://example.com/feed.xml",
+            &[
+                ("Old", "Amy", 100, true),
+                ("Middle", "Bob", 200, false),
+                ("New", "Amy Smith", 300, false),
+            ],
+        );
+        let items = db.get_feed_items(feed_id).expect("failed to get items");
+        let middle = items.iter().find(|i| i.title.as_deref() == Some("Middle"));
+        db.set_item_starred(middle.unwrap().id, true)
+            .expect("failed to star item");
+
+        let titles = |filter: ItemFilter| -> Vec<String> {
+            let query = ItemQuery {
+                filter,
+                ..Default::default()
+            };
+            db.get_feed_items_with(feed_id, &query)
+                .expect("failed to get items")
+                .into_iter()
+                .filter_map(|i| i.title)
+                .collect()
+        };
+
+        let unread = ItemFilter {
+            unread_only: true,
+            ..Default::default()
+        };
+        assert_eq!(titles(unread), vec!["New", "Middle"]);
+
+        let starred = ItemFilter {
+            starred_only: true,
+            ..Default::default()
+        };
+        assert_eq!(titles(starred), vec!["Middle"]);
+
+        let by_author = ItemFilter {
+            author: Some("amy".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(titles(by_author), vec!["New", "Old"]);
+
+        // Wildcards in the author match literally
+        for author in ["%", "A_y", "\\"] {
+            let by_author = ItemFilter {
+                author: Some(author.to_string()),
+                ..Default::default()
+            };
+            assert!(titles(by_author).is_empty(), "{author} matched");
+        }
+
+        let date_range = ItemFilter {
+            since: Some(150),
+            until: Some(250),
+            ..Default::default()
+        };
+        assert_eq!(titles(date_range), vec!["Middle"]);
+    }
+
+    #[test]
+    fn test_update_feed() {
+        let db = test_db();
+        let feed_id = add_feed_with_items(
+            &db,
+            "https://old.example.com/feed.xml",
+            &[("Post", "Amy", 100, false)],
+        );
+        add_feed_with_items(&db, "https://other.example.com/feed.xml", &[]);
+
+        let settings = FeedSettings {
+            url: "https://new.example.com/feed.xml".to_string(),
+            custom_title: Some("My Feed".to_string()),
+            is_paused: true,
+            notify: true,
+        };
+        db.update_feed(feed_id, &settings)
+            .expect("failed to update feed");
+        assert_eq!(db.get_feed_settings(feed_id).unwrap(), settings);
+        assert_eq!(db.get_feed_items(feed_id).unwrap().len(), 1);
+
+        // The custom title survives syncs and is shown instead of the feed's title
+        db.update_feed_metadata(feed_id, Some("Feed Title"), &FeedMetadata::default())
+            .expect("failed to update metadata");
+        let feed = db
+            .list_feeds()
+            .unwrap()
+            .into_iter()
+            .find(|f| f.id == feed_id);
+        let feed = feed.expect("feed not found");
+        assert_eq!(feed.title.as_deref(), Some("My Feed"));
+        assert!(feed.is_paused);
+
+        let taken = FeedSettings {
+            url: "https://other.example.com/feed.xml".to_string(),
+            ..settings
+        };
+        assert!(db.update_feed(feed_id, &taken).is_err());
+        assert!(
+            !db.set_feed_url(feed_id, "https://other.example.com/feed.xml")
+                .unwrap()
+        );
+        assert!(
+            db.set_feed_url(feed_id, "https://moved.example.com/feed.xml")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_gone_feed_and_log() {
+        let db = test_db();
+        let feed_id = add_feed_with_items(&db, "https://example.com/feed.xml", &[]);
+        db.set_feed_gone(feed_id, true)
+            .expect("failed to mark feed gone");
+        db.add_feed_log(feed_id, "Feed is gone")
+            .expect("failed to add log entry");
+        assert!(db.list_feeds().unwrap()[0].is_gone);
+
+        // Editing without changing the URL keeps the feed gone
+        let mut settings = db.get_feed_settings(feed_id).unwrap();
+        db.update_feed(feed_id, &settings).unwrap();
+        assert!(db.list_feeds().unwrap()[0].is_gone);
+
+        settings.url = "https://example.com/new.xml".to_string();
+        db.update_feed(feed_id, &settings).unwrap();
+        assert!(!db.list_feeds().unwrap()[0].is_gone);
+
+        let log = db.get_feed_log(feed_id).expect("failed to get log");
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].message, "Feed is gone");
+    }
+
+    #[test]
+    fn test_folders() {
+        let db = test_db();
+        let feed_id = add_feed_with_items(&db, "https://example.com/feed.xml", &[]);
+        let rust = db
+            .create_folder_path("Tech / Rust")
+            .expect("failed to create folders")
+            .expect("no folder for path");
+        assert_eq!(db.create_folder_path("Tech/Rust").unwrap(), Some(rust));
+        assert_eq!(db.create_folder_path("").unwrap(), None);
+        assert_eq!(db.folder_path(rust).unwrap(), "Tech/Rust");
+
+        db.set_feed_folder(feed_id, Some(rust))
+            .expect("failed to move feed");
+        assert_eq!(db.list_feeds().unwrap()[0].folder_id, Some(rust));
+
+        // Deleting a folder deletes its subfolders and keeps their feeds
+        let tech = db.create_folder("Tech", None).unwrap();
+        assert!(db.delete_folder(tech).unwrap());
+        assert!(db.list_folders().unwrap().is_empty());
+        assert_eq!(db.list_feeds().unwrap()[0].folder_id, None);
+    }
+
+    #[test]
+    fn test_search_matches_wildcards_literally() {
+        let db = test_db();
+        add_feed_with_items(
+            &db,
+            "https://example.com/feed.xml",
+            &[
+                ("100% done", "Amy", 100, false),
+                ("1000 points", "Bob", 200, false),
+                ("a_b", "Amy", 300, false),
+                ("axb", "Bob", 400, false),
+            ],
+        );
+        let titles = |text: &str| -> Vec<String> {
+            db.search_items(text, &ItemQuery::default())
+                .expect("failed to search items")
+                .into_iter()
+                .filter_map(|i| i.title)
+                .collect()
+        };
+
+        assert_eq!(titles("100%"), vec!["100% done"]);
+        assert_eq!(titles("a_b"), vec!["a_b"]);
+        assert_eq!(titles("%"), vec!["100% done"]);
+        assert!(titles("\\").is_empty());
+    }
+
+    #[test]
+    fn test_item_categories() {
+        let db = test_db();
+        let feed_id = add_feed_with_items(&db, "https://example.com/feed.xml", &[]);
+        let categorized = |title: &str, categories: &[&str]| ParsedFeedItem {
+            categories: categories.iter().map(|c| c.to_string()).collect(),
+            ..parsed_item(title, &format!("https://example.com/{}", title))
+        };
+        db.add_feed_item(feed_id, &categorized("Tokio", &["Rust", "async"]))
+            .expect("failed to add item");
+        db.add_feed_item(feed_id, &categorized("Goroutines", &["Go", "async"]))
+            .expect("failed to add item");
+
+        let titles = |items: Vec<FeedItem>| -> Vec<String> {
+            items.into_iter().filter_map(|i| i.title).collect()
+        };
+        assert_eq!(titles(db.items_by_category("rust").unwrap()), vec!["Tokio"]);
+        assert_eq!(db.items_by_category("async").unwrap().len(), 2);
+        let item = &db.items_by_category("go").unwrap()[0];
+        assert_eq!(item.categories, vec!["Go", "async"]);
+
+        // Categories follow the feed
+        db.add_feed_item(feed_id, &categorized("Tokio", &["Rust"]))
+            .expect("failed to update item");
+        assert_eq!(
+            titles(db.items_by_category("async").unwrap()),
+            vec!["Goroutines"]
+        );
+
+        let query = ItemQuery {
+            filter: ItemFilter {
+                category: Some("RUST".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            titles(db.search_items("tok", &query).unwrap()),
+            vec!["Tokio"]
+        );
+        assert!(db.search_items("goroutines", &query).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sort_and_filter_feeds() {
+        let db = test_db();
+        add_feed_with_items(&db, "https://b.example.com", &[("One", "A", 100, false)]);
+        add_feed_with_items(
+            &db,
+            "https://a.example.com",
+            &[("One", "A", 50, false), ("Two", "A", 60, false)],
+        );
+        add_feed_with_items(&db, "https://c.example.com", &[("One", "A", 300, true)]);
+
+        let urls = |query: FeedQuery| -> Vec<String> {
+            db.list_feeds_with(&query)
+                .expect("failed to list feeds")
+                .into_iter()
+                .map(|f| f.url)
+                .collect()
+        };
+
+        assert_eq!(
+            urls(FeedQuery::default()),
+            vec![
+                "https://a.example.com",
+                "https://b.example.com",
+                "https://c.example.com"
+            ]
+        );
+        assert_eq!(
+            urls(FeedQuery {
+                sort: FeedSort::UnreadCount,
+                ..Default::default()
+            }),
+            vec![
+                "https://a.example.com",
+                "https://b.example.com",
+                "https://c.example.com"
+            ]
+        );
+        assert_eq!(
+            urls(FeedQuery {
+                sort: FeedSort::LastUpdated,
+                ..Default::default()
+            }),
+            vec![
+                "https://c.example.com",
+                "https://b.example.com",
+                "https://a.example.com"
+            ]
+        );
+        assert_eq!(
+            urls(FeedQuery {
+                unread_only: true,
+                ..Default::default()
+            }),
+            vec!["https://a.example.com", "https://b.example.com"]
+        );
+
+        let feeds = db.list_feeds().expect("failed to list feeds");
+        assert_eq!(feeds[0].unread_count, 2);
+        assert_eq!(feeds[0].last_updated, Some(60));
+    }
+
+    #[test]
+    fn test_parse_item_filter() {
+        let filter =
+            ItemFilter::parse("unread author:amy category:rust since:2024-01-02 until:2024-01-02")
+                .expect("failed to parse filter");
+        assert!(filter.unread_only);
+        assert!(!filter.starred_only);
+        assert_eq!(filter.author.as_deref(), Some("amy"));
+        assert_eq!(filter.category.as_deref(), Some("rust"));
+        assert_eq!(filter.since, Some(1704153600));
+        assert_eq!(filter.until, Some(1704153600 + 86399));
+        assert_eq!(
+            filter.to_string(),
+            "unread author:amy category:rust since:2024-01-02 until:2024-01-02"
+        );
+
+        assert!(ItemFilter::parse("").unwrap().is_empty());
+        assert!(ItemFilter::parse("bogus").is_err());
+        assert!(ItemFilter::parse("since:2024-13-01").is_err());
+    }
+
+    #[test]
+    fn test_cross_feed_duplicates() {
+        let db = test_db();
+        let a = add_feed_with_items(&db, "https://a.example.com", &[]);
+        let b = add_feed_with_items(&db, "https://b.example.com", &[]);
+
+        let original = db
+            .add_feed_item(
+                a,
+                &parsed_item("Post", "https://example.com/post?utm_source=a"),
+            )
+            .unwrap()
+            .new_id()
+            .unwrap();
+        let duplicate = db
+            .add_feed_item(b, &parsed_item("Post", "https://example.com/post#top"))
+            .unwrap()
+            .new_id()
+            .unwrap();
+        let other = db
+            .add_feed_item(b, &parsed_item("Other", "https://example.com/other"))
+            .unwrap()
+            .new_id()
+            .unwrap();
+
+        let duplicate_item = db.get_feed_item(duplicate).unwrap().unwrap();
+        assert_eq!(duplicate_item.duplicate_of, Some(original));
+        assert_eq!(db.get_feed_item(other).unwrap().unwrap().duplicate_of, None);
+
+        // Each feed still shows its own copy, but the aggregate view shows it once
+        assert_eq!(db.get_feed_items(b).unwrap().len(), 2);
+        let all = db.get_all_items_with(&ItemQuery::default()).unwrap();
+        let ids: Vec<usize> = all.iter().map(|i| i.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&original) && ids.contains(&other));
+
+        // Reading either copy reads both
+        db.mark_item_read(duplicate).unwrap();
+        assert!(db.get_feed_item(original).unwrap().unwrap().is_read);
+        assert!(!db.get_feed_item(other).unwrap().unwrap().is_read);
+
+        // And so does marking either copy unread
+        db.mark_item_unread(original).unwrap();
+        assert!(!db.get_feed_item(duplicate).unwrap().unwrap().is_read);
+
+        // Marking a feed read reads the copies in other feeds, but counts its own items only
+        assert_eq!(db.mark_feed_read(b, None).unwrap(), 2);
+        assert!(db.get_feed_item(original).unwrap().unwrap().is_read);
+        db.mark_item_unread(duplicate).unwrap();
+        assert_eq!(db.mark_feed_read(a, None).unwrap(), 1);
+        assert!(db.get_feed_item(duplicate).unwrap().unwrap().is_read);
+
+        // A copy arriving after the article was read starts out read
+        let c = add_feed_with_items(&db, "https://c.example.com", &[]);
+        let late = db
+            .add_feed_item(c, &parsed_item("Post", "https://example.com/post"))
+            .unwrap()
+            .new_id()
+            .unwrap();
+        let late_item = db.get_feed_item(late).unwrap().unwrap();
+        assert_eq!(late_item.duplicate_of, Some(original));
+        assert!(late_item.is_read);
+    }
+
+    #[test]
+    fn test_duplicates_by_guid_and_content() {
+        let db = test_db();
+        let a = add_feed_with_items(&db, "https://a.example.com", &[]);
+        let b = add_feed_with_items(&db, "https://b.example.com", &[]);
+        let description = "A description long enough to be hashed for duplicate detection.";
+
+        let original = db
+            .add_feed_item(
+                a,
+                &ParsedFeedItem {
+                    guid: Some("urn:uuid:1234".to_string()),
+                    description: Some(description.to_string()),
+                    ..parsed_item("Post", "https://a.example.com/post")
+                },
+            )
+            .unwrap()
+            .new_id()
+            .unwrap();
+        let by_guid = db
+            .add_feed_item(
+                b,
+                &ParsedFeedItem {
+                    guid: Some("urn:uuid:1234".to_string()),
+                    ..parsed_item("Mirrored", "https://mirror.example.com/1")
+                },
+            )
+            .unwrap()
+            .new_id()
+            .unwrap();
+        let by_content = db
+            .add_feed_item(
+                b,
+                &ParsedFeedItem {
+                    description: Some(format!("<p>{}</p>", description)),
+                    ..parsed_item("Post", "https://mirror.example.com/2")
+                },
+            )
+            .unwrap()
+            .new_id()
+            .unwrap();
+        // Feed-local GUIDs aren't compared across feeds
+        let local_guid = db
+            .add_feed_item(
+                b,
+                &ParsedFeedItem {
+                    guid: Some("1".to_string()),
+                    ..parsed_item("Unrelated", "https://b.example.com/1")
+                },
+            )
+            .unwrap()
+            .new_id()
+            .unwrap();
+        db.add_feed_item(
+            a,
+            &ParsedFeedItem {
+                guid: Some("1".to_string()),
+                ..parsed_item("Also unrelated", "https://a.example.com/1")
+            },
+        )
+        .unwrap();
+
+        for id in [by_guid, by_content] {
+            assert_eq!(
+                db.get_feed_item(id).unwrap().unwrap().duplicate_of,
+                Some(original)
+            );
+        }
+        assert_eq!(
+            db.get_feed_item(local_guid).unwrap().unwrap().duplicate_of,
+            None
+        );
+    }
+
+    #[test]
+    fn test_dedupe_items_without_links() {
+        let db = test_db();
+        let feed_id = add_feed_with_items(&db, "https://example.com/feed.xml", &[]);
+        let item = ParsedFeedItem {
+            guid: Some("post-1".to_string()),
+            title: Some("No link".to_string()),
+            ..Default::default()
+        };
+
+        assert!(db.add_feed_item(feed_id, &item).unwrap().new_id().is_some());
+        assert_eq!(
+            db.add_feed_item(feed_id, &item).unwrap(),
+            ItemChange::Unchanged
+        );
+
+        let untitled = ParsedFeedItem {
+            title: Some("Untitled".to_string()),
+            ..Default::default()
+        };
+        assert!(
+            db.add_feed_item(feed_id, &untitled)
+                .unwrap()
+                .new_id()
+                .is_some()
+        );
+        assert_eq!(
+            db.add_feed_item(feed_id, &untitled).unwrap(),
+            ItemChange::Unchanged
+        );
+        assert_eq!(db.get_feed_items(feed_id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_feed_metadata() {
+        let db = test_db();
+        let feed_id = add_feed_with_items(&db, "https://example.com/feed.xml", &[]);
+        assert_eq!(
+            db.get_feed_metadata(feed_id).unwrap(),
+            FeedMetadata::default()
+        );
+
+        let metadata = FeedMetadata {
+            site_link: Some("https://example.com/".to_string()),
+            description: Some("Posts about examples".to_string()),
+            language: Some("en".to_string()),
+            authors: vec!["Jane Doe".to_string(), "John Doe".to_string()],
+            categories: vec!["rust".to_string()],
+            updated: Some(100),
+            ..Default::default()
+        };
+        db.update_feed_metadata(feed_id, None, &metadata).unwrap();
+        assert_eq!(db.get_feed_metadata(feed_id).unwrap(), metadata);
+        // A feed without a title keeps the stored one
+        assert_eq!(
+            db.list_feeds().unwrap()[0].title.as_deref(),
+            Some("https://example.com/feed.xml")
+        );
+
+        db.update_feed_metadata(feed_id, Some("Example"), &metadata)
+            .unwrap();
+        assert_eq!(
+            db.list_feeds().unwrap()[0].title.as_deref(),
+            Some("Example")
+        );
+    }
 }
@@ -0,0 +1,104 @@
+/*!
+Helpers for recognizing the same article across feeds.
+
+* Links are normalized to a canonical URL by dropping tracking query parameters and
+  fragments, so the same article shared through different channels compares equal.
+* Content is reduced to a hash of its normalized text, for items without usable links.
+*/
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Query parameters that only track where a visitor came from.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "ref_src", "_hsenc",
+    "_hsmi",
+];
+
+/// Minimum length of normalized text to hash. Shorter text (e.g. a bare title like "Weekly
+/// update") is too likely to be shared by unrelated items.
+const MIN_HASHED_TEXT_LEN: usize = 64;
+
+/// Matches HTML tags.
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// Normalize a link to its canonical URL: tracking parameters (`utm_*` and friends) and the
+/// fragment are removed. Returns `None` for links that aren't absolute URLs.
+pub fn canonical_url(link: &str) -> Option<String> {
+    let mut url = url::Url::parse(link.trim()).ok()?;
+    url.set_fragment(None);
+
+    let params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !is_tracking_param(key))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    if params.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(params);
+    }
+    Some(url.to_string())
+}
+
+/// Whether a query parameter is used for tracking.
+fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str())
+}
+
+/// Hash the text of an item's title and HTML description, ignoring markup, case and
+/// whitespace. Returns `None` when there's too little text to tell items apart.
+pub fn content_hash(title: Option<&str>, description: Option<&str>) -> Option<String> {
+    let description = description.map(|d| TAG_RE.replace_all(d, " "));
+    let text = [title, description.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(|t| html_escape::decode_html_entities(t).to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if normalized.len() < MIN_HASHED_TEXT_LEN {
+        return None;
+    }
+    Some(format!("{:016x}", fnv1a_hash(normalized.as_bytes())))
+}
+
+/// 64-bit FNV-1a hash, used where hashes are persisted and so must be stable across runs and
+/// Rust versions.
+pub(crate) fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_url_strips_tracking() {
+        assert_eq!(
+            canonical_url("https://Example.com/post?utm_source=rss&id=3&fbclid=x#comments"),
+            Some("https://example.com/post?id=3".to_string())
+        );
+        assert_eq!(
+            canonical_url("https://example.com/post?utm_medium=feed"),
+            Some("https://example.com/post".to_string())
+        );
+        assert_eq!(canonical_url("/relative/post"), None);
+    }
+
+    #[test]
+    fn test_content_hash_ignores_markup() {
+        let description = "<p>This is a long enough description of an article to be hashed.</p>";
+        let a = content_hash(Some("Title"), Some(description));
+        let b = content_hash(
+            Some("title"),
+            Some("This is a  long enough description of an <b>article</b> to be hashed."),
+        );
+        assert!(a.is_some());
+        assert_eq!(a, b);
+        assert_ne!(a, content_hash(Some("Other"), Some(description)));
+        assert_eq!(content_hash(Some("Weekly update"), None), None);
+    }
+}
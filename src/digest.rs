@@ -0,0 +1,533 @@
+/*!
+Email digests of unread items, rendered from templates into an HTML and plain text email.
+
+The built-in templates can be replaced by [MiniJinja](https://docs.rs/minijinja) templates set
+in the config. Templates are given:
+
+* `subject`: the rendered subject, for the body templates.
+* `since`: when the digest starts, as `YYYY-MM-DD HH:MM` in local time.
+* `count`: the number of items.
+* `groups`: the items grouped by feed or tag, each with a `name` and `items`. Each item has a
+  `title`, `link`, `feed`, `authors`, `published` date and plain text `summary`.
+
+Digests are written to an `.eml` file, appended to an mbox file, or sent with an SMTP server.
+*/
+use anyhow::{Context, Result};
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::MultiPart,
+    transport::smtp::authentication::Credentials,
+};
+use minijinja::{Environment, Value, context};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write as _,
+    path::Path,
+};
+use time::{format_description::BorrowedFormatItem, macros::format_description};
+
+use crate::{
+    clock,
+    config::{DigestConfig, SmtpConfig},
+    db::{Db, FeedItem, ItemFilter, ItemQuery},
+};
+
+/// The built-in HTML body template.
+const HTML_TEMPLATE: &str = include_str!("digest/digest.html");
+
+/// The built-in plain text body template.
+const TEXT_TEMPLATE: &str = include_str!("digest/digest.txt");
+
+/// Format of the date of mbox separator lines, the one of C's `asctime`.
+const ASCTIME: &[BorrowedFormatItem] = format_description!(
+    "[weekday repr:short] [month repr:short] [day padding:space] [hour]:[minute]:[second] [year]"
+);
+
+/// Maximum length of item summaries, in characters.
+const SUMMARY_LENGTH: usize = 300;
+
+/// How to group the items of a digest.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum DigestGroup {
+    /// One group per feed.
+    #[default]
+    Feed,
+    /// One group per item tag. Items with several tags appear in each of their groups.
+    Tag,
+}
+
+/// How the connection to an SMTP server is secured.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SmtpSecurity {
+    /// TLS from the start, on port 465 by default.
+    Tls,
+    /// Upgrade to TLS with STARTTLS, on port 587 by default.
+    #[default]
+    Starttls,
+    /// No encryption, on port 25 by default. Only for local servers.
+    None,
+}
+
+/// An item of a digest.
+#[derive(Debug, Serialize)]
+struct DigestItem {
+    title: String,
+    link: Option<String>,
+    feed: String,
+    authors: String,
+    published: Option<String>,
+    summary: String,
+}
+
+/// A group of items of a digest.
+#[derive(Debug, Serialize)]
+struct DigestSection {
+    name: String,
+    items: Vec<DigestItem>,
+}
+
+/// The unread items to send in a digest.
+#[derive(Debug, Serialize)]
+pub struct Digest {
+    since: String,
+    count: usize,
+    groups: Vec<DigestSection>,
+    #[serde(skip)]
+    item_ids: Vec<usize>,
+}
+
+impl Digest {
+    /// Collect the unread items published since a time (Unix timestamp), newest first.
+    pub fn collect(db: &Db, since: i64, group_by: DigestGroup) -> Result<Self> {
+        let query = ItemQuery {
+            filter: ItemFilter {
+                unread_only: true,
+                since: Some(since),
+                ..ItemFilter::default()
+            },
+            ..ItemQuery::default()
+        };
+        let items = db.get_all_items_with(&query)?;
+        let feed_titles: BTreeMap<usize, String> = db
+            .list_feeds()?
+            .into_iter()
+            .map(|feed| (feed.id, feed.title.unwrap_or(feed.url)))
+            .collect();
+        let feed_title =
+            |item: &FeedItem| feed_titles.get(&item.feed_id).cloned().unwrap_or_default();
+
+        // Group names sort case-insensitively, with untagged items last
+        let mut groups: BTreeMap<(bool, String, String), Vec<DigestItem>> = BTreeMap::new();
+        for item in &items {
+            let names = match group_by {
+                DigestGroup::Feed => vec![Some(feed_title(item))],
+                DigestGroup::Tag if item.tags.is_empty() => vec![None],
+                DigestGroup::Tag => item.tags.iter().cloned().map(Some).collect(),
+            };
+            for name in names {
+                let key = match name {
+                    Some(name) => (false, name.to_lowercase(), name),
+                    None => (true, String::new(), "Untagged".to_string()),
+                };
+                groups
+                    .entry(key)
+                    .or_default()
+                    .push(digest_item(item, feed_title(item)));
+            }
+        }
+
+        Ok(Self {
+            since: clock::format_local(since),
+            count: items.len(),
+            groups: groups
+                .into_iter()
+                .map(|((_, _, name), items)| DigestSection { name, items })
+                .collect(),
+            item_ids: items.iter().map(|item| item.id).collect(),
+        })
+    }
+
+    /// Whether there are no items.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Number of items.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// IDs of the items.
+    pub fn item_ids(&self) -> &[usize] {
+        &self.item_ids
+    }
+}
+
+/// An item as shown in a digest.
+fn digest_item(item: &FeedItem, feed: String) -> DigestItem {
+    let summary = item
+        .description
+        .as_deref()
+        .and_then(|html| html2text::from_read(html.as_bytes(), usize::MAX).ok())
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .unwrap_or_default();
+    let summary = match summary.char_indices().nth(SUMMARY_LENGTH) {
+        Some((end, _)) => format!("{}…", summary[..end].trim_end()),
+        None => summary,
+    };
+    DigestItem {
+        title: item
+            .title
+            .clone()
+            .unwrap_or_else(|| "(untitled)".to_string()),
+        link: item.link.clone(),
+        feed,
+        authors: item.authors.join(", "),
+        published: item.published.map(clock::format_local),
+        summary,
+    }
+}
+
+/// Parse a duration such as `90m`, `24h`, `7d` or `2w` into seconds.
+pub fn parse_duration(duration: &str) -> Result<i64> {
+    let duration = duration.trim();
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (number, unit) = duration.split_at(split);
+    let number: i64 = number
+        .parse()
+        .with_context(|| format!("invalid duration `{}`, expected e.g. 24h", duration))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!("invalid duration unit `{}`, expected s, m, h, d or w", unit),
+    };
+    Ok(number * unit_secs)
+}
+
+/// The subject and bodies of a digest.
+#[derive(Debug)]
+pub struct RenderedDigest {
+    /// Subject of the email.
+    pub subject: String,
+    /// HTML body.
+    pub html: String,
+    /// Plain text body.
+    pub text: String,
+}
+
+impl RenderedDigest {
+    /// Render a digest with the templates of the config.
+    pub fn new(config: &DigestConfig, digest: &Digest) -> Result<Self> {
+        let load = |path: &Option<std::path::PathBuf>, default: &str| match path {
+            Some(path) => fs::read_to_string(path)
+                .with_context(|| format!("failed to read template at {}", path.display())),
+            None => Ok(default.to_string()),
+        };
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        // HTML escaping follows the template names
+        env.add_template_owned("subject.txt", config.subject.clone())
+            .context("invalid subject template")?;
+        env.add_template_owned("digest.html", load(&config.html_template, HTML_TEMPLATE)?)
+            .context("invalid HTML template")?;
+        env.add_template_owned("digest.txt", load(&config.text_template, TEXT_TEMPLATE)?)
+            .context("invalid text template")?;
+
+        let render = |name: &str, ctx| {
+            env.get_template(name)?
+                .render(ctx)
+                .with_context(|| format!("failed to render {}", name))
+        };
+        let values = Value::from_serialize(digest);
+        let subject = render("subject.txt", values.clone())?;
+        let ctx = context! { subject => subject.trim(), ..values };
+        Ok(Self {
+            html: render("digest.html", ctx.clone())?,
+            text: render("digest.txt", ctx)?,
+            subject: subject.trim().to_string(),
+        })
+    }
+
+    /// The digest as a multipart email from and to the addresses of the config.
+    pub fn message(&self, config: &DigestConfig) -> Result<Message> {
+        anyhow::ensure!(
+            !config.to.is_empty(),
+            "set `to` under [digest] in the config to the recipients of digests"
+        );
+        let mut builder = Message::builder()
+            .from(config.from.parse().context("invalid `from` address")?)
+            .subject(&self.subject);
+        for to in &config.to {
+            builder = builder.to(to
+                .parse()
+                .with_context(|| format!("invalid `to` address: {}", to))?);
+        }
+        Ok(builder.multipart(MultiPart::alternative_plain_html(
+            self.text.clone(),
+            self.html.clone(),
+        ))?)
+    }
+}
+
+/// Write an email to an `.eml` file.
+pub fn write_eml(path: &Path, message: &Message) -> Result<()> {
+    fs::write(path, message.formatted())
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Append an email to an mbox file, escaping lines starting with `From ` (mboxrd).
+pub fn append_mbox(path: &Path, message: &Message) -> Result<()> {
+    let formatted = message.formatted();
+    let formatted = String::from_utf8_lossy(&formatted);
+    let mut mbox = format!("From ferrofeed {}\n", clock::now_local().format(ASCTIME)?);
+    for line in formatted.lines() {
+        if line.trim_start_matches('>').starts_with("From ") {
+            mbox.push('>');
+        }
+        mbox.push_str(line);
+        mbox.push('\n');
+    }
+    mbox.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    file.write_all(mbox.as_bytes())?;
+    Ok(())
+}
+
+/// Send an email with an SMTP server.
+pub async fn send(smtp: &SmtpConfig, message: Message) -> Result<()> {
+    type Transport = AsyncSmtpTransport<Tokio1Executor>;
+    let mut builder = match smtp.security {
+        SmtpSecurity::Tls => Transport::relay(&smtp.host)?,
+        SmtpSecurity::Starttls => Transport::starttls_relay(&smtp.host)?,
+        SmtpSecurity::None => Transport::builder_dangerous(&smtp.host),
+    };
+    if let Some(port) = smtp.port {
+        builder = builder.port(port);
+    }
+    if let Some(username) = &smtp.username {
+        let password = smtp.password.as_ref().map(|p| p.resolve()).transpose()?;
+        builder = builder.credentials(Credentials::new(
+            username.clone(),
+            password.unwrap_or_default(),
+        ));
+    }
+    builder
+        .build()
+        .send(message)
+        .await
+        .with_context(|| format!("failed to send email with {}", smtp.host))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::ParsedFeedItem,
+        db::test_util::{self, add_item, test_db},
+    };
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    /// Add a feed with items of the given titles, published at the given times, returning the
+    /// item IDs.
+    fn add_feed(db: &Db, url: &str, title: &str, items: &[(&str, i64)]) -> Vec<usize> {
+        let feed_id = test_util::add_feed(db, url, title);
+        items
+            .iter()
+            .map(|(title, published)| {
+                let item = ParsedFeedItem {
+                    title: Some(title.to_string()),
+                    link: Some(format!("{}/{}", url, published)),
+                    description: Some(format!("<p>About <b>{}</b></p>", title)),
+                    authors: vec!["Amy".to_string()],
+                    published: Some(*published),
+                    ..Default::default()
+                };
+                add_item(db, feed_id, &item)
+            })
+            .collect()
+    }
+
+    fn test_config() -> DigestConfig {
+        DigestConfig {
+            to: vec!["manager@example.com".to_string()],
+            ..DigestConfig::default()
+        }
+    }
+
+    /// Accept one SMTP session, returning the message data.
+    async fn smtp_sink(listener: TcpListener) -> String {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+        let mut data = String::new();
+        let mut in_data = false;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if in_data {
+                if line == "." {
+                    in_data = false;
+                    writer.write_all(b"250 OK\r\n").await.unwrap();
+                } else {
+                    data.push_str(&line);
+                    data.push('\n');
+                }
+                continue;
+            }
+            let command = line.split_whitespace().next().unwrap_or_default();
+            let reply: &[u8] = match command.to_ascii_uppercase().as_str() {
+                "EHLO" | "HELO" => b"250 localhost\r\n",
+                "DATA" => {
+                    in_data = true;
+                    b"354 End data with <CR><LF>.<CR><LF>\r\n"
+                }
+                "QUIT" => {
+                    writer.write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                }
+                _ => b"250 OK\r\n",
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("24h").unwrap(), 24 * 60 * 60);
+        assert_eq!(parse_duration("90m").unwrap(), 90 * 60);
+        assert_eq!(parse_duration("2w").unwrap(), 14 * 24 * 60 * 60);
+        assert!(parse_duration("24").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
+
+    #[test]
+    fn test_render_digest() {
+        let db = test_db();
+        let status = add_feed(
+            &db,
+            "https://status.example.com/feed",
+            "Status",
+            &[("Outage <API>", 2000), ("Old", 500)],
+        );
+        let blog = add_feed(
+            &db,
+            "https://blog.example.com/feed",
+            "Blog",
+            &[("Release", 1500), ("Read", 1600)],
+        );
+        db.mark_item_read(blog[1]).unwrap();
+        db.add_item_tag(status[0], "incident").unwrap();
+
+        let digest = Digest::collect(&db, 1000, DigestGroup::Feed).unwrap();
+        assert_eq!(digest.len(), 2);
+        assert_eq!(
+            digest
+                .groups
+                .iter()
+                .map(|g| g.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Blog", "Status"]
+        );
+        let mut item_ids = digest.item_ids().to_vec();
+        item_ids.sort();
+        assert_eq!(item_ids, vec![status[0], blog[0]]);
+
+        let digest = Digest::collect(&db, 1000, DigestGroup::Tag).unwrap();
+        let rendered = RenderedDigest::new(&test_config(), &digest).unwrap();
+        assert_eq!(rendered.subject, "ferrofeed digest: 2 unread items");
+        assert!(rendered.html.contains("0.2em;\">incident</h2>"));
+        assert!(rendered.html.contains("Outage &lt;API&gt;"));
+        assert!(rendered.html.contains("<div>About Outage</div>"));
+        let untagged = rendered.text.find("## Untagged").unwrap();
+        assert!(rendered.text.find("## incident").unwrap() < untagged);
+        assert!(rendered.text.contains("* Outage <API>\n  Status - Amy - "));
+        assert!(
+            rendered
+                .text
+                .contains(" - 1970-01-01 00:33\n  About Outage\n")
+        );
+        assert!(
+            rendered
+                .text
+                .contains("\n  https://blog.example.com/feed/1500\n")
+        );
+
+        let config = DigestConfig {
+            subject: "{{ count }} new{% for g in groups %} {{ g.name }}".to_string(),
+            ..test_config()
+        };
+        assert!(RenderedDigest::new(&config, &digest).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deliver_digest() {
+        let db = test_db();
+        add_feed(
+            &db,
+            "https://status.example.com/feed",
+            "Status",
+            &[("From the team", 2000)],
+        );
+        let digest = Digest::collect(&db, 1000, DigestGroup::Feed).unwrap();
+        let dir = std::env::temp_dir().join(format!("ferrofeed-digest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let text_template = dir.join("digest.txt");
+        fs::write(
+            &text_template,
+            "{% for g in groups %}{% for i in g.items %}{{ i.title }}\n{% endfor %}{% endfor %}",
+        )
+        .unwrap();
+        let config = DigestConfig {
+            text_template: Some(text_template),
+            ..test_config()
+        };
+        let message = RenderedDigest::new(&config, &digest)
+            .unwrap()
+            .message(&config)
+            .unwrap();
+
+        let mbox = dir.join("digests.mbox");
+        append_mbox(&mbox, &message).unwrap();
+        append_mbox(&mbox, &message).unwrap();
+        let mbox = fs::read_to_string(&mbox).unwrap();
+        assert_eq!(mbox.lines().filter(|l| l.starts_with("From ")).count(), 2);
+        assert!(mbox.contains("\n>From the team\n"));
+        assert!(mbox.contains("Content-Type: multipart/alternative"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = tokio::spawn(smtp_sink(listener));
+        let smtp = SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+        };
+        send(&smtp, message).await.unwrap();
+        let data = sink.await.unwrap();
+        assert!(data.contains("To: manager@example.com"));
+        assert!(data.contains("Subject: ferrofeed digest: 1 unread item\n"));
+        assert!(data.contains("Content-Type: text/html"));
+    }
+}
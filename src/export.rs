@@ -0,0 +1,595 @@
+/*!
+Export of selected items for reading elsewhere: Markdown files with front matter, a static HTML
+site, or an EPUB book.
+
+The content of items is cleaned into well-formed XHTML, keeping common elements and attributes
+only. For HTML sites and EPUB books, the images in the content are downloaded so they show
+offline; images that fail to download keep their original URL.
+*/
+mod epub;
+mod html;
+mod markdown;
+
+pub use epub::write_epub;
+pub use html::write_html;
+pub use markdown::write_markdown;
+
+use anyhow::Result;
+use md5::{Digest, Md5};
+use scraper::{ElementRef, Html, Node, Selector};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use crate::{
+    client::FeedClient,
+    clock::format_date,
+    db::{Db, FeedItem, ItemPage, ItemSelection},
+    ui::images::resolve_image_url,
+};
+
+/// Elements kept in cleaned content. Other elements are replaced by their children.
+const KEPT_ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "dd",
+    "del",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+
+/// Elements dropped from cleaned content together with their children.
+const DROPPED_ELEMENTS: &[&str] = &[
+    "audio", "button", "embed", "form", "iframe", "input", "math", "noscript", "object", "script",
+    "select", "style", "svg", "template", "textarea", "video",
+];
+
+/// Elements without content, written as self-closing tags.
+const VOID_ELEMENTS: &[&str] = &["br", "hr", "img"];
+
+/// Style sheet of HTML sites and EPUB books.
+const STYLE: &str = "\
+body { font-family: serif; line-height: 1.5; max-width: 40em; margin: 0 auto; padding: 1em; }
+h1 { font-size: 1.5em; }
+h2 { font-size: 1.2em; border-bottom: 1px solid #ccc; }
+img { max-width: 100%; height: auto; }
+pre { white-space: pre-wrap; }
+blockquote { margin-left: 1em; padding-left: 1em; border-left: 3px solid #ccc; }
+.meta { color: #666; font-size: 0.9em; }
+";
+
+/// Format of exported items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// One Markdown file per item, with YAML front matter.
+    #[default]
+    Markdown,
+    /// A static HTML site with an index page and a page per item.
+    Html,
+    /// An EPUB book with a chapter per item and a table of contents.
+    Epub,
+}
+
+/// How to export items.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// The format to export to.
+    pub format: ExportFormat,
+    /// The directory to write Markdown files or HTML sites to, or the EPUB file to write.
+    pub output: PathBuf,
+    /// Title of the HTML site or EPUB book.
+    pub title: String,
+    /// Whether to download the images of HTML sites and EPUB books.
+    pub images: bool,
+}
+
+/// An item to export, with the feed it belongs to.
+#[derive(Debug, Clone)]
+pub struct ExportItem {
+    /// The item.
+    pub item: FeedItem,
+    /// Title of the feed, or its URL if it has no title.
+    pub feed_title: String,
+    /// URL of the feed.
+    pub feed_url: String,
+    /// File name of the item without extension, unique among the exported items.
+    pub file_stem: String,
+}
+
+impl ExportItem {
+    /// Title of the item.
+    fn title(&self) -> &str {
+        self.item.title.as_deref().unwrap_or("(untitled)")
+    }
+
+    /// When the item was published, or else added (Unix timestamp).
+    fn date(&self) -> i64 {
+        self.item.published.unwrap_or(self.item.created_at)
+    }
+
+    /// The feed, authors and date of the item, as one line of text.
+    fn byline(&self) -> String {
+        let mut parts = vec![self.feed_title.clone()];
+        if !self.item.authors.is_empty() {
+            parts.push(self.item.authors.join(", "));
+        }
+        parts.push(format_date(self.date()));
+        parts.join(" · ")
+    }
+}
+
+/// Collect the selected items for export, oldest first.
+pub fn collect_items(db: &Db, selection: &ItemSelection) -> Result<Vec<ExportItem>> {
+    let ids = db.select_item_ids(selection)?;
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut items = db.get_items_page(selection, ItemPage::Ids(&ids), ids.len())?;
+    items.sort_by_key(|item| (item.published.unwrap_or(item.created_at), item.id));
+
+    let feeds = db.list_feeds()?;
+    let mut stems = HashSet::new();
+    Ok(items
+        .into_iter()
+        .map(|item| {
+            let feed = feeds.iter().find(|f| f.id == item.feed_id);
+            let feed_url = feed.map(|f| f.url.clone()).unwrap_or_default();
+            let feed_title = feed
+                .and_then(|f| f.title.clone())
+                .unwrap_or_else(|| feed_url.clone());
+            let mut file_stem = format!(
+                "{}-{}",
+                format_date(item.published.unwrap_or(item.created_at)),
+                slugify(item.title.as_deref().unwrap_or("untitled"))
+            );
+            if !stems.insert(file_stem.clone()) {
+                file_stem = format!("{}-{}", file_stem, item.id);
+                stems.insert(file_stem.clone());
+            }
+            ExportItem {
+                item,
+                feed_title,
+                feed_url,
+                file_stem,
+            }
+        })
+        .collect())
+}
+
+/// Group items by feed, feeds sorted by title.
+fn by_feed(items: &[ExportItem]) -> Vec<(&str, Vec<&ExportItem>)> {
+    let mut groups: Vec<(&str, Vec<&ExportItem>)> = Vec::new();
+    for item in items {
+        match groups.iter_mut().find(|(feed, _)| *feed == item.feed_title) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((&item.feed_title, vec![item])),
+        }
+    }
+    groups.sort_by_key(|(feed, _)| feed.to_lowercase());
+    groups
+}
+
+/// An image downloaded for export.
+#[derive(Debug, Clone)]
+struct Image {
+    /// File name of the image, from the hash of its URL.
+    file_name: String,
+    /// Media type of the image.
+    media_type: &'static str,
+    /// Content of the image.
+    data: Vec<u8>,
+}
+
+/// The images in the content of exported items, by URL.
+#[derive(Debug, Default)]
+pub struct Images {
+    images: HashMap<String, Image>,
+    failed: usize,
+}
+
+impl Images {
+    /// Download the images in the content of the items. Images that fail to download, or are
+    /// not PNG, JPEG, GIF or WebP images, are skipped.
+    pub async fn fetch(client: &FeedClient, items: &[ExportItem]) -> Self {
+        let selector = Selector::parse("img[src]").expect("valid selector");
+        let mut images = Self::default();
+        let mut seen = HashSet::new();
+        for item in items {
+            let Some(description) = &item.item.description else {
+                continue;
+            };
+            let urls: Vec<String> = Html::parse_fragment(description)
+                .select(&selector)
+                .filter_map(|img| img.value().attr("src"))
+                .filter_map(|src| resolve_image_url(src, item.item.link.as_deref()))
+                .filter(|url| url.starts_with("http"))
+                .collect();
+            for url in urls {
+                if !seen.insert(url.clone()) {
+                    continue;
+                }
+                match Self::download(client, &url).await {
+                    Some(image) => {
+                        images.images.insert(url, image);
+                    }
+                    None => images.failed += 1,
+                }
+            }
+        }
+        images
+    }
+
+    /// Download an image.
+    async fn download(client: &FeedClient, url: &str) -> Option<Image> {
+        let data = client.get_bytes(url).await.ok()?;
+        let (media_type, extension) = match image::guess_format(&data).ok()? {
+            image::ImageFormat::Png => ("image/png", "png"),
+            image::ImageFormat::Jpeg => ("image/jpeg", "jpg"),
+            image::ImageFormat::Gif => ("image/gif", "gif"),
+            image::ImageFormat::WebP => ("image/webp", "webp"),
+            _ => return None,
+        };
+        let hash: String = Md5::digest(url)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Some(Image {
+            file_name: format!("{}.{}", hash, extension),
+            media_type,
+            data,
+        })
+    }
+
+    /// Number of downloaded images.
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Whether no images were downloaded.
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Number of images that failed to download.
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// The downloaded images, ordered by file name.
+    fn sorted(&self) -> Vec<&Image> {
+        let mut images: Vec<&Image> = self.images.values().collect();
+        images.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        images
+    }
+}
+
+/// Cleans HTML content into well-formed XHTML.
+struct Cleaner<'a> {
+    /// URL relative links and images are resolved against.
+    base: Option<&'a str>,
+    /// Downloaded images, shown instead of their URL.
+    images: &'a Images,
+    /// Path of the directory of downloaded images, relative to the page.
+    image_dir: &'a str,
+}
+
+impl Cleaner<'_> {
+    /// Clean HTML content.
+    fn clean(&self, html: &str) -> String {
+        let mut out = String::new();
+        self.write_children(Html::parse_fragment(html).root_element(), &mut out);
+        out
+    }
+
+    fn write_children(&self, element: ElementRef, out: &mut String) {
+        for child in element.children() {
+            if let Some(child) = ElementRef::wrap(child) {
+                self.write_element(child, out);
+            } else if let Node::Text(text) = child.value() {
+                out.push_str(&escape(text));
+            }
+        }
+    }
+
+    fn write_element(&self, element: ElementRef, out: &mut String) {
+        let name = element.value().name();
+        if DROPPED_ELEMENTS.contains(&name) {
+            return;
+        }
+        if !KEPT_ELEMENTS.contains(&name) {
+            self.write_children(element, out);
+            return;
+        }
+
+        let mut attrs: Vec<(&str, String)> = Vec::new();
+        for (attr, value) in element.value().attrs() {
+            match (name, attr) {
+                ("a", "href") => {
+                    if let Some(url) = resolve_image_url(value, self.base) {
+                        attrs.push((attr, url));
+                    }
+                }
+                ("img", "src") => {
+                    if let Some(url) = resolve_image_url(value, self.base) {
+                        let src = match self.images.images.get(&url) {
+                            Some(image) => format!("{}{}", self.image_dir, image.file_name),
+                            None => url,
+                        };
+                        attrs.push((attr, src));
+                    }
+                }
+                ("img", "alt") | (_, "title") | ("td" | "th", "colspan" | "rowspan") => {
+                    attrs.push((attr, value.to_string()));
+                }
+                _ => {}
+            }
+        }
+        if name == "img" {
+            if !attrs.iter().any(|(attr, _)| *attr == "src") {
+                return;
+            }
+            // XHTML requires images to have alternative text
+            if !attrs.iter().any(|(attr, _)| *attr == "alt") {
+                attrs.push(("alt", String::new()));
+            }
+        }
+
+        out.push('<');
+        out.push_str(name);
+        for (attr, value) in attrs {
+            out.push_str(&format!(" {}=\"{}\"", attr, escape(&value)));
+        }
+        if VOID_ELEMENTS.contains(&name) {
+            out.push_str(" />");
+        } else {
+            out.push('>');
+            self.write_children(element, out);
+            out.push_str(&format!("</{}>", name));
+        }
+    }
+}
+
+/// The XHTML of an item's page: its title, byline and content, and a link to the original.
+fn article(item: &ExportItem, images: &Images, image_dir: &str) -> String {
+    let cleaner = Cleaner {
+        base: item.item.link.as_deref(),
+        images,
+        image_dir,
+    };
+    let mut article = format!(
+        "<article>\n<h1>{}</h1>\n<p class=\"meta\">{}</p>\n",
+        escape(item.title()),
+        escape(&item.byline())
+    );
+    if let Some(description) = &item.item.description {
+        article.push_str(&cleaner.clean(description));
+        article.push('\n');
+    }
+    if let Some(link) = &item.item.link {
+        article.push_str(&format!(
+            "<p class=\"meta\"><a href=\"{}\">Read the original</a></p>\n",
+            escape(link)
+        ));
+    }
+    article.push_str("</article>");
+    article
+}
+
+/// Escape text for XHTML content and attributes, dropping characters XML does not allow.
+fn escape(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+        .collect();
+    html_escape::encode_double_quoted_attribute(&text).into_owned()
+}
+
+/// Turn a title into a file name: lowercase letters and digits separated by dashes.
+fn slugify(title: &str) -> String {
+    let slug = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug: String = slug.chars().take(60).collect();
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::ParsedFeedItem,
+        db::test_util::{self, add_item, test_db},
+    };
+    use std::{fs, io::Read};
+
+    /// Add a feed with items of the given titles and publish times, returning the item IDs.
+    fn add_feed(db: &Db, url: &str, title: &str, items: &[(&str, i64)]) -> Vec<usize> {
+        let feed_id = test_util::add_feed(db, url, title);
+        items
+            .iter()
+            .map(|(title, published)| {
+                let item = ParsedFeedItem {
+                    title: Some(title.to_string()),
+                    link: Some(format!("{}/{}", url, title.replace(' ', "-"))),
+                    description: Some(format!(
+                        "<p>About <a href=\"more\">{}</a>.</p><img src=\"pic.png\">",
+                        title
+                    )),
+                    authors: vec!["Ann".to_string()],
+                    published: Some(*published),
+                    ..Default::default()
+                };
+                add_item(db, feed_id, &item)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_export_items() {
+        let db = test_db();
+        let ids = add_feed(
+            &db,
+            "https://blog.example.com",
+            "Blog",
+            &[
+                ("Second post", 1_714_650_000),
+                ("First post", 1_714_564_800),
+            ],
+        );
+        add_feed(
+            &db,
+            "https://news.example.com",
+            "News",
+            &[("Old news", 1_600_000_000), ("Same title", 1_714_600_000)],
+        );
+        db.set_item_starred(ids[0], true).unwrap();
+
+        // Oldest first, within the date range
+        let selection = ItemSelection {
+            since: Some(1_700_000_000),
+            ..Default::default()
+        };
+        let items = collect_items(&db, &selection).unwrap();
+        let stems: Vec<&str> = items.iter().map(|i| i.file_stem.as_str()).collect();
+        assert_eq!(
+            stems,
+            [
+                "2024-05-01-first-post",
+                "2024-05-01-same-title",
+                "2024-05-02-second-post"
+            ]
+        );
+        let starred = ItemSelection {
+            is_starred: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(collect_items(&db, &starred).unwrap().len(), 1);
+
+        let dir = std::env::temp_dir().join(format!("ferrofeed-export-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        write_markdown(&items, &dir.join("md")).unwrap();
+        let markdown = fs::read_to_string(dir.join("md/2024-05-01-first-post.md")).unwrap();
+        assert!(markdown.starts_with(
+            "---\ntitle: \"First post\"\nfeed: \"Blog\"\nfeed_url: \"https://blog.example.com\"\n"
+        ));
+        assert!(markdown.contains("published: 2024-05-01T12:00:00Z\n"));
+        assert!(markdown.contains("[1]: https://blog.example.com/more"));
+
+        write_html(&items, &Images::default(), &dir.join("site"), "Reading").unwrap();
+        let index = fs::read_to_string(dir.join("site/index.html")).unwrap();
+        assert!(index.contains("<a href=\"2024-05-01-first-post.html\">First post</a>"));
+        assert!(index.find("<h2>Blog</h2>") < index.find("<h2>News</h2>"));
+        assert!(dir.join("site/2024-05-02-second-post.html").exists());
+
+        let path = dir.join("reading.epub");
+        write_epub(&items, &Images::default(), &path, "Reading").unwrap();
+        let mut book = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        let names: Vec<&str> = book.file_names().collect();
+        assert!(names.contains(&"META-INF/container.xml"));
+        assert!(names.contains(&"OEBPS/items/2024-05-01-same-title.xhtml"));
+        let mimetype = book.by_index(0).unwrap();
+        assert_eq!(mimetype.name(), "mimetype");
+        assert_eq!(mimetype.compression(), zip::CompressionMethod::Stored);
+        drop(mimetype);
+        let mut nav = String::new();
+        book.by_name("OEBPS/nav.xhtml")
+            .unwrap()
+            .read_to_string(&mut nav)
+            .unwrap();
+        assert!(nav.contains("<a href=\"items/2024-05-01-first-post.xhtml\">First post</a>"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(
+            slugify("  Rust 2024: what's new?  "),
+            "rust-2024-what-s-new"
+        );
+        assert_eq!(slugify("???"), "untitled");
+        assert_eq!(slugify(&"a".repeat(80)).len(), 60);
+    }
+
+    #[test]
+    fn test_clean_html() {
+        let mut images = Images::default();
+        images.images.insert(
+            "https://example.com/img/cat.png".to_string(),
+            Image {
+                file_name: "cat.png".to_string(),
+                media_type: "image/png",
+                data: vec![],
+            },
+        );
+        let cleaner = Cleaner {
+            base: Some("https://example.com/posts/1"),
+            images: &images,
+            image_dir: "../images/",
+        };
+        assert_eq!(
+            cleaner.clean(
+                "<p class=\"x\" onclick=\"evil()\">Fish &amp; chips<br>\
+                 <a href=\"/about\">about</a></p>\
+                 <script>alert(1)</script>\
+                 <custom-tag><img src=\"/img/cat.png\"><img src=\"dog.jpg\" alt=\"Dog\"></custom-tag>"
+            ),
+            "<p>Fish &amp; chips<br /><a href=\"https://example.com/about\">about</a></p>\
+             <img src=\"../images/cat.png\" alt=\"\" />\
+             <img alt=\"Dog\" src=\"https://example.com/posts/dog.jpg\" />"
+        );
+    }
+}
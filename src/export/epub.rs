@@ -0,0 +1,154 @@
+//! EPUB 3 book export: a chapter per item, a table of contents grouped by feed, and the
+//! downloaded images.
+
+use anyhow::{Context, Result};
+use md5::{Digest, Md5};
+use std::{fs::File, io::Write, path::Path};
+use time::OffsetDateTime;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use super::{ExportItem, Images, STYLE, article, by_feed, escape};
+use crate::clock::format_rfc3339;
+
+/// Points readers to the package document.
+const CONTAINER: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">
+<rootfiles>
+<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\" />
+</rootfiles>
+</container>
+";
+
+/// Write the items as an EPUB book to `path`.
+pub fn write_epub(items: &[ExportItem], images: &Images, path: &Path, title: &str) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // Readers identify books by this first, uncompressed entry
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(CONTAINER.as_bytes())?;
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(package(items, images, title).as_bytes())?;
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(nav(items, title).as_bytes())?;
+    zip.start_file("OEBPS/style.css", deflated)?;
+    zip.write_all(STYLE.as_bytes())?;
+    for item in items {
+        zip.start_file(format!("OEBPS/items/{}.xhtml", item.file_stem), deflated)?;
+        zip.write_all(
+            page(
+                item.title(),
+                "../style.css",
+                &article(item, images, "../images/"),
+            )
+            .as_bytes(),
+        )?;
+    }
+    // Images are compressed already
+    for image in images.sorted() {
+        zip.start_file(format!("OEBPS/images/{}", image.file_name), stored)?;
+        zip.write_all(&image.data)?;
+    }
+    zip.finish()
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// The package document, describing the book and listing its files in reading order.
+fn package(items: &[ExportItem], images: &Images, title: &str) -> String {
+    // The same items make the same book
+    let identifier: String = Md5::digest(
+        items
+            .iter()
+            .map(|item| item.item.id.to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+    )
+    .iter()
+    .map(|b| format!("{:02x}", b))
+    .collect();
+
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\" />\n\
+         <item id=\"style\" href=\"style.css\" media-type=\"text/css\" />\n",
+    );
+    let mut spine = String::from("<itemref idref=\"nav\" />\n");
+    for (i, item) in items.iter().enumerate() {
+        manifest.push_str(&format!(
+            "<item id=\"item-{}\" href=\"items/{}.xhtml\" media-type=\"application/xhtml+xml\" />\n",
+            i + 1,
+            item.file_stem
+        ));
+        spine.push_str(&format!("<itemref idref=\"item-{}\" />\n", i + 1));
+    }
+    for (i, image) in images.sorted().into_iter().enumerate() {
+        manifest.push_str(&format!(
+            "<item id=\"image-{}\" href=\"images/{}\" media-type=\"{}\" />\n",
+            i + 1,
+            image.file_name,
+            image.media_type
+        ));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+         <dc:identifier id=\"book-id\">urn:ferrofeed:{}</dc:identifier>\n\
+         <dc:title>{}</dc:title>\n\
+         <dc:language>en</dc:language>\n\
+         <meta property=\"dcterms:modified\">{}</meta>\n\
+         </metadata>\n\
+         <manifest>\n{}</manifest>\n\
+         <spine>\n{}</spine>\n\
+         </package>\n",
+        identifier,
+        escape(title),
+        // Without fractional seconds, as EPUB requires
+        format_rfc3339(OffsetDateTime::now_utc().unix_timestamp()),
+        manifest,
+        spine
+    )
+}
+
+/// The navigation document, with the table of contents of the items grouped by feed.
+fn nav(items: &[ExportItem], title: &str) -> String {
+    let mut toc = String::from("<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+    for (feed, items) in by_feed(items) {
+        toc.push_str(&format!("<li><span>{}</span>\n<ol>\n", escape(feed)));
+        for item in items {
+            toc.push_str(&format!(
+                "<li><a href=\"items/{}.xhtml\">{}</a></li>\n",
+                item.file_stem,
+                escape(item.title())
+            ));
+        }
+        toc.push_str("</ol>\n</li>\n");
+    }
+    toc.push_str("</ol>\n</nav>");
+    page(title, "style.css", &toc)
+}
+
+/// An XHTML content document.
+fn page(title: &str, stylesheet: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\">\n\
+         <head>\n\
+         <meta charset=\"utf-8\" />\n\
+         <title>{}</title>\n\
+         <link rel=\"stylesheet\" type=\"text/css\" href=\"{}\" />\n\
+         </head>\n\
+         <body>\n{}\n</body>\n\
+         </html>\n",
+        escape(title),
+        stylesheet,
+        body
+    )
+}
@@ -0,0 +1,77 @@
+//! Static HTML site export: an index page linking to a page per item, with the downloaded
+//! images in an `images` directory.
+
+use anyhow::{Context, Result};
+use std::{fs, path::Path};
+
+use super::{ExportItem, Images, STYLE, article, by_feed, escape};
+use crate::clock::format_date;
+
+/// Write the items as a static HTML site in `dir`, creating it if needed.
+pub fn write_html(items: &[ExportItem], images: &Images, dir: &Path, title: &str) -> Result<()> {
+    let write = |name: &str, content: &[u8]| {
+        let path = dir.join(name);
+        fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))
+    };
+    fs::create_dir_all(dir.join("images"))
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+
+    write("style.css", STYLE.as_bytes())?;
+    for image in images.sorted() {
+        write(&format!("images/{}", image.file_name), &image.data)?;
+    }
+    write("index.html", index(items, title).as_bytes())?;
+    for item in items {
+        let body = format!(
+            "<p class=\"meta\"><a href=\"index.html\">{}</a></p>\n{}",
+            escape(title),
+            article(item, images, "images/")
+        );
+        write(
+            &format!("{}.html", item.file_stem),
+            page(item.title(), &body).as_bytes(),
+        )?;
+    }
+    Ok(())
+}
+
+/// The index page, listing the items by feed.
+fn index(items: &[ExportItem], title: &str) -> String {
+    let mut body = format!(
+        "<h1>{}</h1>\n<p class=\"meta\">{} item{}</p>\n",
+        escape(title),
+        items.len(),
+        if items.len() == 1 { "" } else { "s" }
+    );
+    for (feed, items) in by_feed(items) {
+        body.push_str(&format!("<h2>{}</h2>\n<ul>\n", escape(feed)));
+        for item in items {
+            body.push_str(&format!(
+                "<li><a href=\"{}.html\">{}</a> <span class=\"meta\">{}</span></li>\n",
+                item.file_stem,
+                escape(item.title()),
+                format_date(item.date())
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+    page(title, &body)
+}
+
+/// An HTML page.
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\" />\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\n\
+         <title>{}</title>\n\
+         <link rel=\"stylesheet\" href=\"style.css\" />\n\
+         </head>\n\
+         <body>\n{}\n</body>\n\
+         </html>\n",
+        escape(title),
+        body
+    )
+}
@@ -0,0 +1,76 @@
+//! Markdown export: one file per item, with YAML front matter.
+
+use anyhow::{Context, Result};
+use std::{fs, path::Path};
+
+use super::{Cleaner, ExportItem, Images};
+use crate::clock::format_rfc3339;
+
+/// Width of the Markdown text, in columns.
+const WIDTH: usize = 80;
+
+/// Write each item to a Markdown file in `dir`, creating it if needed.
+pub fn write_markdown(items: &[ExportItem], dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    for item in items {
+        let path = dir.join(format!("{}.md", item.file_stem));
+        fs::write(&path, document(item)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// The Markdown document of an item.
+fn document(item: &ExportItem) -> Result<String> {
+    // JSON strings and arrays are valid YAML, and quote whatever the values contain
+    let mut doc = String::from("---\n");
+    doc.push_str(&format!(
+        "title: {}\n",
+        serde_json::to_string(item.title())?
+    ));
+    doc.push_str(&format!(
+        "feed: {}\n",
+        serde_json::to_string(&item.feed_title)?
+    ));
+    doc.push_str(&format!(
+        "feed_url: {}\n",
+        serde_json::to_string(&item.feed_url)?
+    ));
+    if let Some(link) = &item.item.link {
+        doc.push_str(&format!("link: {}\n", serde_json::to_string(link)?));
+    }
+    if !item.item.authors.is_empty() {
+        doc.push_str(&format!(
+            "authors: {}\n",
+            serde_json::to_string(&item.item.authors)?
+        ));
+    }
+    if let Some(published) = item.item.published {
+        doc.push_str(&format!("published: {}\n", format_rfc3339(published)));
+    }
+    if !item.item.tags.is_empty() {
+        doc.push_str(&format!(
+            "tags: {}\n",
+            serde_json::to_string(&item.item.tags)?
+        ));
+    }
+    doc.push_str(&format!("starred: {}\n", item.item.is_starred));
+    doc.push_str(&format!("read: {}\n", item.item.is_read));
+    doc.push_str("---\n\n");
+
+    doc.push_str(&format!("# {}\n", item.title()));
+    if let Some(description) = &item.item.description {
+        // Clean first, so relative links point to the site of the item
+        let cleaner = Cleaner {
+            base: item.item.link.as_deref(),
+            images: &Images::default(),
+            image_dir: "",
+        };
+        let text = html2text::from_read(cleaner.clean(description).as_bytes(), WIDTH)
+            .context("failed to convert the item content to Markdown")?;
+        doc.push('\n');
+        doc.push_str(text.trim_end());
+        doc.push('\n');
+    }
+    Ok(doc)
+}
@@ -0,0 +1,646 @@
+/*!
+Hooks notifying other programs of new items, loaded from a TOML hooks file.
+
+A hook either POSTs a JSON payload to a webhook URL or runs a shell command. Commands get the
+payload on stdin and the fields of the item in `FERROFEED_*` environment variables. Hooks fire
+once per new item, or once per sync for all the new items with `batch = true`. Like rules, hooks
+can be limited to matching items, with regular expressions for text conditions. They fire after
+the rules, so `tag` also matches the tags added by rules.
+
+Deliveries that take longer than the hook's timeout fail, and commands are killed. Failed
+deliveries are retried with exponential backoff. Deliveries that fail after every retry are
+appended to the dead-letter log, with their payload.
+
+```toml
+[[hook]]
+name = "Incidents to chat"
+feed = "status\\.example\\.com"
+title = "(?i)(outage|degraded)"
+webhook = "https://chat.example.com/hooks/incidents"
+headers = { Authorization = { env = "CHAT_TOKEN" } }
+retries = 5
+
+[[hook]]
+tag = "ferrofeed"
+command = "notify-send \"$FERROFEED_FEED_TITLE\" \"$FERROFEED_ITEM_TITLE\""
+
+[[hook]]
+command = "jq -r '.items[].link' >> ~/new-links.txt"
+batch = true
+```
+*/
+use anyhow::{Context, Result};
+use regex::Regex;
+use reqwest::{
+    Method,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    auth::Secret,
+    client::FeedClient,
+    config::HooksConfig,
+    db::{Db, Feed, FeedItem},
+};
+
+/// Default number of retries of a failed delivery.
+const DEFAULT_RETRIES: u32 = 3;
+
+/// Default delay before the first retry, doubled for each retry after it.
+const DEFAULT_RETRY_DELAY_SECS: u64 = 2;
+
+/// Default time a delivery may take before it fails.
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// A hook as written in the hooks file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HookDef {
+    name: Option<String>,
+    feed: Option<String>,
+    tag: Option<String>,
+    title: Option<String>,
+    content: Option<String>,
+    webhook: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, Secret>,
+    command: Option<String>,
+    #[serde(default)]
+    batch: bool,
+    retries: Option<u32>,
+    retry_delay_secs: Option<u64>,
+    timeout_secs: Option<u64>,
+}
+
+/// The hooks file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HooksFile {
+    #[serde(default)]
+    hook: Vec<HookDef>,
+}
+
+/// Where a hook delivers its payload.
+#[derive(Debug)]
+pub enum Target {
+    /// POST the payload to a URL, with extra headers.
+    Webhook {
+        url: String,
+        headers: BTreeMap<String, Secret>,
+    },
+    /// Run a shell command with the payload on stdin.
+    Command(String),
+}
+
+/// A hook with its conditions compiled.
+#[derive(Debug)]
+pub struct Hook {
+    /// Name of the hook, for messages and the dead-letter log.
+    pub name: String,
+    /// Matches the feed title or URL.
+    feed: Option<Regex>,
+    /// Matches any tag or category of the item, ignoring case.
+    tag: Option<String>,
+    /// Matches the item title.
+    title: Option<Regex>,
+    /// Matches the item title or description.
+    content: Option<Regex>,
+    /// Where to deliver the payload.
+    pub target: Target,
+    /// Whether to fire once for all the new items of a sync, instead of once per item.
+    pub batch: bool,
+    /// Number of retries of a failed delivery.
+    retries: u32,
+    /// Delay before the first retry.
+    retry_delay: Duration,
+    /// Time a delivery may take before it fails.
+    timeout: Duration,
+}
+
+impl Hook {
+    /// Whether the hook fires for an item of a feed.
+    pub fn matches(&self, feed: &Feed, item: &FeedItem) -> bool {
+        let feed_matches = self.feed.as_ref().is_none_or(|re| {
+            re.is_match(&feed.url) || feed.title.as_deref().is_some_and(|t| re.is_match(t))
+        });
+        let tag_matches = self.tag.as_ref().is_none_or(|tag| {
+            item.tags
+                .iter()
+                .chain(&item.categories)
+                .any(|t| t.eq_ignore_ascii_case(tag))
+        });
+        let title_matches = self
+            .title
+            .as_ref()
+            .is_none_or(|re| item.title.as_deref().is_some_and(|t| re.is_match(t)));
+        let content_matches = self.content.as_ref().is_none_or(|re| {
+            [&item.title, &item.description]
+                .into_iter()
+                .any(|text| text.as_deref().is_some_and(|t| re.is_match(t)))
+        });
+        feed_matches && tag_matches && title_matches && content_matches
+    }
+
+    /// Deliver a payload, retrying on failure. Returns the last error.
+    async fn deliver_with_retries(
+        &self,
+        client: &FeedClient,
+        payload: &serde_json::Value,
+        env: &[(String, String)],
+    ) -> Result<()> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            let delivery = tokio::time::timeout(self.timeout, self.deliver(client, payload, env))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(anyhow::anyhow!(
+                        "timed out after {}s",
+                        self.timeout.as_secs_f64()
+                    ))
+                });
+            match delivery {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.retries => return Err(e),
+                Err(_) => {
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Deliver a payload once. Dropping the future kills a running command.
+    async fn deliver(
+        &self,
+        client: &FeedClient,
+        payload: &serde_json::Value,
+        env: &[(String, String)],
+    ) -> Result<()> {
+        match &self.target {
+            Target::Webhook { url, headers } => {
+                let mut header_map = HeaderMap::new();
+                for (name, value) in headers {
+                    let name = HeaderName::try_from(name.as_str())
+                        .with_context(|| format!("invalid header name: {}", name))?;
+                    let mut value =
+                        HeaderValue::from_str(&value.resolve()?).context("invalid header value")?;
+                    value.set_sensitive(true);
+                    header_map.insert(name, value);
+                }
+                client
+                    .request(Method::POST, url)
+                    .headers(header_map)
+                    .json(payload)
+                    .send()
+                    .await
+                    .with_context(|| format!("failed to reach {}", url))?
+                    .error_for_status()?;
+                Ok(())
+            }
+            Target::Command(cmd) => {
+                let mut child = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(cmd)
+                    .envs(env.iter().map(|(k, v)| (k, v)))
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .with_context(|| format!("failed to run `{}`", cmd))?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    // Commands may exit without reading their input
+                    let _ = stdin.write_all(payload.to_string().as_bytes()).await;
+                }
+                let output = child.wait_with_output().await?;
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    anyhow::bail!("`{}` failed with {}: {}", cmd, output.status, stderr.trim());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The JSON payload describing an item.
+#[derive(Debug, Serialize)]
+struct ItemPayload<'a> {
+    feed: FeedPayload<'a>,
+    id: usize,
+    title: Option<&'a str>,
+    link: Option<&'a str>,
+    description: Option<&'a str>,
+    authors: &'a [String],
+    published: Option<i64>,
+    tags: &'a [String],
+    categories: &'a [String],
+}
+
+/// The feed of an item in a payload.
+#[derive(Debug, Serialize)]
+struct FeedPayload<'a> {
+    id: usize,
+    url: &'a str,
+    title: Option<&'a str>,
+}
+
+impl<'a> ItemPayload<'a> {
+    fn new(feed: &'a Feed, item: &'a FeedItem) -> Self {
+        Self {
+            feed: FeedPayload {
+                id: feed.id,
+                url: &feed.url,
+                title: feed.title.as_deref(),
+            },
+            id: item.id,
+            title: item.title.as_deref(),
+            link: item.link.as_deref(),
+            description: item.description.as_deref(),
+            authors: &item.authors,
+            published: item.published,
+            tags: &item.tags,
+            categories: &item.categories,
+        }
+    }
+
+    /// The environment variables given to commands for the item.
+    fn env(&self) -> Vec<(String, String)> {
+        let vars = [
+            ("FERROFEED_FEED_ID", self.feed.id.to_string()),
+            ("FERROFEED_FEED_URL", self.feed.url.to_string()),
+            (
+                "FERROFEED_FEED_TITLE",
+                self.feed.title.unwrap_or_default().to_string(),
+            ),
+            ("FERROFEED_ITEM_ID", self.id.to_string()),
+            (
+                "FERROFEED_ITEM_TITLE",
+                self.title.unwrap_or_default().to_string(),
+            ),
+            (
+                "FERROFEED_ITEM_LINK",
+                self.link.unwrap_or_default().to_string(),
+            ),
+            ("FERROFEED_ITEM_AUTHORS", self.authors.join(", ")),
+            (
+                "FERROFEED_ITEM_PUBLISHED",
+                self.published.map(|p| p.to_string()).unwrap_or_default(),
+            ),
+            ("FERROFEED_ITEM_TAGS", self.tags.join(",")),
+        ];
+        vars.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+    }
+}
+
+/// What firing the hooks did.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HookOutcome {
+    /// Number of payloads delivered.
+    pub delivered: usize,
+    /// Number of payloads that failed after every retry, and went to the dead-letter log.
+    pub failed: usize,
+}
+
+/// The hooks of the hooks file.
+#[derive(Debug, Default)]
+pub struct HookSet {
+    /// The hooks, in the order they appear in the hooks file.
+    pub hooks: Vec<Hook>,
+    /// Where to log failed deliveries, if anywhere.
+    pub dead_letter_path: Option<PathBuf>,
+}
+
+impl HookSet {
+    /// Load the hooks file at `path`. A missing file has no hooks.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("failed to read hooks file at {}", path.display()))?;
+        Self::parse(&data)
+            .with_context(|| format!("failed to parse hooks file at {}", path.display()))
+    }
+
+    /// Load the hooks file set in the config, if any, logging failures where the config says.
+    pub fn from_config(config: &HooksConfig) -> Result<Self> {
+        let hooks = match &config.path {
+            Some(path) => Self::load(path)?,
+            None => Self::default(),
+        };
+        Ok(Self {
+            dead_letter_path: config.dead_letter_path.clone(),
+            ..hooks
+        })
+    }
+
+    /// Parse hooks from TOML.
+    pub fn parse(data: &str) -> Result<Self> {
+        let file: HooksFile = toml::from_str(data)?;
+        let hooks = file
+            .hook
+            .into_iter()
+            .enumerate()
+            .map(|(i, def)| compile_hook(i, def))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            hooks,
+            dead_letter_path: None,
+        })
+    }
+
+    /// Whether there are no hooks.
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Fire the hooks for the new items with the given IDs. Deliveries that fail after every
+    /// retry go to the dead-letter log.
+    pub async fn fire(
+        &self,
+        db: &Db,
+        client: &FeedClient,
+        item_ids: &[usize],
+    ) -> Result<HookOutcome> {
+        let mut outcome = HookOutcome::default();
+        if self.is_empty() || item_ids.is_empty() {
+            return Ok(outcome);
+        }
+
+        let feeds: HashMap<usize, Feed> = db
+            .list_feeds()?
+            .into_iter()
+            .map(|feed| (feed.id, feed))
+            .collect();
+        let mut items = Vec::new();
+        for &item_id in item_ids {
+            // Items may have been deleted by a rule
+            if let Some(item) = db.get_feed_item(item_id)?
+                && let Some(feed) = feeds.get(&item.feed_id)
+            {
+                items.push((feed, item));
+            }
+        }
+
+        for hook in &self.hooks {
+            let payloads: Vec<ItemPayload> = items
+                .iter()
+                .filter(|(feed, item)| hook.matches(feed, item))
+                .map(|(feed, item)| ItemPayload::new(feed, item))
+                .collect();
+            if payloads.is_empty() {
+                continue;
+            }
+
+            let deliveries = if hook.batch {
+                let env = vec![(
+                    "FERROFEED_ITEM_COUNT".to_string(),
+                    payloads.len().to_string(),
+                )];
+                vec![(json!({ "items": payloads }), env)]
+            } else {
+                payloads
+                    .iter()
+                    .map(|payload| (json!(payload), payload.env()))
+                    .collect()
+            };
+            for (payload, env) in deliveries {
+                match hook.deliver_with_retries(client, &payload, &env).await {
+                    Ok(()) => outcome.delivered += 1,
+                    Err(e) => {
+                        outcome.failed += 1;
+                        eprintln!("Warning: hook {} failed: {:#}", hook.name, e);
+                        if let Err(e) = self.log_dead_letter(hook, &e, &payload) {
+                            eprintln!("Warning: failed to log failed hook delivery: {:#}", e);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Append a failed delivery to the dead-letter log.
+    fn log_dead_letter(
+        &self,
+        hook: &Hook,
+        error: &anyhow::Error,
+        payload: &serde_json::Value,
+    ) -> Result<()> {
+        let Some(path) = &self.dead_letter_path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let entry = json!({
+            "time": time,
+            "hook": hook.name,
+            "error": format!("{:#}", error),
+            "payload": payload,
+        });
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        writeln!(file, "{}", entry)?;
+        Ok(())
+    }
+}
+
+/// Compile the hook at `index` in the hooks file.
+fn compile_hook(index: usize, def: HookDef) -> Result<Hook> {
+    let name = def.name.unwrap_or_else(|| format!("hook #{}", index + 1));
+    let compile = |pattern: Option<String>, field: &str| -> Result<Option<Regex>> {
+        pattern
+            .map(|p| {
+                Regex::new(&p).with_context(|| format!("invalid `{}` regex in {}", field, name))
+            })
+            .transpose()
+    };
+    let target = match (def.webhook, def.command) {
+        (Some(url), None) => Target::Webhook {
+            url,
+            headers: def.headers,
+        },
+        (None, Some(cmd)) if def.headers.is_empty() => Target::Command(cmd),
+        (None, Some(_)) => anyhow::bail!("{} has headers but no webhook", name),
+        _ => anyhow::bail!("{} needs either a webhook or a command", name),
+    };
+    Ok(Hook {
+        feed: compile(def.feed, "feed")?,
+        tag: def.tag,
+        title: compile(def.title, "title")?,
+        content: compile(def.content, "content")?,
+        target,
+        batch: def.batch,
+        retries: def.retries.unwrap_or(DEFAULT_RETRIES),
+        retry_delay: Duration::from_secs(def.retry_delay_secs.unwrap_or(DEFAULT_RETRY_DELAY_SECS)),
+        timeout: Duration::from_secs(def.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)),
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::ParsedFeedItem,
+        config::HttpConfig,
+        db::test_util::{self, add_feed, test_db},
+    };
+
+    /// Add an item to a new feed and return its ID.
+    fn add_item(db: &Db, feed_url: &str, title: &str) -> usize {
+        let feed_id = add_feed(db, feed_url, "Status");
+        let item = ParsedFeedItem {
+            title: Some(title.to_string()),
+            link: Some(format!("{}/{}", feed_url, title.len())),
+            categories: vec!["Incident".to_string()],
+            ..Default::default()
+        };
+        test_util::add_item(db, feed_id, &item)
+    }
+
+    #[test]
+    fn test_parse_hooks() {
+        let hooks = HookSet::parse(
+            r#"
+            [[hook]]
+            feed = "status"
+            tag = "incident"
+            webhook = "https://chat.example.com/hook"
+            headers = { Authorization = "Bearer token" }
+
+            [[hook]]
+            command = "cat"
+            batch = true
+            "#,
+        )
+        .expect("failed to parse hooks");
+        assert_eq!(hooks.hooks.len(), 2);
+        assert!(matches!(hooks.hooks[0].target, Target::Webhook { .. }));
+        assert!(hooks.hooks[1].batch);
+
+        assert!(HookSet::parse("[[hook]]\nfeed = \"x\"").is_err());
+        assert!(HookSet::parse("[[hook]]\ncommand = \"x\"\nwebhook = \"y\"").is_err());
+        assert!(HookSet::parse("[[hook]]\ncommand = \"x\"\ntitle = \"(\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fire_hooks() {
+        let db = test_db();
+        let outage = add_item(&db, "https://status.example.com/feed", "Outage");
+        let other = add_item(&db, "https://blog.example.com/feed", "Release notes");
+        let dir = std::env::temp_dir().join(format!("ferrofeed-hooks-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("output");
+        let dead_letters = dir.join("failed.jsonl");
+
+        let mut hooks = HookSet::parse(&format!(
+            r#"
+            [[hook]]
+            name = "Incidents"
+            feed = "status"
+            tag = "incident"
+            command = "echo \"$FERROFEED_ITEM_TITLE\" >> {output}"
+
+            [[hook]]
+            name = "Batch"
+            command = "echo \"$FERROFEED_ITEM_COUNT items\" >> {output}"
+            batch = true
+
+            [[hook]]
+            name = "Unreachable"
+            title = "Outage"
+            webhook = "http://127.0.0.1:1/hook"
+            retries = 1
+            retry_delay_secs = 0
+            "#,
+            output = output.display()
+        ))
+        .expect("failed to parse hooks");
+        hooks.dead_letter_path = Some(dead_letters.clone());
+        let client = FeedClient::new(&HttpConfig::default()).unwrap();
+
+        let outcome = hooks.fire(&db, &client, &[outage, other]).await.unwrap();
+        assert_eq!(
+            outcome,
+            HookOutcome {
+                delivered: 2,
+                failed: 1
+            }
+        );
+        let output = fs::read_to_string(&output).unwrap();
+        assert_eq!(output, "Outage\n2 items\n");
+
+        let dead_letters = fs::read_to_string(&dead_letters).unwrap();
+        let entry: serde_json::Value = serde_json::from_str(dead_letters.trim()).unwrap();
+        assert_eq!(entry["hook"], "Unreachable");
+        assert_eq!(entry["payload"]["title"], "Outage");
+        assert_eq!(
+            entry["payload"]["feed"]["url"],
+            "https://status.example.com/feed"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hook_timeout() {
+        let db = test_db();
+        let item = add_item(&db, "https://blog.example.com/feed", "Post");
+        let dir =
+            std::env::temp_dir().join(format!("ferrofeed-hooks-timeout-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let marker = dir.join("finished");
+
+        let mut hooks = HookSet::parse(&format!(
+            r#"
+            [[hook]]
+            name = "Slow"
+            command = "sleep 3 && touch {marker}"
+            timeout_secs = 1
+            retries = 0
+            "#,
+            marker = marker.display()
+        ))
+        .expect("failed to parse hooks");
+        hooks.dead_letter_path = Some(dir.join("failed.jsonl"));
+        let client = FeedClient::new(&HttpConfig::default()).unwrap();
+
+        let started = std::time::Instant::now();
+        let outcome = hooks.fire(&db, &client, &[item]).await.unwrap();
+        assert_eq!(
+            outcome,
+            HookOutcome {
+                delivered: 0,
+                failed: 1
+            }
+        );
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        // The command was killed rather than left to finish
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert!(!marker.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
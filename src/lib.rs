@@ -1,6 +1,19 @@
 #![doc = include_str!("../README.md")]
+pub mod auth;
 pub mod client;
+pub mod clock;
 pub mod commands;
 pub mod config;
 pub mod db;
+pub mod dedup;
+pub mod digest;
+pub mod export;
+pub mod hooks;
+pub mod notifications;
+pub mod opml;
+pub mod output;
+pub mod publish;
+pub mod remote;
+pub mod rules;
+pub mod server;
 pub mod ui;
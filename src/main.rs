@@ -4,10 +4,23 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
-use std::process::Stdio;
+use std::process::{ExitCode, Stdio};
 use tokio::{io::AsyncWriteExt, process::Command as TokioCommand};
 
-use ferrofeed::{commands, config, db, ui};
+use ferrofeed::{
+    client::FeedClient,
+    clock,
+    commands::{self, ExportSelection},
+    config, db,
+    digest::DigestGroup,
+    export::{ExportFormat, ExportOptions},
+    hooks::HookSet,
+    output::{OutputFormat, PARTIAL_FAILURE},
+    publish::PublishFormat,
+    rules::RuleSet,
+    server::Api,
+    ui,
+};
 
 /// A RSS CLI and TUI for managing, viewing, and exporting RSS/Atom feeds.
 #[derive(Parser)]
@@ -17,6 +30,10 @@ struct Args {
     #[clap(short = 'c', long)]
     config_path: Option<PathBuf>,
 
+    /// Output format of `list`, `items`, `search` and `sync`.
+    #[clap(long, global = true, value_enum, default_value_t)]
+    format: OutputFormat,
+
     #[clap(subcommand)]
     command: Option<Command>,
 }
@@ -33,12 +50,82 @@ enum Command {
         /// The URL of the RSS/Atom resource.
         url: String,
     },
+    /// Edit the properties of a feed, keeping its items.
+    EditFeed {
+        /// The URL of the RSS/Atom resource.
+        url: String,
+        /// A new URL for the feed, e.g. after it moved.
+        #[clap(long)]
+        new_url: Option<String>,
+        /// A custom display title for the feed. An empty title restores the feed's own title.
+        #[clap(long)]
+        title: Option<String>,
+        /// Pause (true) or resume (false) syncing the feed.
+        #[clap(long)]
+        paused: Option<bool>,
+        /// Show (true) or stop showing (false) desktop notifications for new items of the feed.
+        #[clap(long)]
+        notify: Option<bool>,
+    },
     /// List current feeds in the RSS store.
     List,
+    /// List the items of a feed, or of all feeds, newest first.
+    Items {
+        /// Only list items of the feed with this URL.
+        #[clap(long, value_name = "URL")]
+        feed: Option<String>,
+        /// Only list items matching this filter, e.g. `unread starred author:<text>
+        /// category:<name> since:<YYYY-MM-DD> until:<YYYY-MM-DD>`.
+        #[clap(long)]
+        filter: Option<String>,
+        /// List at most this many items.
+        #[clap(long)]
+        limit: Option<usize>,
+    },
+    /// Show information about a feed and its site.
+    Info {
+        /// The URL of the RSS/Atom resource.
+        url: String,
+    },
     /// Manually trigger sync across RSS feeds.
     Sync,
     /// Export feed(s) as OPML.
     Export { feed: Option<Vec<String>> },
+    /// Export items as Markdown files, a static HTML site or an EPUB book.
+    ExportItems {
+        /// The directory to write Markdown files or an HTML site to, or the EPUB file to write.
+        #[clap(short, long)]
+        output: PathBuf,
+        /// What to export to.
+        #[clap(long, value_enum, default_value_t)]
+        to: ExportFormat,
+        /// Only export items of this feed, given by URL. Can be given several times.
+        #[clap(long = "feed", value_name = "URL")]
+        feeds: Vec<String>,
+        /// Only export items with this tag.
+        #[clap(long)]
+        tag: Option<String>,
+        /// Only export starred items.
+        #[clap(long)]
+        starred: bool,
+        /// Only export items published on or after this date (YYYY-MM-DD).
+        #[clap(long)]
+        since: Option<String>,
+        /// Only export items published on or before this date (YYYY-MM-DD).
+        #[clap(long)]
+        until: Option<String>,
+        /// Title of the HTML site or EPUB book.
+        #[clap(long, default_value = "ferrofeed export")]
+        title: String,
+        /// Link to images on the web instead of downloading them.
+        #[clap(long)]
+        no_images: bool,
+    },
+    /// Import feeds and folders from an OPML file.
+    Import {
+        /// Path to the OPML file.
+        path: PathBuf,
+    },
     /// Add a tag to feed(s).
     Tag {
         /// The name of the tag to add.
@@ -50,7 +137,61 @@ enum Command {
     /// Search RSS store content (titles, authors, page content)
     Search {
         /// Pattern to match
-        query: String,
+        #[clap(required_unless_present = "category")]
+        query: Option<String>,
+        /// Only include items in this category (case-insensitive).
+        #[clap(long)]
+        category: Option<String>,
+    },
+    /// Serve a sync API (Fever or Google Reader) for other feed readers.
+    Serve {
+        /// The API to serve, overriding the config.
+        #[clap(long, value_enum)]
+        api: Option<Api>,
+        /// Address to listen on, overriding the config.
+        #[clap(long)]
+        address: Option<String>,
+    },
+    /// Publish the items matching the filters of the config as an Atom or RSS feed.
+    Publish {
+        /// What to publish as.
+        #[clap(long, value_enum, default_value_t)]
+        to: PublishFormat,
+        /// Only publish items with this tag, overriding the config.
+        #[clap(long)]
+        tag: Option<String>,
+        /// Only publish starred items.
+        #[clap(long)]
+        starred: bool,
+        /// Only publish items matching the conditions of the rule with this name, overriding
+        /// the config.
+        #[clap(long)]
+        rule: Option<String>,
+        /// Maximum number of entries, overriding the config.
+        #[clap(long)]
+        limit: Option<usize>,
+        /// Write the feed to a file instead of printing it.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Email a digest of the unread items published recently.
+    Digest {
+        /// How far back to include items, e.g. `24h`, `7d` or `90m`.
+        #[clap(long, default_value = "24h")]
+        since: String,
+        /// Group items by feed or by tag, overriding the config.
+        #[clap(long, value_enum)]
+        group_by: Option<DigestGroup>,
+        /// Write the email to a file instead of printing it. Files ending in `.mbox` are
+        /// appended to as mbox files, others are written as `.eml` files.
+        #[clap(long, conflicts_with = "send")]
+        output: Option<PathBuf>,
+        /// Send the email with the SMTP server of the config.
+        #[clap(long)]
+        send: bool,
+        /// Mark the items of the digest as read.
+        #[clap(long)]
+        mark_read: bool,
     },
     /// Display the current configuration file.
     Config,
@@ -61,11 +202,28 @@ enum Command {
         #[clap(short = 'm', long, default_value_t = 60, value_name = "MINUTES")]
         minutes: u32,
     },
+    /// Manage the rules that act on items automatically.
+    Rules {
+        #[clap(subcommand)]
+        command: RulesCommand,
+    },
 }
 
-/// Main entry point.
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(Subcommand)]
+enum RulesCommand {
+    /// Apply the rules to all existing items.
+    Apply,
+}
+
+/// Main entry point. Exits with 1 on errors, and with 2 when a sync partly failed.
+fn main() -> Result<ExitCode> {
+    // Only readable before the runtime starts its threads
+    clock::init_local_offset();
+    tokio::runtime::Runtime::new()?.block_on(run())
+}
+
+/// Run the command given on the command line.
+async fn run() -> Result<ExitCode> {
     let args = Args::parse();
 
     // Parse user config, if it exists
@@ -82,11 +240,110 @@ async fn main() -> Result<()> {
     db.init_feed_table()?;
     db.init_feed_item_table()?;
 
-    match args.command {
-        Some(Command::AddFeed { url }) => commands::add_feed(&db, &url).await,
-        Some(Command::RemoveFeed { url }) => commands::remove_feed(&db, &url).await,
-        Some(Command::List) => commands::list_feeds(&db),
-        Some(Command::Sync) => commands::sync_feeds(&db).await,
+    let result = match args.command {
+        Some(Command::AddFeed { url }) => {
+            let rules = RuleSet::from_config(&cfg.rules)?;
+            let hooks = HookSet::from_config(&cfg.hooks)?;
+            commands::add_feed(
+                &db,
+                &url,
+                &FeedClient::from_config(&cfg)?,
+                &cfg.sync,
+                &rules,
+                &hooks,
+            )
+            .await
+        }
+        Some(Command::RemoveFeed { url }) => {
+            let rules = RuleSet::from_config(&cfg.rules)?;
+            let hooks = HookSet::from_config(&cfg.hooks)?;
+            commands::remove_feed(
+                &db,
+                &url,
+                &FeedClient::from_config(&cfg)?,
+                &cfg.sync,
+                &rules,
+                &hooks,
+            )
+            .await
+        }
+        Some(Command::EditFeed {
+            url,
+            new_url,
+            title,
+            paused,
+            notify,
+        }) => commands::edit_feed(
+            &db,
+            &url,
+            new_url.as_deref(),
+            title.as_deref(),
+            paused,
+            notify,
+        ),
+        Some(Command::List) => commands::list_feeds(&db, args.format),
+        Some(Command::Items {
+            feed,
+            filter,
+            limit,
+        }) => commands::list_items(&db, feed.as_deref(), filter.as_deref(), limit, args.format),
+        Some(Command::Info { url }) => commands::feed_info(&db, &url),
+        Some(Command::Sync) => {
+            let rules = RuleSet::from_config(&cfg.rules)?;
+            let hooks = HookSet::from_config(&cfg.hooks)?;
+            let failed = commands::sync_feeds(
+                &db,
+                &FeedClient::from_config(&cfg)?,
+                &cfg.sync,
+                &rules,
+                &hooks,
+                args.format,
+            )
+            .await?;
+            if failed > 0 {
+                return Ok(ExitCode::from(PARTIAL_FAILURE));
+            }
+            Ok(())
+        }
+        Some(Command::Export { feed }) => {
+            commands::export_opml(&db, feed.as_deref().unwrap_or_default())
+        }
+        Some(Command::ExportItems {
+            output,
+            to,
+            feeds,
+            tag,
+            starred,
+            since,
+            until,
+            title,
+            no_images,
+        }) => {
+            let selection = ExportSelection {
+                feeds: &feeds,
+                tag: tag.as_deref(),
+                starred,
+                since: since.as_deref(),
+                until: until.as_deref(),
+            };
+            let options = ExportOptions {
+                format: to,
+                output,
+                title,
+                images: !no_images,
+            };
+            commands::export_items(&db, &FeedClient::from_config(&cfg)?, &selection, &options).await
+        }
+        Some(Command::Import { path }) => commands::import_opml(&db, &path),
+        Some(Command::Search { query, category }) => {
+            commands::search(&db, query.as_deref(), category.as_deref(), args.format)
+        }
+        Some(Command::Rules {
+            command: RulesCommand::Apply,
+        }) => {
+            let rules = RuleSet::from_config(&cfg.rules)?;
+            commands::apply_rules(&db, &rules)
+        }
         Some(Command::Config) => {
             let conf = match toml::to_string_pretty(&cfg) {
                 Ok(s) => s,
@@ -106,6 +363,43 @@ async fn main() -> Result<()> {
 
             Ok(())
         }
+        Some(Command::Serve { api, address }) => {
+            commands::serve(db, &cfg, api, address.as_deref()).await
+        }
+        Some(Command::Publish {
+            to,
+            tag,
+            starred,
+            rule,
+            limit,
+            output,
+        }) => {
+            let rules = RuleSet::from_config(&cfg.rules)?;
+            let mut config = cfg.publish.clone();
+            config.tag = tag.or(config.tag);
+            config.starred |= starred;
+            config.rule = rule.or(config.rule);
+            config.limit = limit.unwrap_or(config.limit);
+            commands::publish(&db, &config, &rules, to, output.as_deref())
+        }
+        Some(Command::Digest {
+            since,
+            group_by,
+            output,
+            send,
+            mark_read,
+        }) => {
+            commands::digest(
+                &db,
+                &cfg.digest,
+                &since,
+                group_by,
+                output.as_deref(),
+                send,
+                mark_read,
+            )
+            .await
+        }
         Some(Command::Schedule { minutes }) => Ok(commands::schedule(minutes).await?),
         Some(_) => {
             // TODO: Handle remaining subcommands
@@ -113,7 +407,8 @@ async fn main() -> Result<()> {
         }
         None => {
             // Open TUI
-            ui::init(&db)
+            ui::init(&db, &cfg)
         }
-    }
+    };
+    result.map(|()| ExitCode::SUCCESS)
 }
@@ -0,0 +1,328 @@
+/*!
+Desktop notifications for new items, sent over D-Bus following the
+[freedesktop notification spec](https://specifications.freedesktop.org/notification-spec/latest/).
+
+After a sync, each feed that opted in gets one notification summarizing its new items, unless
+the sync ran during the quiet hours set in the config.
+*/
+use anyhow::{Context, Result};
+use std::{collections::HashMap, str::FromStr};
+use time::{Time, format_description::BorrowedFormatItem, macros::format_description};
+use zbus::{Connection, zvariant::Value};
+
+use crate::{clock, config::NotificationsConfig, db::Db};
+
+/// Bus name and interface of the notification server.
+const SERVICE: &str = "org.freedesktop.Notifications";
+
+/// Object path of the notification server.
+const PATH: &str = "/org/freedesktop/Notifications";
+
+/// Format of the start and end of quiet hours.
+const HH_MM: &[BorrowedFormatItem] = format_description!("[hour]:[minute]");
+
+/// A time range of the day in which no notifications are shown. The range wraps around
+/// midnight when it ends before it starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    start: Time,
+    end: Time,
+}
+
+impl QuietHours {
+    /// Whether a time of the day is within the quiet hours.
+    pub fn contains(&self, time: Time) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl FromStr for QuietHours {
+    type Err = anyhow::Error;
+
+    /// Parse quiet hours written as `HH:MM-HH:MM`.
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |time: &str| {
+            Time::parse(time.trim(), HH_MM)
+                .with_context(|| format!("invalid time `{}`, expected HH:MM", time.trim()))
+        };
+        let (start, end) = s
+            .split_once('-')
+            .with_context(|| format!("invalid quiet hours `{}`, expected HH:MM-HH:MM", s))?;
+        Ok(Self {
+            start: parse(start)?,
+            end: parse(end)?,
+        })
+    }
+}
+
+/// A notification to show.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// Summary line, naming the feed.
+    pub summary: String,
+    /// Body listing the new items. Markup characters are escaped.
+    pub body: String,
+}
+
+/// A client of the notification server.
+pub struct Notifier {
+    connection: Connection,
+}
+
+impl Notifier {
+    /// Connect to the notification server on the session bus.
+    pub async fn session() -> Result<Self> {
+        let connection = Connection::session()
+            .await
+            .context("failed to connect to the D-Bus session bus")?;
+        Ok(Self::new(connection))
+    }
+
+    /// Use a notification server reachable over a connection.
+    pub fn new(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    /// Show a notification, returning the ID the server gave it.
+    pub async fn send(&self, notification: &Notification) -> Result<u32> {
+        let actions: Vec<&str> = Vec::new();
+        let hints: HashMap<&str, Value> = HashMap::new();
+        let reply = self
+            .connection
+            .call_method(
+                Some(SERVICE),
+                PATH,
+                Some(SERVICE),
+                "Notify",
+                &(
+                    "ferrofeed",
+                    0u32,
+                    "",
+                    notification.summary.as_str(),
+                    notification.body.as_str(),
+                    actions,
+                    hints,
+                    // Expire when the server decides
+                    -1i32,
+                ),
+            )
+            .await
+            .context("failed to show notification")?;
+        Ok(reply.body().deserialize()?)
+    }
+}
+
+/// Summarize the new items with the given IDs, one notification for each feed that opted in,
+/// listing up to `max_titles` item titles.
+pub fn summarize(db: &Db, item_ids: &[usize], max_titles: usize) -> Result<Vec<Notification>> {
+    let feeds = db.list_feeds()?;
+    // Keep feeds in the order their first new item came in
+    let mut titles_by_feed: Vec<(usize, Vec<String>)> = Vec::new();
+    for &item_id in item_ids {
+        // Items may have been deleted by a rule
+        let Some(item) = db.get_feed_item(item_id)? else {
+            continue;
+        };
+        let title = item.title.unwrap_or_else(|| "(untitled)".to_string());
+        match titles_by_feed
+            .iter_mut()
+            .find(|(id, _)| *id == item.feed_id)
+        {
+            Some((_, titles)) => titles.push(title),
+            None => titles_by_feed.push((item.feed_id, vec![title])),
+        }
+    }
+
+    let mut notifications = Vec::new();
+    for (feed_id, titles) in titles_by_feed {
+        let Some(feed) = feeds.iter().find(|f| f.id == feed_id) else {
+            continue;
+        };
+        if !db.get_feed_settings(feed_id)?.notify {
+            continue;
+        }
+        let count = titles.len();
+        let mut lines: Vec<String> = titles
+            .iter()
+            .take(max_titles)
+            .map(|title| format!("• {}", html_escape::encode_text(title)))
+            .collect();
+        if count > max_titles {
+            lines.push(format!("and {} more", count - max_titles));
+        }
+        notifications.push(Notification {
+            summary: format!(
+                "{}: {} new item{}",
+                feed.title.as_deref().unwrap_or(&feed.url),
+                count,
+                if count == 1 { "" } else { "s" }
+            ),
+            body: lines.join("\n"),
+        });
+    }
+    Ok(notifications)
+}
+
+/// Show notifications for the new items with the given IDs, if enabled and outside the quiet
+/// hours. Returns the number of notifications shown.
+pub async fn notify_new_items(
+    db: &Db,
+    config: &NotificationsConfig,
+    item_ids: &[usize],
+) -> Result<usize> {
+    if !config.enabled || item_ids.is_empty() {
+        return Ok(0);
+    }
+    if let Some(quiet_hours) = &config.quiet_hours {
+        let quiet_hours: QuietHours = quiet_hours.parse()?;
+        if quiet_hours.contains(clock::now_local().time()) {
+            return Ok(0);
+        }
+    }
+    let notifications = summarize(db, item_ids, config.max_titles)?;
+    if notifications.is_empty() {
+        return Ok(0);
+    }
+    let notifier = Notifier::session().await?;
+    for notification in &notifications {
+        notifier.send(notification).await?;
+    }
+    Ok(notifications.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::ParsedFeedItem,
+        db::test_util::{self, add_item, test_db},
+    };
+    use std::sync::{Arc, Mutex};
+    use zbus::{Guid, connection::Builder, zvariant::OwnedValue};
+
+    /// A notification server recording the summary and body of each notification.
+    struct MockServer {
+        received: Arc<Mutex<Vec<(String, String)>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl MockServer {
+        // The signature is set by the spec
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            app_name: String,
+            _replaces_id: u32,
+            _app_icon: String,
+            summary: String,
+            body: String,
+            _actions: Vec<String>,
+            _hints: HashMap<String, OwnedValue>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            assert_eq!(app_name, "ferrofeed");
+            let mut received = self.received.lock().unwrap();
+            received.push((summary, body));
+            received.len() as u32
+        }
+    }
+
+    /// Add a feed with items of the given titles, returning the item IDs.
+    fn add_feed(db: &Db, url: &str, title: &str, notify: bool, items: &[&str]) -> Vec<usize> {
+        let feed_id = test_util::add_feed(db, url, title);
+        let mut settings = db.get_feed_settings(feed_id).unwrap();
+        settings.notify = notify;
+        db.update_feed(feed_id, &settings).unwrap();
+        items
+            .iter()
+            .map(|title| {
+                let item = ParsedFeedItem {
+                    title: Some(title.to_string()),
+                    link: Some(format!("{}/{}", url, title.replace(' ', "-"))),
+                    ..Default::default()
+                };
+                add_item(db, feed_id, &item)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let time = |s| Time::parse(s, HH_MM).unwrap();
+        let night: QuietHours = "22:00-07:00".parse().unwrap();
+        assert!(night.contains(time("23:30")));
+        assert!(night.contains(time("06:59")));
+        assert!(!night.contains(time("07:00")));
+        assert!(!night.contains(time("12:00")));
+
+        let lunch: QuietHours = " 12:00 - 13:30 ".parse().unwrap();
+        assert!(lunch.contains(time("12:45")));
+        assert!(!lunch.contains(time("21:00")));
+
+        assert!("22:00".parse::<QuietHours>().is_err());
+        assert!("25:00-07:00".parse::<QuietHours>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_notify_over_dbus() {
+        let db = test_db();
+        let mut item_ids = add_feed(
+            &db,
+            "https://status.example.com/feed",
+            "Status",
+            true,
+            &["Outage", "Degraded <API>", "Resolved"],
+        );
+        item_ids.extend(add_feed(
+            &db,
+            "https://blog.example.com/feed",
+            "Blog",
+            false,
+            &["Release notes"],
+        ));
+
+        let notifications = summarize(&db, &item_ids, 2).unwrap();
+        assert_eq!(
+            notifications,
+            vec![Notification {
+                summary: "Status: 3 new items".to_string(),
+                body: "• Outage\n• Degraded &lt;API&gt;\nand 1 more".to_string(),
+            }]
+        );
+
+        // A peer-to-peer connection stands in for the session bus
+        let (server_stream, client_stream) = tokio::net::UnixStream::pair().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let server = MockServer {
+            received: received.clone(),
+        };
+        let (server, client) = tokio::join!(
+            async {
+                Builder::unix_stream(server_stream)
+                    .server(Guid::generate())
+                    .unwrap()
+                    .p2p()
+                    .serve_at(PATH, server)
+                    .unwrap()
+                    .build()
+                    .await
+            },
+            Builder::unix_stream(client_stream).p2p().build(),
+        );
+        let _server = server.unwrap();
+        let notifier = Notifier::new(client.unwrap());
+
+        assert_eq!(notifier.send(&notifications[0]).await.unwrap(), 1);
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![(
+                notifications[0].summary.clone(),
+                notifications[0].body.clone()
+            )]
+        );
+    }
+}
@@ -0,0 +1,276 @@
+/*!
+OPML import and export of feeds and their folders.
+
+* Folders are written as outlines containing the outlines of their subfolders and feeds, so
+  that other feed readers recreate the same hierarchy.
+* On import, outlines with an `xmlUrl` are feeds and any other outlines are folders.
+*/
+use anyhow::{Context, Result};
+use html_escape::encode_double_quoted_attribute as escape;
+use quick_xml::{
+    encoding::Decoder,
+    events::{BytesStart, Event},
+};
+use std::fmt::Write;
+
+use crate::db::{Db, Feed, Folder};
+
+/// What an OPML import did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Number of feeds that weren't in the database yet.
+    pub added: usize,
+    /// Number of feeds that were already in the database. They're moved to the folder they
+    /// have in the OPML.
+    pub existing: usize,
+}
+
+/// Export feeds as an OPML document, nested in their folders. With no URLs, every feed is
+/// exported; otherwise only the feeds with the given URLs.
+pub fn export(db: &Db, urls: &[String]) -> Result<String> {
+    let mut feeds = db.list_feeds().context("failed to list feeds")?;
+    if !urls.is_empty() {
+        if let Some(missing) = urls
+            .iter()
+            .find(|url| !feeds.iter().any(|f| &f.url == *url))
+        {
+            anyhow::bail!("feed not found: {}", missing);
+        }
+        feeds.retain(|f| urls.contains(&f.url));
+    }
+    let folders = db.list_folders().context("failed to list folders")?;
+
+    let mut out = String::new();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<opml version="2.0">"#)?;
+    writeln!(out, "  <head>")?;
+    writeln!(out, "    <title>ferrofeed feeds</title>")?;
+    writeln!(out, "  </head>")?;
+    writeln!(out, "  <body>")?;
+    let outlines = Outlines {
+        db,
+        folders: &folders,
+        feeds: &feeds,
+    };
+    outlines.write(&mut out, None, 2)?;
+    writeln!(out, "  </body>")?;
+    writeln!(out, "</opml>")?;
+    Ok(out)
+}
+
+/// Feeds and folders being written as outlines.
+struct Outlines<'a> {
+    db: &'a Db,
+    folders: &'a [Folder],
+    feeds: &'a [Feed],
+}
+
+impl Outlines<'_> {
+    /// Write the outlines of the contents of a folder, or of the top level. Folders without
+    /// any of the feeds are left out.
+    fn write(&self, out: &mut String, parent_id: Option<usize>, depth: usize) -> Result<()> {
+        let indent = "  ".repeat(depth);
+        for folder in self.folders.iter().filter(|f| f.parent_id == parent_id) {
+            if !self.contains_feeds(folder.id) {
+                continue;
+            }
+            let name = escape(&folder.name);
+            writeln!(out, r#"{indent}<outline text="{name}" title="{name}">"#)?;
+            self.write(out, Some(folder.id), depth + 1)?;
+            writeln!(out, "{indent}</outline>")?;
+        }
+        for feed in self.feeds.iter().filter(|f| self.parent_of(f) == parent_id) {
+            let title = escape(feed.title.as_deref().unwrap_or(&feed.url)).into_owned();
+            write!(
+                out,
+                r#"{indent}<outline type="rss" text="{title}" title="{title}" xmlUrl="{}""#,
+                escape(&feed.url)
+            )?;
+            if let Some(site_link) = self.db.get_feed_metadata(feed.id)?.site_link {
+                write!(out, r#" htmlUrl="{}""#, escape(&site_link))?;
+            }
+            writeln!(out, "/>")?;
+        }
+        Ok(())
+    }
+
+    /// The folder a feed is written in.
+    fn parent_of(&self, feed: &Feed) -> Option<usize> {
+        feed.folder_id
+            .filter(|id| self.folders.iter().any(|f| f.id == *id))
+    }
+
+    /// Whether a folder or its subfolders contain any of the feeds.
+    fn contains_feeds(&self, folder_id: usize) -> bool {
+        self.feeds
+            .iter()
+            .any(|f| self.parent_of(f) == Some(folder_id))
+            || self
+                .folders
+                .iter()
+                .filter(|f| f.parent_id == Some(folder_id))
+                .any(|f| self.contains_feeds(f.id))
+    }
+}
+
+/// Import the feeds and folders of an OPML document. Feeds are added without fetching them;
+/// their items arrive with the next sync.
+pub fn import(db: &Db, content: &str) -> Result<ImportSummary> {
+    let mut reader = quick_xml::Reader::from_str(content);
+    let mut summary = ImportSummary::default();
+    // The folder of each open outline; feed outlines keep the folder they're in
+    let mut open: Vec<Option<usize>> = Vec::new();
+
+    loop {
+        let (element, has_children) = match reader.read_event().context("invalid OPML")? {
+            Event::Start(e) => (e, true),
+            Event::Empty(e) => (e, false),
+            Event::End(e) if e.name().as_ref() == b"outline" => {
+                open.pop();
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        if element.name().as_ref() != b"outline" {
+            continue;
+        }
+
+        let decoder = reader.decoder();
+        let parent = open.last().copied().flatten();
+        let folder = match attribute(&element, "xmlUrl", decoder)? {
+            Some(url) => {
+                let title = attribute(&element, "title", decoder)?
+                    .or(attribute(&element, "text", decoder)?);
+                import_feed(db, &url, title.as_deref(), parent, &mut summary)?;
+                parent
+            }
+            None => {
+                let name = attribute(&element, "text", decoder)?
+                    .or(attribute(&element, "title", decoder)?);
+                match name.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+                    Some(name) => Some(db.create_folder(name, parent)?),
+                    None => parent,
+                }
+            }
+        };
+        if has_children {
+            open.push(folder);
+        }
+    }
+    Ok(summary)
+}
+
+/// Add a feed from an OPML outline if it's new, and move it to its folder.
+fn import_feed(
+    db: &Db,
+    url: &str,
+    title: Option<&str>,
+    folder_id: Option<usize>,
+    summary: &mut ImportSummary,
+) -> Result<()> {
+    let existing = db.list_feeds()?.into_iter().find(|f| f.url == url);
+    let feed_id = match existing {
+        Some(feed) => {
+            summary.existing += 1;
+            feed.id
+        }
+        None => {
+            db.add_feed(url, title)
+                .with_context(|| format!("failed to add feed {}", url))?;
+            summary.added += 1;
+            db.list_feeds()?
+                .into_iter()
+                .find(|f| f.url == url)
+                .context("failed to find added feed")?
+                .id
+        }
+    };
+    db.set_feed_folder(feed_id, folder_id)
+}
+
+/// The unescaped value of an attribute of an element, if present.
+fn attribute(element: &BytesStart, name: &str, decoder: Decoder) -> Result<Option<String>> {
+    match element.try_get_attribute(name)? {
+        Some(attribute) => Ok(Some(
+            attribute.decode_and_unescape_value(decoder)?.into_owned(),
+        )),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_util::test_db;
+
+    const OPML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <opml version="2.0">
+          <head><title>Subscriptions</title></head>
+          <body>
+            <outline text="Tech">
+              <outline text="Rust">
+                <outline type="rss" text="Rust Blog" xmlUrl="https://blog.rust-lang.org/feed.xml"/>
+              </outline>
+              <outline type="rss" text="Tom &amp; Co" xmlUrl="https://example.com/feed?a=1&amp;b=2"/>
+            </outline>
+            <outline type="rss" title="Loose" xmlUrl="https://loose.example.com/rss"/>
+          </body>
+        </opml>"#;
+
+    #[test]
+    fn test_import_nested_folders() {
+        let db = test_db();
+        let summary = import(&db, OPML).expect("failed to import");
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 3,
+                existing: 0
+            }
+        );
+
+        let feeds = db.list_feeds().unwrap();
+        let folder_of = |url: &str| {
+            let feed = feeds.iter().find(|f| f.url == url).expect("feed not found");
+            feed.folder_id.map(|id| db.folder_path(id).unwrap())
+        };
+        assert_eq!(
+            folder_of("https://blog.rust-lang.org/feed.xml").as_deref(),
+            Some("Tech/Rust")
+        );
+        assert_eq!(
+            folder_of("https://example.com/feed?a=1&b=2").as_deref(),
+            Some("Tech")
+        );
+        assert_eq!(folder_of("https://loose.example.com/rss"), None);
+
+        // Importing again only moves feeds to their folders
+        let summary = import(&db, OPML).expect("failed to import");
+        assert_eq!(summary.existing, 3);
+        assert_eq!(db.list_folders().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let db = test_db();
+        import(&db, OPML).expect("failed to import");
+        let exported = export(&db, &[]).expect("failed to export");
+        assert!(exported.contains(r#"text="Tom &amp; Co""#));
+
+        let copy = test_db();
+        import(&copy, &exported).expect("failed to import export");
+        let paths = |db: &Db| -> Vec<(String, Option<String>)> {
+            db.list_feeds()
+                .unwrap()
+                .into_iter()
+                .map(|f| (f.url, f.folder_id.map(|id| db.folder_path(id).unwrap())))
+                .collect()
+        };
+        assert_eq!(paths(&db), paths(&copy));
+
+        let selected = export(&db, &["https://loose.example.com/rss".to_string()]).unwrap();
+        assert!(!selected.contains("Tech"));
+        assert!(export(&db, &["https://missing.example.com".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+/*!
+Machine-readable output of CLI commands, for scripts and dashboards.
+
+Commands turn what they list into records with stable field names, written as a JSON array,
+as newline-delimited JSON (one object per line) or as CSV with a header row. Times are RFC 3339
+in UTC, and lists in CSV cells are joined with `; `.
+*/
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+
+use crate::{
+    clock::format_rfc3339,
+    db::{Feed, FeedItem},
+    remote::SyncSummary,
+};
+
+/// Exit code of commands that partly failed, e.g. a sync in which some feeds failed.
+pub const PARTIAL_FAILURE: u8 = 2;
+
+/// Format of command output.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Text for people to read.
+    #[default]
+    Text,
+    /// A JSON array of records.
+    Json,
+    /// One JSON record per line.
+    Ndjson,
+    /// CSV with a header row.
+    Csv,
+}
+
+/// A record with a fixed set of fields, so that CSV output has a header even without records.
+pub trait Record: Serialize {
+    /// Names of the serialized fields, in order.
+    const FIELDS: &'static [&'static str];
+}
+
+/// A feed, as listed by `list`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeedRecord {
+    /// Database ID of the feed.
+    pub id: usize,
+    /// URL the feed is fetched from.
+    pub url: String,
+    /// Title of the feed, if it has one.
+    pub title: Option<String>,
+    /// Number of unread items.
+    pub unread_count: usize,
+    /// When the newest item was published.
+    pub last_updated: Option<String>,
+    /// When the feed was added.
+    pub created_at: String,
+    /// Whether syncing the feed is paused.
+    pub paused: bool,
+    /// Whether the feed is gone from its URL.
+    pub gone: bool,
+}
+
+impl Record for FeedRecord {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "url",
+        "title",
+        "unread_count",
+        "last_updated",
+        "created_at",
+        "paused",
+        "gone",
+    ];
+}
+
+impl From<&Feed> for FeedRecord {
+    fn from(feed: &Feed) -> Self {
+        Self {
+            id: feed.id,
+            url: feed.url.clone(),
+            title: feed.title.clone(),
+            unread_count: feed.unread_count,
+            last_updated: feed.last_updated.map(format_rfc3339),
+            created_at: format_rfc3339(feed.created_at),
+            paused: feed.is_paused,
+            gone: feed.is_gone,
+        }
+    }
+}
+
+/// An item, as listed by `items` and `search`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ItemRecord {
+    /// Database ID of the item.
+    pub id: usize,
+    /// Database ID of the feed of the item.
+    pub feed_id: usize,
+    /// Title of the feed, or its URL if it has no title.
+    pub feed: Option<String>,
+    /// Title of the item.
+    pub title: Option<String>,
+    /// Link to the item on the web.
+    pub link: Option<String>,
+    /// Names of the authors.
+    pub authors: Vec<String>,
+    /// When the item was published.
+    pub published: Option<String>,
+    /// Whether the item has been read.
+    pub read: bool,
+    /// Whether the item is starred.
+    pub starred: bool,
+    /// Tags added to the item.
+    pub tags: Vec<String>,
+    /// Categories the feed gave the item.
+    pub categories: Vec<String>,
+}
+
+impl Record for ItemRecord {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "feed_id",
+        "feed",
+        "title",
+        "link",
+        "authors",
+        "published",
+        "read",
+        "starred",
+        "tags",
+        "categories",
+    ];
+}
+
+impl ItemRecord {
+    /// The record of an item of a feed.
+    pub fn new(item: &FeedItem, feed: Option<&Feed>) -> Self {
+        Self {
+            id: item.id,
+            feed_id: item.feed_id,
+            feed: feed.map(|f| f.title.clone().unwrap_or_else(|| f.url.clone())),
+            title: item.title.clone(),
+            link: item.link.clone(),
+            authors: item.authors.clone(),
+            published: item.published.map(format_rfc3339),
+            read: item.is_read,
+            starred: item.is_starred,
+            tags: item.tags.clone(),
+            categories: item.categories.clone(),
+        }
+    }
+}
+
+/// How syncing a feed went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncStatus {
+    /// The feed was fetched.
+    Ok,
+    /// The feed is paused and was skipped.
+    Paused,
+    /// The feed is gone, now or before.
+    Gone,
+    /// Fetching the feed failed.
+    Failed,
+}
+
+/// The result of syncing a feed, as listed by `sync`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeedSyncRecord {
+    /// Database ID of the feed.
+    pub feed_id: usize,
+    /// URL the feed was fetched from.
+    pub url: String,
+    /// Title of the feed, if it has one.
+    pub title: Option<String>,
+    /// How syncing the feed went.
+    pub status: SyncStatus,
+    /// Number of new items.
+    pub new_items: usize,
+    /// Number of items that changed since they were last synced.
+    pub updated_items: usize,
+    /// Where the feed permanently moved to, if its URL was updated.
+    pub moved_to: Option<String>,
+    /// Why the feed failed or is gone.
+    pub error: Option<String>,
+}
+
+impl Record for FeedSyncRecord {
+    const FIELDS: &'static [&'static str] = &[
+        "feed_id",
+        "url",
+        "title",
+        "status",
+        "new_items",
+        "updated_items",
+        "moved_to",
+        "error",
+    ];
+}
+
+impl Record for SyncSummary {
+    const FIELDS: &'static [&'static str] = &[
+        "subscribed",
+        "unsubscribed",
+        "feeds_added",
+        "feeds_removed",
+        "new_items",
+        "pushed",
+        "pulled",
+        "failed",
+    ];
+}
+
+/// Write records in a machine-readable format. Text output is up to each command.
+pub fn write_records<T: Record>(
+    out: &mut dyn Write,
+    format: OutputFormat,
+    records: &[T],
+) -> Result<()> {
+    match format {
+        OutputFormat::Text => anyhow::bail!("records have no text format"),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, records)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            for record in records {
+                serde_json::to_writer(&mut *out, record)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(T::FIELDS)?;
+            for record in records {
+                let Value::Object(fields) = serde_json::to_value(record)? else {
+                    anyhow::bail!("only records with fields can be written as CSV");
+                };
+                writer.write_record(
+                    T::FIELDS
+                        .iter()
+                        .map(|name| fields.get(*name).map(csv_cell).unwrap_or_default()),
+                )?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Write records to stdout in a machine-readable format.
+pub fn print_records<T: Record>(format: OutputFormat, records: &[T]) -> Result<()> {
+    write_records(&mut std::io::stdout().lock(), format, records)
+}
+
+/// The CSV cell of a field.
+fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(csv_cell).collect::<Vec<_>>().join("; "),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<FeedSyncRecord> {
+        vec![
+            FeedSyncRecord {
+                feed_id: 1,
+                url: "https://example.com/feed.xml".to_string(),
+                title: Some("Example, \"quoted\"".to_string()),
+                status: SyncStatus::Ok,
+                new_items: 2,
+                updated_items: 0,
+                moved_to: None,
+                error: None,
+            },
+            FeedSyncRecord {
+                feed_id: 2,
+                url: "https://down.example.com/rss".to_string(),
+                title: None,
+                status: SyncStatus::Failed,
+                new_items: 0,
+                updated_items: 0,
+                moved_to: None,
+                error: Some("HTTP 500".to_string()),
+            },
+        ]
+    }
+
+    fn write(format: OutputFormat, records: &[impl Record]) -> String {
+        let mut out = Vec::new();
+        write_records(&mut out, format, records).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_records() {
+        let records = records();
+
+        let json: Value = serde_json::from_str(&write(OutputFormat::Json, &records)).unwrap();
+        assert_eq!(json[1]["status"], "failed");
+        assert_eq!(json[1]["error"], "HTTP 500");
+        assert_eq!(json[0]["moved_to"], Value::Null);
+
+        let ndjson = write(OutputFormat::Ndjson, &records);
+        assert_eq!(ndjson.lines().count(), 2);
+        assert!(ndjson.starts_with("{\"feed_id\":1,\"url\":\"https://example.com/feed.xml\","));
+
+        assert_eq!(
+            write(OutputFormat::Csv, &records),
+            "feed_id,url,title,status,new_items,updated_items,moved_to,error\n\
+             1,https://example.com/feed.xml,\"Example, \"\"quoted\"\"\",ok,2,0,,\n\
+             2,https://down.example.com/rss,,failed,0,0,,HTTP 500\n"
+        );
+
+        let item = ItemRecord {
+            id: 7,
+            feed_id: 1,
+            feed: Some("Example".to_string()),
+            title: Some("Post".to_string()),
+            link: None,
+            authors: vec!["Ann".to_string(), "Bo".to_string()],
+            published: Some(format_rfc3339(1_714_564_800)),
+            read: false,
+            starred: true,
+            tags: vec![],
+            categories: vec!["rust".to_string()],
+        };
+        assert_eq!(
+            write(OutputFormat::Csv, &[item]),
+            "id,feed_id,feed,title,link,authors,published,read,starred,tags,categories\n\
+             7,1,Example,Post,,Ann; Bo,2024-05-01T12:00:00Z,false,true,,rust\n"
+        );
+        assert!(write_records(&mut Vec::new(), OutputFormat::Text, &records).is_err());
+
+        // The header comes from the type, so it's there without records too
+        assert_eq!(
+            write(OutputFormat::Csv, &[] as &[FeedRecord]),
+            "id,url,title,unread_count,last_updated,created_at,paused,gone\n"
+        );
+        assert_eq!(write(OutputFormat::Json, &[] as &[FeedRecord]), "[]\n");
+    }
+
+    /// The names of the fields of a record, as serialized.
+    fn field_names(record: impl Record) -> Vec<String> {
+        match serde_json::to_value(record).unwrap() {
+            Value::Object(fields) => fields.keys().cloned().collect(),
+            _ => panic!("record has no fields"),
+        }
+    }
+
+    #[test]
+    fn test_record_fields() {
+        let feed = FeedRecord {
+            id: 1,
+            url: String::new(),
+            title: None,
+            unread_count: 0,
+            last_updated: None,
+            created_at: String::new(),
+            paused: false,
+            gone: false,
+        };
+        assert_eq!(field_names(feed), FeedRecord::FIELDS);
+        let item = ItemRecord {
+            id: 1,
+            feed_id: 1,
+            feed: None,
+            title: None,
+            link: None,
+            authors: vec![],
+            published: None,
+            read: false,
+            starred: false,
+            tags: vec![],
+            categories: vec![],
+        };
+        assert_eq!(field_names(item), ItemRecord::FIELDS);
+        assert_eq!(field_names(records().remove(0)), FeedSyncRecord::FIELDS);
+        assert_eq!(field_names(SyncSummary::default()), SyncSummary::FIELDS);
+    }
+}
@@ -0,0 +1,622 @@
+/*!
+A feed re-publishing selected items of the store as Atom or RSS 2.0, e.g. a "planet" of the
+posts of a team.
+
+The items are picked by the tag, starred and rule filters of the `[publish]` section of the
+config. Entries keep the ID their original feed gave them when it is a URI, and credit the
+feed they come from: with an `<atom:source>` element in Atom and a `<source>` element in RSS.
+*/
+use anyhow::{Context, Result};
+use axum::{
+    Router,
+    extract::State,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use md5::{Digest, Md5};
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+
+use crate::{
+    clock::{format_rfc2822, format_rfc3339},
+    config::PublishConfig,
+    db::{Db, Feed, FeedItem, ItemSelection},
+    rules::RuleSet,
+};
+
+/// Maximum number of items read from the database at once while picking the entries.
+const MAX_PAGE: usize = 200;
+
+/// Format of the published feed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PublishFormat {
+    /// An Atom feed.
+    #[default]
+    Atom,
+    /// An RSS 2.0 feed.
+    Rss,
+}
+
+impl PublishFormat {
+    /// The media type of the feed.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Atom => "application/atom+xml; charset=utf-8",
+            Self::Rss => "application/rss+xml; charset=utf-8",
+        }
+    }
+}
+
+/// An item to publish, with the feed it comes from.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// The item.
+    pub item: FeedItem,
+    /// The feed of the item.
+    pub feed: Feed,
+    /// Permanent ID of the entry.
+    pub id: String,
+    /// When the entry last changed (Unix timestamp).
+    pub updated: i64,
+}
+
+/// Collect the newest items matching the filters of the config, newest first. Items that
+/// duplicate an item of another feed are left out.
+pub fn collect(db: &Db, config: &PublishConfig, rules: &RuleSet) -> Result<Vec<Entry>> {
+    let rule = config
+        .rule
+        .as_deref()
+        .map(|name| {
+            rules
+                .rules
+                .iter()
+                .find(|r| r.name == name)
+                .with_context(|| format!("no rule named `{}` in the rules file", name))
+        })
+        .transpose()?;
+    let selection = ItemSelection {
+        tag: config.tag.clone(),
+        is_starred: config.starred.then_some(true),
+        ..Default::default()
+    };
+    let feeds = db.list_feeds()?;
+
+    // Page through the newest items until enough of them pass the rule
+    let page_size = config.limit.min(MAX_PAGE);
+    let mut items: Vec<(FeedItem, &Feed)> = Vec::new();
+    let mut offset = 0;
+    while items.len() < config.limit {
+        let page = db.get_newest_items(&selection, offset, page_size)?;
+        offset += page.len();
+        let is_last = page.len() < page_size;
+        items.extend(
+            page.into_iter()
+                .filter_map(|item| {
+                    let feed = feeds.iter().find(|f| f.id == item.feed_id)?;
+                    Some((item, feed))
+                })
+                .filter(|(item, feed)| rule.is_none_or(|rule| rule.matches(feed, item))),
+        );
+        if is_last {
+            break;
+        }
+    }
+    items.truncate(config.limit);
+
+    items
+        .into_iter()
+        .map(|(item, feed)| {
+            let (guid, updated) = db.get_item_origin(item.id)?.unwrap_or_default();
+            let id = match guid {
+                // Keep the ID of entries that already have a permanent one
+                Some(guid) if url::Url::parse(&guid).is_ok() => guid,
+                guid => {
+                    let key = guid
+                        .or_else(|| item.link.clone())
+                        .unwrap_or_else(|| item.id.to_string());
+                    format!(
+                        "urn:ferrofeed:{}",
+                        md5_hex(&format!("{}\n{}", feed.url, key))
+                    )
+                }
+            };
+            Ok(Entry {
+                id,
+                updated: updated.or(item.published).unwrap_or(item.created_at),
+                feed: feed.clone(),
+                item,
+            })
+        })
+        .collect()
+}
+
+/// Render entries as a feed document.
+pub fn render(format: PublishFormat, config: &PublishConfig, entries: &[Entry]) -> String {
+    match format {
+        PublishFormat::Atom => atom(config, entries),
+        PublishFormat::Rss => rss(config, entries),
+    }
+}
+
+/// Render entries as an Atom feed.
+fn atom(config: &PublishConfig, entries: &[Entry]) -> String {
+    let mut doc = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <feed xmlns=\"http://www.w3.org/2005/Atom\">\n",
+    );
+    doc.push_str(&format!("<id>{}</id>\n", escape(&feed_id(config))));
+    doc.push_str(&format!("<title>{}</title>\n", escape(&config.title)));
+    if let Some(description) = &config.description {
+        doc.push_str(&format!("<subtitle>{}</subtitle>\n", escape(description)));
+    }
+    if let Some(url) = &config.url {
+        doc.push_str(&format!(
+            "<link rel=\"alternate\" href=\"{}\" />\n",
+            escape(url)
+        ));
+    }
+    doc.push_str(&format!(
+        "<updated>{}</updated>\n",
+        format_rfc3339(last_updated(entries))
+    ));
+    // Entries without authors are credited to the feed's author
+    doc.push_str(&format!(
+        "<author><name>{}</name></author>\n",
+        escape(config.author.as_deref().unwrap_or(&config.title))
+    ));
+    doc.push_str(&format!(
+        "<generator version=\"{}\">ferrofeed</generator>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+
+    for entry in entries {
+        let item = &entry.item;
+        doc.push_str("<entry>\n");
+        doc.push_str(&format!("<id>{}</id>\n", escape(&entry.id)));
+        doc.push_str(&format!(
+            "<title>{}</title>\n",
+            escape(item.title.as_deref().unwrap_or_default())
+        ));
+        if let Some(link) = &item.link {
+            doc.push_str(&format!(
+                "<link rel=\"alternate\" href=\"{}\" />\n",
+                escape(link)
+            ));
+        }
+        doc.push_str(&format!(
+            "<updated>{}</updated>\n",
+            format_rfc3339(entry.updated)
+        ));
+        if let Some(published) = item.published {
+            doc.push_str(&format!(
+                "<published>{}</published>\n",
+                format_rfc3339(published)
+            ));
+        }
+        for author in &item.authors {
+            doc.push_str(&format!(
+                "<author><name>{}</name></author>\n",
+                escape(author)
+            ));
+        }
+        for tag in &item.tags {
+            doc.push_str(&format!("<category term=\"{}\" />\n", escape(tag)));
+        }
+        if let Some(description) = &item.description {
+            doc.push_str(&format!(
+                "<content type=\"html\">{}</content>\n",
+                escape(description)
+            ));
+        }
+        doc.push_str(&format!(
+            "<source>\n<id>{}</id>\n<title>{}</title>\n<link rel=\"self\" href=\"{}\" />\n\
+             <updated>{}</updated>\n</source>\n",
+            escape(&entry.feed.url),
+            escape(feed_title(&entry.feed)),
+            escape(&entry.feed.url),
+            format_rfc3339(entry.feed.last_updated.unwrap_or(entry.updated))
+        ));
+        doc.push_str("</entry>\n");
+    }
+    doc.push_str("</feed>\n");
+    doc
+}
+
+/// Render entries as an RSS 2.0 feed.
+fn rss(config: &PublishConfig, entries: &[Entry]) -> String {
+    let mut doc = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <rss version=\"2.0\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+         <channel>\n",
+    );
+    doc.push_str(&format!("<title>{}</title>\n", escape(&config.title)));
+    if let Some(url) = &config.url {
+        doc.push_str(&format!("<link>{}</link>\n", escape(url)));
+    }
+    doc.push_str(&format!(
+        "<description>{}</description>\n",
+        escape(config.description.as_deref().unwrap_or(&config.title))
+    ));
+    doc.push_str(&format!(
+        "<lastBuildDate>{}</lastBuildDate>\n",
+        format_rfc2822(last_updated(entries))
+    ));
+    doc.push_str(&format!(
+        "<generator>ferrofeed {}</generator>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+
+    for entry in entries {
+        let item = &entry.item;
+        doc.push_str("<item>\n");
+        doc.push_str(&format!(
+            "<title>{}</title>\n",
+            escape(item.title.as_deref().unwrap_or_default())
+        ));
+        if let Some(link) = &item.link {
+            doc.push_str(&format!("<link>{}</link>\n", escape(link)));
+        }
+        doc.push_str(&format!(
+            "<guid isPermaLink=\"{}\">{}</guid>\n",
+            item.link.as_ref() == Some(&entry.id),
+            escape(&entry.id)
+        ));
+        if let Some(published) = item.published {
+            doc.push_str(&format!(
+                "<pubDate>{}</pubDate>\n",
+                format_rfc2822(published)
+            ));
+        }
+        // RSS authors are email addresses, so names go in Dublin Core creators
+        for author in &item.authors {
+            doc.push_str(&format!("<dc:creator>{}</dc:creator>\n", escape(author)));
+        }
+        for tag in &item.tags {
+            doc.push_str(&format!("<category>{}</category>\n", escape(tag)));
+        }
+        if let Some(description) = &item.description {
+            doc.push_str(&format!(
+                "<description>{}</description>\n",
+                escape(description)
+            ));
+        }
+        doc.push_str(&format!(
+            "<source url=\"{}\">{}</source>\n",
+            escape(&entry.feed.url),
+            escape(feed_title(&entry.feed))
+        ));
+        doc.push_str("</item>\n");
+    }
+    doc.push_str("</channel>\n</rss>\n");
+    doc
+}
+
+/// State shared by the handlers of the published feed.
+struct PublishState {
+    db: Mutex<Db>,
+    config: PublishConfig,
+    rules: RuleSet,
+}
+
+/// The routes serving the published feed at `/publish/atom.xml` and `/publish/rss.xml`.
+pub fn router(db: Db, config: PublishConfig, rules: RuleSet) -> Router {
+    let state = Arc::new(PublishState {
+        db: Mutex::new(db),
+        config,
+        rules,
+    });
+    Router::new()
+        .route(
+            "/publish/atom.xml",
+            get(|State(state): State<Arc<PublishState>>| async move {
+                respond(&state, PublishFormat::Atom)
+            }),
+        )
+        .route(
+            "/publish/rss.xml",
+            get(|State(state): State<Arc<PublishState>>| async move {
+                respond(&state, PublishFormat::Rss)
+            }),
+        )
+        .with_state(state)
+}
+
+/// Render the published feed for a request.
+fn respond(state: &PublishState, format: PublishFormat) -> Response {
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    match collect(&db, &state.config, &state.rules) {
+        Ok(entries) => (
+            [(header::CONTENT_TYPE, format.content_type())],
+            render(format, &state.config, &entries),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response(),
+    }
+}
+
+/// The ID of the published feed: its URL, or else one derived from its title.
+fn feed_id(config: &PublishConfig) -> String {
+    match &config.url {
+        Some(url) => url.clone(),
+        None => format!("urn:ferrofeed:{}", md5_hex(&config.title)),
+    }
+}
+
+/// Title of a feed, or its URL if it has no title.
+fn feed_title(feed: &Feed) -> &str {
+    feed.title.as_deref().unwrap_or(&feed.url)
+}
+
+/// When the newest entry last changed, or now if there are no entries.
+fn last_updated(entries: &[Entry]) -> i64 {
+    entries
+        .iter()
+        .map(|e| e.updated)
+        .max()
+        .unwrap_or_else(|| OffsetDateTime::now_utc().unix_timestamp())
+}
+
+/// The MD5 hash of text, in lowercase hex.
+fn md5_hex(text: &str) -> String {
+    Md5::digest(text)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Escape text for XML content and attributes, dropping characters XML does not allow.
+fn escape(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+        .collect();
+    html_escape::encode_double_quoted_attribute(&text).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::ParsedFeedItem,
+        db::test_util::{self, add_item, test_db},
+    };
+
+    /// Add a feed with the given items, returning their IDs.
+    fn add_feed(db: &Db, url: &str, title: &str, items: &[ParsedFeedItem]) -> Vec<usize> {
+        let feed_id = test_util::add_feed(db, url, title);
+        items
+            .iter()
+            .map(|item| add_item(db, feed_id, item))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_publish() {
+        let db = test_db();
+        let alice = add_feed(
+            &db,
+            "https://alice.example.com/feed.xml",
+            "Alice's blog",
+            &[
+                ParsedFeedItem {
+                    guid: Some("tag:alice.example.com,2024:1".to_string()),
+                    title: Some("Fearless <concurrency>".to_string()),
+                    link: Some("https://alice.example.com/1".to_string()),
+                    description: Some("<p>Threads &amp; more</p>".to_string()),
+                    authors: vec!["Alice".to_string()],
+                    published: Some(1_714_564_800),
+                    updated: Some(1_714_600_000),
+                    ..Default::default()
+                },
+                ParsedFeedItem {
+                    guid: Some("1234".to_string()),
+                    title: Some("Holiday photos".to_string()),
+                    link: Some("https://alice.example.com/2".to_string()),
+                    published: Some(1_714_700_000),
+                    ..Default::default()
+                },
+            ],
+        );
+        let bob = add_feed(
+            &db,
+            "https://bob.example.com/rss",
+            "Bob",
+            &[ParsedFeedItem {
+                title: Some("Async Rust".to_string()),
+                link: Some("https://bob.example.com/async".to_string()),
+                published: Some(1_714_650_000),
+                ..Default::default()
+            }],
+        );
+        for id in [alice[0], alice[1], bob[0]] {
+            db.add_item_tag(id, "team").unwrap();
+        }
+        let rules = RuleSet::parse(
+            "[[rule]]\nname = \"Rust\"\ntitle = \"(?i)rust|concurrency\"\nactions = [\"star\"]\n",
+        )
+        .unwrap();
+        let config = PublishConfig {
+            title: "Team planet".to_string(),
+            url: Some("https://planet.example.com/".to_string()),
+            tag: Some("team".to_string()),
+            rule: Some("Rust".to_string()),
+            ..Default::default()
+        };
+
+        let entries = collect(&db, &config, &rules).unwrap();
+        let titles: Vec<_> = entries.iter().map(|e| e.item.title.as_deref()).collect();
+        assert_eq!(titles, [Some("Async Rust"), Some("Fearless <concurrency>")]);
+        assert_eq!(entries[1].id, "tag:alice.example.com,2024:1");
+        assert_eq!(entries[1].updated, 1_714_600_000);
+        assert!(entries[0].id.starts_with("urn:ferrofeed:"));
+        let unknown_rule = PublishConfig {
+            rule: Some("Missing".to_string()),
+            ..Default::default()
+        };
+        assert!(collect(&db, &unknown_rule, &rules).is_err());
+
+        // Both documents parse back with their sources
+        let atom = render(PublishFormat::Atom, &config, &entries);
+        assert!(atom.contains("<title>Fearless &lt;concurrency&gt;</title>"));
+        assert!(atom.contains("<content type=\"html\">&lt;p&gt;Threads &amp;amp; more&lt;/p&gt;"));
+        assert!(atom.contains("<source>\n<id>https://alice.example.com/feed.xml</id>"));
+        let parsed = feed_rs::parser::parse(atom.as_bytes()).unwrap();
+        assert_eq!(parsed.id, "https://planet.example.com/");
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[1].id, "tag:alice.example.com,2024:1");
+        assert_eq!(parsed.entries[1].authors[0].name, "Alice");
+
+        let rss = render(PublishFormat::Rss, &config, &entries);
+        assert!(rss.contains("<source url=\"https://bob.example.com/rss\">Bob</source>"));
+        let parsed = feed_rs::parser::parse(rss.as_bytes()).unwrap();
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(
+            parsed.entries[0].links[0].href,
+            "https://bob.example.com/async"
+        );
+
+        // And are served without login
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, router(db, config, rules))
+                .await
+                .unwrap()
+        });
+        let response = reqwest::get(format!("{}/publish/rss.xml", base))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/rss+xml; charset=utf-8"
+        );
+        assert_eq!(response.text().await.unwrap(), rss);
+    }
+
+    /// An item published at a time.
+    fn item(title: &str, published: i64) -> ParsedFeedItem {
+        ParsedFeedItem {
+            title: Some(title.to_string()),
+            link: Some(format!("https://example.com/{}", published)),
+            published: Some(published),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_collect_pages_through_rule() {
+        let db = test_db();
+        let items: Vec<_> = (1..=7)
+            .map(|i| item(if i % 3 == 0 { "Rust" } else { "Other" }, i * 100))
+            .collect();
+        add_feed(&db, "https://example.com/feed.xml", "Example", &items);
+        let rules =
+            RuleSet::parse("[[rule]]\nname = \"Rust\"\ntitle = \"Rust\"\nactions = [\"star\"]\n")
+                .unwrap();
+        let published = |config: &PublishConfig| -> Vec<Option<i64>> {
+            collect(&db, config, &rules)
+                .unwrap()
+                .iter()
+                .map(|e| e.item.published)
+                .collect()
+        };
+
+        // The matching items are spread over several pages of the limit's size
+        let config = PublishConfig {
+            rule: Some("Rust".to_string()),
+            limit: 2,
+            ..Default::default()
+        };
+        assert_eq!(published(&config), [Some(600), Some(300)]);
+
+        let config = PublishConfig {
+            limit: 3,
+            ..Default::default()
+        };
+        assert_eq!(published(&config), [Some(700), Some(600), Some(500)]);
+    }
+
+    #[test]
+    fn test_stable_ids_without_guids() {
+        let entry_id = |earlier: &[ParsedFeedItem]| {
+            let db = test_db();
+            add_feed(&db, "https://other.example.com/feed.xml", "Other", earlier);
+            add_feed(
+                &db,
+                "https://example.com/feed.xml",
+                "Example",
+                &[item("Post", 100)],
+            );
+            let config = PublishConfig {
+                limit: 1,
+                ..Default::default()
+            };
+            collect(&db, &config, &RuleSet::default()).unwrap()[0]
+                .id
+                .clone()
+        };
+
+        // The ID doesn't depend on where the item is stored, or the database it's in
+        let id = entry_id(&[]);
+        assert!(id.starts_with("urn:ferrofeed:"));
+        assert_eq!(entry_id(&[item("Old", 50), item("Older", 40)]), id);
+        assert_eq!(
+            id,
+            format!(
+                "urn:ferrofeed:{}",
+                md5_hex("https://example.com/feed.xml\nhttps://example.com/100")
+            )
+        );
+    }
+
+    #[test]
+    fn test_rss_output() {
+        let db = test_db();
+        add_feed(
+            &db,
+            "https://example.com/feed.xml",
+            "Example",
+            &[
+                ParsedFeedItem {
+                    guid: Some("https://example.com/permalink".to_string()),
+                    link: Some("https://example.com/permalink".to_string()),
+                    ..item("Linked", 1_714_564_800)
+                },
+                item("Unlinked & <odd>", 1_714_478_400),
+            ],
+        );
+        let config = PublishConfig {
+            title: "Planet".to_string(),
+            description: Some("Posts of the team".to_string()),
+            ..Default::default()
+        };
+        let entries = collect(&db, &config, &RuleSet::default()).unwrap();
+        let rss = render(PublishFormat::Rss, &config, &entries);
+
+        assert!(rss.contains("<title>Planet</title>\n"));
+        assert!(rss.contains("<description>Posts of the team</description>\n"));
+        assert!(rss.contains(
+            "<guid isPermaLink=\"true\">https://example.com/permalink</guid>\n\
+             <pubDate>Wed, 01 May 2024 12:00:00 +0000</pubDate>\n"
+        ));
+        assert!(rss.contains("<title>Unlinked &amp; &lt;odd&gt;</title>"));
+        assert!(rss.contains(&format!(
+            "<guid isPermaLink=\"false\">{}</guid>",
+            entries[1].id
+        )));
+
+        let parsed = feed_rs::parser::parse(rss.as_bytes()).unwrap();
+        assert_eq!(parsed.title.unwrap().content, "Planet");
+        let published: Vec<_> = parsed
+            .entries
+            .iter()
+            .map(|e| e.published.map(|p| p.timestamp()))
+            .collect();
+        assert_eq!(published, [Some(1_714_564_800), Some(1_714_478_400)]);
+        assert_eq!(
+            parsed.entries[1].title.as_ref().unwrap().content,
+            "Unlinked & <odd>"
+        );
+    }
+}
@@ -0,0 +1,838 @@
+/*!
+Sync with a [Miniflux](https://miniflux.app) server as the backend, instead of fetching feeds
+directly.
+
+* Subscriptions are pulled from the server. Feeds without a folder are put in the folder of
+  their category, unless it's the default category. Feeds added here are subscribed on the
+  server, and feeds removed here are unsubscribed.
+* New items are pulled from the server. Items already fetched directly are matched by link.
+* Read and starred state is synced both ways. Each item keeps the state it had on the server
+  when last synced, so changes made here while offline are kept until a sync reaches the
+  server. When an item changed both here and on the server, the change made here wins.
+*/
+use anyhow::{Context, Result};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+use crate::{
+    client::{FeedClient, ParsedFeedItem},
+    config::RemoteConfig,
+    db::{Db, Feed, ItemChange},
+    rules::RuleSet,
+};
+
+/// Number of entries requested per page.
+const PAGE_SIZE: usize = 250;
+
+/// What a sync with the server did.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncSummary {
+    /// Number of feeds added here that were subscribed on the server.
+    pub subscribed: usize,
+    /// Number of feeds removed here that were unsubscribed on the server.
+    pub unsubscribed: usize,
+    /// Number of feeds subscribed on the server that were added here.
+    pub feeds_added: usize,
+    /// Number of feeds unsubscribed on the server that were removed here.
+    pub feeds_removed: usize,
+    /// IDs of the new items.
+    pub new_items: Vec<usize>,
+    /// Number of items whose read or starred state was sent to the server.
+    pub pushed: usize,
+    /// Number of items whose read or starred state was taken from the server.
+    pub pulled: usize,
+    /// Feeds that couldn't be subscribed on the server, with the error.
+    pub failed: Vec<String>,
+}
+
+/// A feed on the server.
+#[derive(Debug, Deserialize)]
+struct RemoteFeed {
+    id: usize,
+    feed_url: String,
+    #[serde(default)]
+    title: String,
+    category: Option<Category>,
+}
+
+/// A category on the server.
+#[derive(Debug, Deserialize)]
+struct Category {
+    id: usize,
+    title: String,
+}
+
+/// An entry (item) on the server.
+#[derive(Debug, Deserialize)]
+struct Entry {
+    id: usize,
+    feed_id: usize,
+    status: String,
+    #[serde(default)]
+    starred: bool,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    author: String,
+    published_at: Option<String>,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+/// A page of entries.
+#[derive(Debug, Deserialize)]
+struct EntryPage {
+    entries: Vec<Entry>,
+}
+
+/// How to log in to the server.
+enum Login {
+    Token(String),
+    Password(String, String),
+}
+
+/// A client for the Miniflux API.
+struct Miniflux<'a> {
+    client: &'a FeedClient,
+    base_url: String,
+    login: Login,
+}
+
+impl<'a> Miniflux<'a> {
+    /// Create a client for the server in the config, resolving its secrets.
+    fn new(client: &'a FeedClient, config: &RemoteConfig) -> Result<Self> {
+        let login = match (&config.token, &config.username, &config.password) {
+            (Some(token), _, _) => Login::Token(token.resolve()?),
+            (None, Some(username), Some(password)) => {
+                Login::Password(username.clone(), password.resolve()?)
+            }
+            _ => anyhow::bail!("set a token, or a username and password, for the remote server"),
+        };
+        Ok(Self {
+            client,
+            base_url: config.url.trim_end_matches('/').to_string(),
+            login,
+        })
+    }
+
+    /// Start a request to an API endpoint under `/v1/`.
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, &format!("{}/v1/{}", self.base_url, path));
+        match &self.login {
+            Login::Token(token) => request.header("X-Auth-Token", token),
+            Login::Password(username, password) => request.basic_auth(username, Some(password)),
+        }
+    }
+
+    /// Send a request, failing on error statuses.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request.send().await.context("failed to reach server")?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            anyhow::bail!("server responded with {}: {}", status, message.trim());
+        }
+        Ok(response)
+    }
+
+    /// Send a request and parse the JSON response.
+    async fn send_json<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        self.send(request)
+            .await?
+            .json()
+            .await
+            .context("invalid response from server")
+    }
+
+    async fn feeds(&self) -> Result<Vec<RemoteFeed>> {
+        self.send_json(self.request(Method::GET, "feeds")).await
+    }
+
+    async fn categories(&self) -> Result<Vec<Category>> {
+        self.send_json(self.request(Method::GET, "categories"))
+            .await
+    }
+
+    async fn create_category(&self, title: &str) -> Result<Category> {
+        self.send_json(
+            self.request(Method::POST, "categories")
+                .json(&json!({ "title": title })),
+        )
+        .await
+    }
+
+    /// Subscribe to a feed, returning its ID on the server.
+    async fn create_feed(&self, url: &str, category_id: Option<usize>) -> Result<usize> {
+        #[derive(Deserialize)]
+        struct Created {
+            feed_id: usize,
+        }
+        let mut body = json!({ "feed_url": url });
+        if let Some(category_id) = category_id {
+            body["category_id"] = json!(category_id);
+        }
+        let created: Created = self
+            .send_json(self.request(Method::POST, "feeds").json(&body))
+            .await?;
+        Ok(created.feed_id)
+    }
+
+    /// Unsubscribe from a feed. A feed that is already gone is fine.
+    async fn delete_feed(&self, id: usize) -> Result<()> {
+        let response = self
+            .request(Method::DELETE, &format!("feeds/{}", id))
+            .send()
+            .await
+            .context("failed to reach server")?;
+        if response.status() != StatusCode::NOT_FOUND {
+            response.error_for_status()?;
+        }
+        Ok(())
+    }
+
+    /// Every entry matching the query parameters, fetched a page at a time.
+    async fn entries(&self, query: &[(&str, String)]) -> Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        loop {
+            let page: EntryPage = self
+                .send_json(
+                    self.request(Method::GET, "entries")
+                        .query(query)
+                        .query(&[("limit", PAGE_SIZE), ("offset", entries.len())]),
+                )
+                .await?;
+            let done = page.entries.len() < PAGE_SIZE;
+            entries.extend(page.entries);
+            if done {
+                return Ok(entries);
+            }
+        }
+    }
+
+    async fn set_entries_read(&self, ids: &[usize], read: bool) -> Result<()> {
+        let status = if read { "read" } else { "unread" };
+        self.send(
+            self.request(Method::PUT, "entries")
+                .json(&json!({ "entry_ids": ids, "status": status })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn toggle_starred(&self, id: usize) -> Result<()> {
+        self.send(self.request(Method::PUT, &format!("entries/{}/bookmark", id)))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Sync subscriptions, items and read and starred state with the server, applying the rules to
+/// new items.
+pub async fn sync(
+    db: &Db,
+    client: &FeedClient,
+    config: &RemoteConfig,
+    rules: &RuleSet,
+) -> Result<SyncSummary> {
+    let server = Miniflux::new(client, config)?;
+    let mut summary = SyncSummary::default();
+    push_subscriptions(db, &server, &mut summary).await?;
+    let feeds = pull_subscriptions(db, &server, &mut summary).await?;
+    pull_new_items(db, &server, &feeds, rules, &mut summary).await?;
+    sync_item_states(db, &server, &mut summary).await?;
+    Ok(summary)
+}
+
+/// Unsubscribe from the feeds removed here, and subscribe to the feeds added here.
+async fn push_subscriptions(
+    db: &Db,
+    server: &Miniflux<'_>,
+    summary: &mut SyncSummary,
+) -> Result<()> {
+    for remote_id in db.removed_remote_feeds()? {
+        server.delete_feed(remote_id).await?;
+        db.clear_removed_remote_feed(remote_id)?;
+        summary.unsubscribed += 1;
+    }
+
+    let mut categories: Option<Vec<Category>> = None;
+    for feed in db.list_feeds()? {
+        if db.feed_remote_id(feed.id)?.is_some() {
+            continue;
+        }
+        // Feeds go in the category named after their folder, if any
+        let category_id = match feed.folder_id {
+            Some(folder_id) => {
+                let name = db.folder_path(folder_id)?;
+                let categories = match &mut categories {
+                    Some(categories) => categories,
+                    None => categories.insert(server.categories().await?),
+                };
+                match categories.iter().find(|c| c.title == name) {
+                    Some(category) => Some(category.id),
+                    None => {
+                        let category = server.create_category(&name).await?;
+                        let id = category.id;
+                        categories.push(category);
+                        Some(id)
+                    }
+                }
+            }
+            None => None,
+        };
+        match server.create_feed(&feed.url, category_id).await {
+            Ok(remote_id) => {
+                db.set_feed_remote_id(feed.id, Some(remote_id))?;
+                summary.subscribed += 1;
+            }
+            // Feeds the server already has are matched by URL when pulling
+            Err(e) => summary.failed.push(format!("{}: {:#}", feed.url, e)),
+        }
+    }
+    Ok(())
+}
+
+/// Add the feeds subscribed on the server and remove those unsubscribed, putting feeds without a
+/// folder in the folders of their categories. Returns the feeds by their ID on the server.
+async fn pull_subscriptions(
+    db: &Db,
+    server: &Miniflux<'_>,
+    summary: &mut SyncSummary,
+) -> Result<HashMap<usize, Feed>> {
+    let remote_feeds = server.feeds().await?;
+    // Feeds subscribed without a category are put in the first one, which isn't made a folder
+    let default_category = server.categories().await?.iter().map(|c| c.id).min();
+    // Folders picked here are kept
+    let in_folder: HashSet<usize> = db
+        .list_feeds()?
+        .into_iter()
+        .filter(|f| f.folder_id.is_some())
+        .map(|f| f.id)
+        .collect();
+    for remote in &remote_feeds {
+        let feed_id = match db.feed_for_remote_id(remote.id)? {
+            Some(feed_id) => feed_id,
+            None => {
+                let existing = db
+                    .list_feeds()?
+                    .into_iter()
+                    .find(|f| f.url == remote.feed_url);
+                let feed_id = match existing {
+                    Some(feed) => feed.id,
+                    None => {
+                        let title = Some(remote.title.as_str()).filter(|t| !t.is_empty());
+                        db.add_feed(&remote.feed_url, title)?;
+                        summary.feeds_added += 1;
+                        db.list_feeds()?
+                            .into_iter()
+                            .find(|f| f.url == remote.feed_url)
+                            .context("failed to find added feed")?
+                            .id
+                    }
+                };
+                db.set_feed_remote_id(feed_id, Some(remote.id))?;
+                feed_id
+            }
+        };
+        if let Some(category) = &remote.category
+            && Some(category.id) != default_category
+            && !in_folder.contains(&feed_id)
+        {
+            db.set_feed_folder(feed_id, db.create_folder_path(&category.title)?)?;
+        }
+    }
+
+    let remote_ids: HashSet<usize> = remote_feeds.iter().map(|f| f.id).collect();
+    let mut feeds = HashMap::new();
+    for feed in db.list_feeds()? {
+        let Some(remote_id) = db.feed_remote_id(feed.id)? else {
+            continue;
+        };
+        if remote_ids.contains(&remote_id) {
+            feeds.insert(remote_id, feed);
+        } else {
+            db.remove_feed(&feed.url)?;
+            // The server already knows
+            db.clear_removed_remote_feed(remote_id)?;
+            summary.feeds_removed += 1;
+        }
+    }
+    Ok(feeds)
+}
+
+/// Add the entries newer than any item pulled before.
+async fn pull_new_items(
+    db: &Db,
+    server: &Miniflux<'_>,
+    feeds: &HashMap<usize, Feed>,
+    rules: &RuleSet,
+    summary: &mut SyncSummary,
+) -> Result<()> {
+    let after = db.max_item_remote_id()?.unwrap_or_default();
+    let entries = server
+        .entries(&[
+            ("after_entry_id", after.to_string()),
+            ("order", "id".to_string()),
+            ("direction", "asc".to_string()),
+        ])
+        .await?;
+    for entry in entries {
+        let Some(feed) = feeds.get(&entry.feed_id) else {
+            continue;
+        };
+        let existing = match entry.url.as_str() {
+            "" => None,
+            url => db.find_feed_item_by_link(feed.id, url)?,
+        };
+        let (item_id, is_new) = match existing {
+            Some(item_id) => (item_id, false),
+            None => match db.add_feed_item(feed.id, &parsed_item(&entry))? {
+                ItemChange::New(item_id) => (item_id, true),
+                ItemChange::Updated(item_id) => (item_id, false),
+                ItemChange::Unchanged => continue,
+            },
+        };
+        db.set_item_remote(item_id, entry.id, entry.status == "read", entry.starred)?;
+        if is_new {
+            let deleted = rules
+                .apply_by_id(db, feed, item_id)
+                .is_ok_and(|outcome| outcome.deleted);
+            if !deleted {
+                summary.new_items.push(item_id);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// An entry as an item to store.
+fn parsed_item(entry: &Entry) -> ParsedFeedItem {
+    let non_empty = |s: &str| Some(s.to_string()).filter(|s| !s.is_empty());
+    ParsedFeedItem {
+        guid: None,
+        title: non_empty(&entry.title),
+        link: non_empty(&entry.url),
+        canonical_link: None,
+        description: non_empty(&entry.content),
+        authors: non_empty(&entry.author).into_iter().collect(),
+        published: entry
+            .published_at
+            .as_deref()
+            .and_then(|date| OffsetDateTime::parse(date, &Rfc3339).ok())
+            .map(|date| date.unix_timestamp()),
+        updated: None,
+        categories: entry.tags.clone().unwrap_or_default(),
+    }
+}
+
+/// What to do about the read or starred state of an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resolution {
+    /// Both sides agree and nothing changed.
+    InSync,
+    /// Send the state here to the server.
+    Push(bool),
+    /// Take the state on the server.
+    Pull(bool),
+}
+
+/// Reconcile a state as it is here, as it was on the server when last synced, and as it is
+/// on the server now. A change made here wins over a change on the server.
+fn reconcile(local: bool, synced: bool, remote: bool) -> Resolution {
+    if local == remote {
+        if synced == remote {
+            Resolution::InSync
+        } else {
+            // Both sides changed the same way
+            Resolution::Pull(remote)
+        }
+    } else if local != synced {
+        Resolution::Push(local)
+    } else {
+        Resolution::Pull(remote)
+    }
+}
+
+/// Sync the read and starred state of every item pulled from the server both ways.
+async fn sync_item_states(db: &Db, server: &Miniflux<'_>, summary: &mut SyncSummary) -> Result<()> {
+    let ids = |entries: Vec<Entry>| entries.into_iter().map(|e| e.id).collect::<HashSet<_>>();
+    let remote_unread = ids(server.entries(&[("status", "unread".to_string())]).await?);
+    let remote_starred = ids(server.entries(&[("starred", "true".to_string())]).await?);
+
+    let mut read = Vec::new();
+    let mut unread = Vec::new();
+    let mut toggle_starred = Vec::new();
+    let mut updates = Vec::new();
+    for state in db.remote_item_states()? {
+        let remote_read = !remote_unread.contains(&state.remote_id);
+        let remote_starred = remote_starred.contains(&state.remote_id);
+        let read_resolution = reconcile(state.is_read, state.remote_is_read, remote_read);
+        let starred_resolution =
+            reconcile(state.is_starred, state.remote_is_starred, remote_starred);
+        if read_resolution == Resolution::InSync && starred_resolution == Resolution::InSync {
+            continue;
+        }
+
+        match read_resolution {
+            Resolution::Push(true) => read.push(state.remote_id),
+            Resolution::Push(false) => unread.push(state.remote_id),
+            _ => {}
+        }
+        if matches!(starred_resolution, Resolution::Push(_)) {
+            toggle_starred.push(state.remote_id);
+        }
+        let pushed = [read_resolution, starred_resolution]
+            .iter()
+            .any(|r| matches!(r, Resolution::Push(_)));
+        if pushed {
+            summary.pushed += 1;
+        } else if state.is_read != remote_read || state.is_starred != remote_starred {
+            summary.pulled += 1;
+        }
+        let resolved = |resolution, local| match resolution {
+            Resolution::Push(value) | Resolution::Pull(value) => value,
+            Resolution::InSync => local,
+        };
+        updates.push((
+            state.item_id,
+            resolved(read_resolution, state.is_read),
+            resolved(starred_resolution, state.is_starred),
+        ));
+    }
+
+    // Record the new state only once the server has it, so that failed pushes are retried
+    for chunk in read.chunks(PAGE_SIZE) {
+        server.set_entries_read(chunk, true).await?;
+    }
+    for chunk in unread.chunks(PAGE_SIZE) {
+        server.set_entries_read(chunk, false).await?;
+    }
+    for remote_id in toggle_starred {
+        server.toggle_starred(remote_id).await?;
+    }
+    for (item_id, is_read, is_starred) in updates {
+        db.set_item_synced_state(item_id, is_read, is_starred)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::HttpConfig, db::test_util::test_db};
+    use axum::{
+        Json, Router,
+        extract::{Path, Query, State},
+        http::HeaderMap,
+        routing::{delete, get, put},
+    };
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+
+    /// The data of a stub Miniflux server.
+    struct Stub {
+        feeds: Vec<Value>,
+        /// Entries as `(id, feed_id, is_read, is_starred)`.
+        entries: Vec<(usize, usize, bool, bool)>,
+    }
+
+    type StubState = Arc<Mutex<Stub>>;
+
+    /// Serve a stub of the parts of the Miniflux API used for syncing, accepting the token
+    /// `token`, and return the base URL.
+    async fn serve(stub: StubState) -> String {
+        fn authorized(headers: &HeaderMap) -> Result<(), StatusCode> {
+            match headers.get("X-Auth-Token") {
+                Some(token) if token == "token" => Ok(()),
+                _ => Err(StatusCode::UNAUTHORIZED),
+            }
+        }
+
+        let app =
+            Router::new()
+                .route(
+                    "/v1/feeds",
+                    get(
+                        |State(stub): State<StubState>, headers: HeaderMap| async move {
+                            authorized(&headers)?;
+                            Ok::<_, StatusCode>(Json(json!(stub.lock().unwrap().feeds)))
+                        },
+                    )
+                    .post(
+                        |State(stub): State<StubState>,
+                         headers: HeaderMap,
+                         Json(body): Json<Value>| async move {
+                            authorized(&headers)?;
+                            let mut stub = stub.lock().unwrap();
+                            let id = stub.feeds.len() + 100;
+                            stub.feeds.push(json!({
+                                "id": id,
+                                "feed_url": body["feed_url"],
+                                "title": "",
+                                "category": { "id": 1, "title": "All" },
+                            }));
+                            Ok::<_, StatusCode>(Json(json!({ "feed_id": id })))
+                        },
+                    ),
+                )
+                .route(
+                    "/v1/feeds/{id}",
+                    delete(
+                        |State(stub): State<StubState>, Path(id): Path<usize>| async move {
+                            stub.lock().unwrap().feeds.retain(|f| f["id"] != id);
+                            StatusCode::NO_CONTENT
+                        },
+                    ),
+                )
+                .route(
+                    "/v1/categories",
+                    get(|| async {
+                        Json(json!([
+                            { "id": 1, "title": "All" },
+                            { "id": 2, "title": "News" },
+                        ]))
+                    }),
+                )
+                .route(
+                    "/v1/entries",
+                    get(
+                        |State(stub): State<StubState>,
+                         Query(query): Query<HashMap<String, String>>| async move {
+                            let number = |name: &str| {
+                                query
+                                    .get(name)
+                                    .and_then(|v| v.parse::<usize>().ok())
+                                    .unwrap_or_default()
+                            };
+                            let stub = stub.lock().unwrap();
+                            let entries: Vec<Value> = stub
+                                .entries
+                                .iter()
+                                .filter(|(id, ..)| *id > number("after_entry_id"))
+                                .filter(|(_, _, read, _)| {
+                                    query.get("status").is_none_or(|s| (s == "read") == *read)
+                                })
+                                .filter(|(.., starred)| !query.contains_key("starred") || *starred)
+                                .skip(number("offset"))
+                                .map(|(id, feed_id, read, starred)| {
+                                    json!({
+                                        "id": id,
+                                        "feed_id": feed_id,
+                                        "status": if *read { "read" } else { "unread" },
+                                        "starred": starred,
+                                        "title": format!("Entry {}", id),
+                                        "url": format!("https://a.example.com/{}", id),
+                                        "published_at": "2024-05-01T12:00:00Z",
+                                    })
+                                })
+                                .collect();
+                            Json(json!({ "total": entries.len(), "entries": entries }))
+                        },
+                    )
+                    .put(
+                        |State(stub): State<StubState>, Json(body): Json<Value>| async move {
+                            let read = body["status"] == "read";
+                            for entry in &mut stub.lock().unwrap().entries {
+                                if body["entry_ids"]
+                                    .as_array()
+                                    .unwrap()
+                                    .contains(&json!(entry.0))
+                                {
+                                    entry.2 = read;
+                                }
+                            }
+                            StatusCode::NO_CONTENT
+                        },
+                    ),
+                )
+                .route(
+                    "/v1/entries/{id}/bookmark",
+                    put(
+                        |State(stub): State<StubState>, Path(id): Path<usize>| async move {
+                            for entry in &mut stub.lock().unwrap().entries {
+                                if entry.0 == id {
+                                    entry.3 = !entry.3;
+                                }
+                            }
+                            StatusCode::NO_CONTENT
+                        },
+                    ),
+                )
+                .with_state(stub);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        base
+    }
+
+    fn remote_config(url: &str) -> RemoteConfig {
+        RemoteConfig {
+            url: url.to_string(),
+            token: Some(crate::auth::Secret::Value("token".to_string())),
+            username: None,
+            password: None,
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        assert_eq!(reconcile(true, true, true), Resolution::InSync);
+        assert_eq!(reconcile(true, false, false), Resolution::Push(true));
+        assert_eq!(reconcile(false, false, true), Resolution::Pull(true));
+        // Both changed the same way
+        assert_eq!(reconcile(true, false, true), Resolution::Pull(true));
+        // Both changed differently: the change made here wins
+        assert_eq!(reconcile(false, true, true), Resolution::Push(false));
+    }
+
+    #[tokio::test]
+    async fn test_sync_keeps_local_folders() {
+        let stub = Arc::new(Mutex::new(Stub {
+            feeds: vec![
+                json!({
+                    "id": 1,
+                    "feed_url": "https://a.example.com/feed",
+                    "title": "A",
+                    "category": { "id": 2, "title": "News" },
+                }),
+                json!({
+                    "id": 2,
+                    "feed_url": "https://b.example.com/feed",
+                    "title": "B",
+                    "category": { "id": 1, "title": "All" },
+                }),
+            ],
+            entries: vec![],
+        }));
+        let base = serve(stub).await;
+        let client = FeedClient::new(&HttpConfig::default()).unwrap();
+        let rules = RuleSet::default();
+        let db = test_db();
+        let folder = |url: &str| {
+            let feed = db
+                .list_feeds()
+                .unwrap()
+                .into_iter()
+                .find(|f| f.url == url)
+                .unwrap();
+            (
+                feed.id,
+                feed.folder_id.map(|id| db.folder_path(id).unwrap()),
+            )
+        };
+
+        sync(&db, &client, &remote_config(&base), &rules)
+            .await
+            .unwrap();
+        let (a, a_folder) = folder("https://a.example.com/feed");
+        assert_eq!(a_folder.as_deref(), Some("News"));
+        // The default category isn't a folder
+        assert_eq!(folder("https://b.example.com/feed").1, None);
+
+        // A feed moved here stays in its folder
+        db.set_feed_folder(a, db.create_folder_path("Mine").unwrap())
+            .unwrap();
+        sync(&db, &client, &remote_config(&base), &rules)
+            .await
+            .unwrap();
+        assert_eq!(
+            folder("https://a.example.com/feed").1.as_deref(),
+            Some("Mine")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_with_server() {
+        let stub = Arc::new(Mutex::new(Stub {
+            feeds: vec![json!({
+                "id": 1,
+                "feed_url": "https://a.example.com/feed",
+                "title": "A",
+                "category": { "id": 1, "title": "All" },
+            })],
+            entries: vec![(10, 1, false, false), (11, 1, true, true)],
+        }));
+        let base = serve(stub.clone()).await;
+        let client = FeedClient::new(&HttpConfig::default()).unwrap();
+        let rules = RuleSet::default();
+        let db = test_db();
+        db.add_feed("https://b.example.com/feed", None).unwrap();
+
+        let summary = sync(&db, &client, &remote_config(&base), &rules)
+            .await
+            .unwrap();
+        assert_eq!(summary.subscribed, 1);
+        assert_eq!(summary.feeds_added, 1);
+        assert_eq!(summary.new_items.len(), 2);
+        assert_eq!(stub.lock().unwrap().feeds.len(), 2);
+        let feed = db
+            .list_feeds()
+            .unwrap()
+            .into_iter()
+            .find(|f| f.url == "https://a.example.com/feed")
+            .unwrap();
+        assert_eq!(feed.folder_id, None);
+        let items = db.get_feed_items(feed.id).unwrap();
+        let item = |title: &str| {
+            items
+                .iter()
+                .find(|i| i.title.as_deref() == Some(title))
+                .unwrap()
+        };
+        assert!(!item("Entry 10").is_read);
+        assert!(item("Entry 11").is_read && item("Entry 11").is_starred);
+
+        // Changes made while the server is unreachable are kept
+        db.mark_item_read(item("Entry 10").id).unwrap();
+        db.set_item_starred(item("Entry 10").id, true).unwrap();
+        assert!(
+            sync(&db, &client, &remote_config("http://127.0.0.1:1"), &rules)
+                .await
+                .is_err()
+        );
+        let pending = db.remote_item_states().unwrap();
+        assert_eq!(pending.iter().filter(|s| s.has_local_changes()).count(), 1);
+
+        // Meanwhile, another client marks an entry as unread
+        stub.lock().unwrap().entries[1].2 = false;
+        let summary = sync(&db, &client, &remote_config(&base), &rules)
+            .await
+            .unwrap();
+        assert_eq!((summary.pushed, summary.pulled), (1, 1));
+        assert_eq!(
+            stub.lock().unwrap().entries,
+            vec![(10, 1, true, true), (11, 1, false, true)]
+        );
+        assert!(
+            !db.get_feed_item(item("Entry 11").id)
+                .unwrap()
+                .unwrap()
+                .is_read
+        );
+        assert!(
+            !db.remote_item_states()
+                .unwrap()
+                .iter()
+                .any(|s| s.has_local_changes())
+        );
+
+        // Removing a feed here unsubscribes it on the server
+        db.remove_feed("https://a.example.com/feed").unwrap();
+        let summary = sync(&db, &client, &remote_config(&base), &rules)
+            .await
+            .unwrap();
+        assert_eq!(summary.unsubscribed, 1);
+        assert_eq!(stub.lock().unwrap().feeds.len(), 1);
+        assert!(db.removed_remote_feeds().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,411 @@
+/*!
+Rules for acting on feed items automatically, loaded from a TOML rules file.
+
+Each rule has one or more match conditions, all of which must match an item, and a list of
+actions to take on matching items. Text conditions are regular expressions.
+
+```toml
+[[rule]]
+name = "Hide sponsored posts"
+title = "(?i)sponsored"
+actions = ["hide"]
+
+[[rule]]
+name = "Star mentions of ferrofeed"
+content = "(?i)ferrofeed"
+actions = ["star", { tag = "ferrofeed" }]
+
+[[rule]]
+feed = "example\\.com"
+domain = "ads.example.com"
+actions = ["delete"]
+```
+*/
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+use crate::{
+    config::RulesConfig,
+    db::{Db, Feed, FeedItem},
+};
+
+/// An action to take on an item matched by a rule.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Mark the item as read.
+    MarkRead,
+    /// Star the item.
+    Star,
+    /// Add a tag to the item.
+    Tag(String),
+    /// Hide the item from item lists.
+    Hide,
+    /// Delete the item.
+    Delete,
+}
+
+/// A rule as written in the rules file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleDef {
+    name: Option<String>,
+    feed: Option<String>,
+    title: Option<String>,
+    author: Option<String>,
+    content: Option<String>,
+    domain: Option<String>,
+    actions: Vec<Action>,
+}
+
+/// The rules file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    rule: Vec<RuleDef>,
+}
+
+/// A rule with its conditions compiled.
+#[derive(Debug)]
+pub struct Rule {
+    /// Name of the rule, for messages.
+    pub name: String,
+    /// Matches the feed title or URL.
+    feed: Option<Regex>,
+    /// Matches the item title.
+    title: Option<Regex>,
+    /// Matches any of the item authors.
+    author: Option<Regex>,
+    /// Matches the item title or description.
+    content: Option<Regex>,
+    /// Matches the host of the item link, or any of its subdomains.
+    domain: Option<String>,
+    /// Actions to take on matching items.
+    pub actions: Vec<Action>,
+}
+
+impl Rule {
+    /// Whether the rule matches an item of a feed.
+    pub fn matches(&self, feed: &Feed, item: &FeedItem) -> bool {
+        let feed_matches = self.feed.as_ref().is_none_or(|re| {
+            re.is_match(&feed.url) || feed.title.as_deref().is_some_and(|t| re.is_match(t))
+        });
+        let title_matches = self
+            .title
+            .as_ref()
+            .is_none_or(|re| item.title.as_deref().is_some_and(|t| re.is_match(t)));
+        let author_matches = self
+            .author
+            .as_ref()
+            .is_none_or(|re| item.authors.iter().any(|a| re.is_match(a)));
+        let content_matches = self.content.as_ref().is_none_or(|re| {
+            [&item.title, &item.description]
+                .into_iter()
+                .any(|text| text.as_deref().is_some_and(|t| re.is_match(t)))
+        });
+        let domain_matches = self.domain.as_ref().is_none_or(|domain| {
+            item.link
+                .as_deref()
+                .and_then(|link| url::Url::parse(link).ok())
+                .and_then(|url| url.host_str().map(|h| h.to_ascii_lowercase()))
+                .is_some_and(|host| host == *domain || host.ends_with(&format!(".{}", domain)))
+        });
+        feed_matches && title_matches && author_matches && content_matches && domain_matches
+    }
+}
+
+/// What applying the rules did to an item.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RuleOutcome {
+    /// Number of rules that matched the item.
+    pub matched: usize,
+    /// Whether the item was deleted.
+    pub deleted: bool,
+}
+
+/// An ordered set of rules.
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    /// The rules, in the order they appear in the rules file.
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// Load the rules file at `path`. A missing file is an empty rule set.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("failed to read rules file at {}", path.display()))?;
+        Self::parse(&data)
+            .with_context(|| format!("failed to parse rules file at {}", path.display()))
+    }
+
+    /// Load the rules file set in the config, if any.
+    pub fn from_config(config: &RulesConfig) -> Result<Self> {
+        match &config.path {
+            Some(path) => Self::load(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Parse rules from TOML.
+    pub fn parse(data: &str) -> Result<Self> {
+        let file: RulesFile = toml::from_str(data)?;
+        let rules = file
+            .rule
+            .into_iter()
+            .enumerate()
+            .map(|(i, def)| compile_rule(i, def))
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Whether there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Evaluate every rule against the item with the given ID, as [`RuleSet::apply`]. Used for
+    /// newly added items.
+    pub fn apply_by_id(&self, db: &Db, feed: &Feed, item_id: usize) -> Result<RuleOutcome> {
+        if self.is_empty() {
+            return Ok(RuleOutcome::default());
+        }
+        match db.get_feed_item(item_id)? {
+            Some(item) => self.apply(db, feed, &item),
+            None => Ok(RuleOutcome::default()),
+        }
+    }
+
+    /// Evaluate every rule against an item of a feed and take the actions of those that match.
+    pub fn apply(&self, db: &Db, feed: &Feed, item: &FeedItem) -> Result<RuleOutcome> {
+        let mut outcome = RuleOutcome::default();
+        for rule in self.rules.iter().filter(|r| r.matches(feed, item)) {
+            outcome.matched += 1;
+            for action in &rule.actions {
+                match action {
+                    Action::MarkRead => db.mark_item_read(item.id)?,
+                    Action::Star => db.set_item_starred(item.id, true)?,
+                    Action::Tag(tag) => db.add_item_tag(item.id, tag)?,
+                    Action::Hide => db.set_item_hidden(item.id, true)?,
+                    Action::Delete => {
+                        db.delete_feed_item(item.id)?;
+                        outcome.deleted = true;
+                        // Nothing left to act on
+                        return Ok(outcome);
+                    }
+                }
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+/// Compile the rule at `index` in the rules file.
+fn compile_rule(index: usize, def: RuleDef) -> Result<Rule> {
+    let name = def.name.unwrap_or_else(|| format!("rule #{}", index + 1));
+    let compile = |pattern: Option<String>, field: &str| -> Result<Option<Regex>> {
+        pattern
+            .map(|p| {
+                Regex::new(&p).with_context(|| format!("invalid `{}` regex in {}", field, name))
+            })
+            .transpose()
+    };
+    let rule = Rule {
+        feed: compile(def.feed, "feed")?,
+        title: compile(def.title, "title")?,
+        author: compile(def.author, "author")?,
+        content: compile(def.content, "content")?,
+        domain: def
+            .domain
+            .map(|d| d.trim_start_matches('.').to_ascii_lowercase()),
+        actions: def.actions,
+        name,
+    };
+
+    // A rule without conditions would act on every item
+    if rule.feed.is_none()
+        && rule.title.is_none()
+        && rule.author.is_none()
+        && rule.content.is_none()
+        && rule.domain.is_none()
+    {
+        anyhow::bail!("{} has no match conditions", rule.name);
+    }
+    if rule.actions.is_empty() {
+        anyhow::bail!("{} has no actions", rule.name);
+    }
+    Ok(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::ParsedFeedItem,
+        db::{
+            ItemChange,
+            test_util::{self, add_feed, test_db},
+        },
+    };
+
+    /// Add a feed with one item and return both.
+    fn add_item(db: &Db, title: &str, link: &str, description: &str) -> (Feed, FeedItem) {
+        add_feed(db, "https://example.com/feed.xml", "Example");
+        let feed = db.list_feeds().expect("failed to list feeds").remove(0);
+        let id = test_util::add_item(
+            db,
+            feed.id,
+            &ParsedFeedItem {
+                title: Some(title.to_string()),
+                link: Some(link.to_string()),
+                description: Some(description.to_string()),
+                authors: vec!["Jane Doe".to_string()],
+                published: Some(100),
+                ..Default::default()
+            },
+        );
+        let item = db.get_feed_item(id).expect("failed to get item").unwrap();
+        (feed, item)
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = RuleSet::parse(
+            r#"
+            [[rule]]
+            name = "Sponsored"
+            title = "(?i)sponsored"
+            actions = ["hide", "mark-read", { tag = "ads" }]
+
+            [[rule]]
+            domain = "example.com"
+            actions = ["delete"]
+            "#,
+        )
+        .expect("failed to parse rules");
+        assert_eq!(rules.rules.len(), 2);
+        assert_eq!(rules.rules[0].name, "Sponsored");
+        assert_eq!(
+            rules.rules[0].actions,
+            vec![
+                Action::Hide,
+                Action::MarkRead,
+                Action::Tag("ads".to_string())
+            ]
+        );
+        assert_eq!(rules.rules[1].name, "rule #2");
+
+        assert!(RuleSet::parse("").unwrap().is_empty());
+        assert!(RuleSet::parse("[[rule]]\nactions = [\"star\"]").is_err());
+        assert!(RuleSet::parse("[[rule]]\ntitle = \"(\"\nactions = [\"star\"]").is_err());
+        assert!(RuleSet::parse("[[rule]]\ntitle = \"a\"\nactions = [\"explode\"]").is_err());
+    }
+
+    #[test]
+    fn test_rule_matching() {
+        let db = test_db();
+        let (feed, item) = add_item(
+            &db,
+            "Sponsored: buy now",
+            "https://blog.example.com/post",
+            "<p>An ad</p>",
+        );
+
+        let matches = |toml: &str| {
+            let rules = RuleSet::parse(toml).expect("failed to parse rules");
+            rules.rules[0].matches(&feed, &item)
+        };
+        assert!(matches(
+            "[[rule]]\ntitle = \"(?i)sponsored\"\nactions = [\"hide\"]"
+        ));
+        assert!(matches("[[rule]]\nauthor = \"Jane\"\nactions = [\"hide\"]"));
+        assert!(matches(
+            "[[rule]]\ncontent = \"An ad\"\nactions = [\"hide\"]"
+        ));
+        assert!(matches(
+            "[[rule]]\nfeed = \"^Example$\"\nactions = [\"hide\"]"
+        ));
+        assert!(matches(
+            "[[rule]]\ndomain = \"example.com\"\nactions = [\"hide\"]"
+        ));
+        assert!(!matches(
+            "[[rule]]\ndomain = \"ample.com\"\nactions = [\"hide\"]"
+        ));
+        assert!(!matches(
+            "[[rule]]\ntitle = \"Sponsored\"\nauthor = \"John\"\nactions = [\"hide\"]"
+        ));
+    }
+
+    #[test]
+    fn test_apply_rules() {
+        let db = test_db();
+        let (feed, item) = add_item(&db, "Release notes", "https://example.com/1", "ferrofeed");
+        let rules = RuleSet::parse(
+            r#"
+            [[rule]]
+            content = "ferrofeed"
+            actions = ["star", "mark-read", { tag = "product" }]
+
+            [[rule]]
+            title = "Release"
+            actions = ["hide"]
+            "#,
+        )
+        .expect("failed to parse rules");
+
+        let outcome = rules
+            .apply(&db, &feed, &item)
+            .expect("failed to apply rules");
+        assert_eq!(outcome.matched, 2);
+        assert!(!outcome.deleted);
+
+        let item = db.get_feed_item(item.id).unwrap().unwrap();
+        assert!(item.is_starred);
+        assert!(item.is_read);
+        assert!(item.is_hidden);
+        assert_eq!(item.tags, vec!["product"]);
+        assert!(db.get_feed_items(feed.id).unwrap().is_empty());
+
+        let delete = RuleSet::parse("[[rule]]\ntitle = \"Release\"\nactions = [\"delete\"]")
+            .expect("failed to parse rules");
+        let outcome = delete
+            .apply(&db, &feed, &item)
+            .expect("failed to apply rules");
+        assert!(outcome.deleted);
+        assert!(db.get_feed_item(item.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_deleted_items_stay_deleted() {
+        let db = test_db();
+        add_feed(&db, "https://example.com/feed.xml", "Example");
+        let feed = db.list_feeds().expect("failed to list feeds").remove(0);
+        let rules = RuleSet::parse("[[rule]]\ntitle = \"Sponsored\"\nactions = [\"delete\"]")
+            .expect("failed to parse rules");
+        let parsed = ParsedFeedItem {
+            title: Some("Sponsored post".to_string()),
+            link: Some("https://example.com/ad".to_string()),
+            ..Default::default()
+        };
+
+        // Sync the same item twice, as it stays in the feed; deleted items don't count as new
+        let (mut new_items, mut deleted) = (0, 0);
+        for _ in 0..2 {
+            if let ItemChange::New(item_id) = db.add_feed_item(feed.id, &parsed).unwrap() {
+                if rules.apply_by_id(&db, &feed, item_id).unwrap().deleted {
+                    deleted += 1;
+                } else {
+                    new_items += 1;
+                }
+            }
+        }
+        assert_eq!((new_items, deleted), (0, 1));
+        assert!(db.get_feed_items(feed.id).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+/*!
+Servers for the sync APIs of other feed readers, so that their desktop and mobile apps can use
+the ferrofeed database as their sync backend.
+
+* [`fever`] serves the [Fever API](https://feedafever.com/api).
+* [`greader`] serves the Google Reader API, as implemented by FreshRSS and Miniflux.
+*/
+pub mod fever;
+pub mod greader;
+
+use axum::Router;
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+
+use crate::db::Db;
+
+/// A sync API to serve.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Api {
+    /// The Fever API.
+    #[default]
+    Fever,
+    /// The Google Reader API.
+    Greader,
+}
+
+/// The login of API clients.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    /// Username, or email for the Fever API.
+    pub username: String,
+    /// Password.
+    pub password: String,
+}
+
+impl Credentials {
+    /// The API key of the login: the MD5 hash of `<username>:<password>`, in lowercase hex.
+    pub fn api_key(&self) -> String {
+        Md5::digest(format!("{}:{}", self.username, self.password))
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// The routes of an API, for clients logging in with the given credentials.
+pub fn router(db: Db, api: Api, credentials: Credentials) -> Router {
+    match api {
+        Api::Fever => fever::router(db, credentials.api_key()),
+        Api::Greader => greader::router(db, credentials),
+    }
+}
+
+/// The parameters of a request, from both the query string and a form body.
+struct Params(Vec<(String, String)>);
+
+impl Params {
+    /// Collect the parameters of the query string and a form body.
+    fn new(query: Vec<(String, String)>, body: &[u8]) -> Self {
+        let mut params = query;
+        params.extend(url::form_urlencoded::parse(body).into_owned());
+        Self(params)
+    }
+
+    /// The value of a parameter, if present. For repeated parameters, the first value.
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value of a repeated parameter.
+    fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether a parameter is present, with or without a value.
+    fn has(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// The value of a numeric parameter, if present and valid.
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.get(name).and_then(|v| v.trim().parse().ok())
+    }
+}
+
+/// Compare secrets in constant time, so that how long a check takes doesn't tell how much of a
+/// guess was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
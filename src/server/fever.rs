@@ -0,0 +1,411 @@
+/*!
+The [Fever API](https://feedafever.com/api).
+
+* Clients log in with an API key, the MD5 hash of `<username>:<password>`.
+* Folders are groups, titled with their full path since Fever groups can't be nested. A feed
+  belongs to the group of the folder it's directly in.
+* Starred items are saved items, and hidden items are left out.
+*/
+use anyhow::{Context, Result};
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Query, State},
+    http::StatusCode,
+    routing::any,
+};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+use super::{Params, constant_time_eq};
+use crate::db::{Db, FeedItem, ItemPage, ItemSelection};
+
+/// Version of the Fever API implemented.
+const API_VERSION: u32 = 3;
+
+/// Maximum number of items returned at once, as in the Fever API.
+const MAX_ITEMS: usize = 50;
+
+/// State shared by the request handlers.
+struct FeverState {
+    db: Mutex<Db>,
+    api_key: String,
+}
+
+/// The routes of the Fever API, at `/` and `/fever/`, for clients accepting the given API key.
+pub fn router(db: Db, api_key: String) -> Router {
+    let state = Arc::new(FeverState {
+        db: Mutex::new(db),
+        api_key,
+    });
+    Router::new()
+        .route("/", any(handle))
+        .route("/fever", any(handle))
+        .route("/fever/", any(handle))
+        .with_state(state)
+}
+
+/// Handle an API request.
+async fn handle(
+    State(state): State<Arc<FeverState>>,
+    Query(query): Query<Vec<(String, String)>>,
+    body: Bytes,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    let params = Params::new(query, &body);
+
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    // API keys are hex, which clients may send in uppercase
+    let authorized = params.get("api_key").is_some_and(|key| {
+        constant_time_eq(
+            key.to_ascii_lowercase().as_bytes(),
+            state.api_key.as_bytes(),
+        )
+    });
+    respond(&db, &params, authorized)
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))
+}
+
+/// Build the response to a request, applying any mark action first.
+fn respond(db: &Db, params: &Params, authorized: bool) -> Result<Value> {
+    let mut response = json!({ "api_version": API_VERSION, "auth": u8::from(authorized) });
+    if !authorized {
+        return Ok(response);
+    }
+
+    if let Some(kind) = params.get("mark") {
+        mark(db, kind, params)?;
+    }
+
+    let feeds = db.list_feeds()?;
+    response["last_refreshed_on_time"] = json!(
+        feeds
+            .iter()
+            .filter_map(|f| f.last_updated)
+            .max()
+            .unwrap_or_default()
+    );
+    if params.has("groups") {
+        let groups = db
+            .list_folders()?
+            .iter()
+            .map(|folder| Ok(json!({ "id": folder.id, "title": db.folder_path(folder.id)? })))
+            .collect::<Result<Vec<_>>>()?;
+        response["groups"] = json!(groups);
+    }
+    if params.has("feeds") {
+        let feeds = feeds
+            .iter()
+            .map(|feed| {
+                Ok(json!({
+                    "id": feed.id,
+                    "favicon_id": 0,
+                    "title": feed.title.as_deref().unwrap_or(&feed.url),
+                    "url": feed.url,
+                    "site_url": db.get_feed_metadata(feed.id)?.site_link.unwrap_or_default(),
+                    "is_spark": 0,
+                    "last_updated_on_time": feed.last_updated.unwrap_or_default(),
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        response["feeds"] = json!(feeds);
+    }
+    if params.has("groups") || params.has("feeds") {
+        let feeds_groups = db
+            .list_folders()?
+            .iter()
+            .map(|folder| {
+                let feed_ids = feeds
+                    .iter()
+                    .filter(|f| f.folder_id == Some(folder.id))
+                    .map(|f| f.id);
+                json!({ "group_id": folder.id, "feed_ids": join_ids(feed_ids) })
+            })
+            .collect::<Vec<_>>();
+        response["feeds_groups"] = json!(feeds_groups);
+    }
+    if params.has("favicons") {
+        response["favicons"] = json!([]);
+    }
+    if params.has("links") {
+        response["links"] = json!([]);
+    }
+    if params.has("items") {
+        let ids: Vec<usize>;
+        let page = if let Some(with_ids) = params.get("with_ids") {
+            ids = with_ids
+                .split(',')
+                .filter_map(|id| id.trim().parse().ok())
+                .take(MAX_ITEMS)
+                .collect();
+            ItemPage::Ids(&ids)
+        } else if let Some(max_id) = params.number("max_id") {
+            ItemPage::Before(max_id)
+        } else {
+            ItemPage::After(params.number("since_id").unwrap_or_default())
+        };
+        let items = db.get_items_page(&ItemSelection::default(), page, MAX_ITEMS)?;
+        response["items"] = json!(items.iter().map(item_json).collect::<Vec<_>>());
+        response["total_items"] = json!(db.count_items()?);
+    }
+    if params.has("unread_item_ids") {
+        response["unread_item_ids"] = json!(join_ids(db.unread_item_ids()?));
+    }
+    if params.has("saved_item_ids") {
+        response["saved_item_ids"] = json!(join_ids(db.starred_item_ids()?));
+    }
+    Ok(response)
+}
+
+/// Apply a mark action: `item` as `read`, `unread`, `saved` or `unsaved`, or `feed` or
+/// `group` as `read`, optionally only for items added `before` a time. Group 0 is every feed.
+fn mark(db: &Db, kind: &str, params: &Params) -> Result<()> {
+    let (Some(action), Some(id)) = (params.get("as"), params.number::<i64>("id")) else {
+        anyhow::bail!("mark needs `as` and `id`");
+    };
+    let before = params.number("before");
+    // Only groups have negative IDs
+    let item_or_feed = || {
+        usize::try_from(id)
+            .ok()
+            .with_context(|| format!("invalid id: {}", id))
+    };
+    match (kind, action) {
+        ("item", "read") => db.mark_item_read(item_or_feed()?),
+        ("item", "unread") => db.mark_item_unread(item_or_feed()?),
+        ("item", "saved") => db.set_item_starred(item_or_feed()?, true),
+        ("item", "unsaved") => db.set_item_starred(item_or_feed()?, false),
+        ("feed", "read") => db.mark_feed_read(item_or_feed()?, before).map(|_| ()),
+        ("group", "read") => {
+            // Negative groups are Fever's "sparks", which ferrofeed doesn't have
+            let feeds = db
+                .list_feeds()?
+                .into_iter()
+                .filter(|f| id == 0 || f.folder_id.is_some_and(|g| g as i64 == id));
+            for feed in feeds {
+                db.mark_feed_read(feed.id, before)?;
+            }
+            Ok(())
+        }
+        _ => anyhow::bail!("unsupported mark action: {} as {}", kind, action),
+    }
+}
+
+/// An item as returned by the API.
+fn item_json(item: &FeedItem) -> Value {
+    json!({
+        "id": item.id,
+        "feed_id": item.feed_id,
+        "title": item.title.as_deref().unwrap_or_default(),
+        "author": item.authors.join(", "),
+        "html": item.description.as_deref().unwrap_or_default(),
+        "url": item.link.as_deref().unwrap_or_default(),
+        "is_saved": u8::from(item.is_starred),
+        "is_read": u8::from(item.is_read),
+        "created_on_time": item.published.unwrap_or(item.created_at),
+    })
+}
+
+/// IDs as a comma-separated list, as the API returns them.
+fn join_ids(ids: impl IntoIterator<Item = usize>) -> String {
+    ids.into_iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::ParsedFeedItem,
+        db::test_util::{add_feed, add_item, test_db},
+        server::Credentials,
+    };
+
+    fn credentials() -> Credentials {
+        Credentials {
+            username: "me@example.com".to_string(),
+            password: "secret".to_string(),
+        }
+    }
+
+    /// Serve the API for a database with one feed of two items in a folder and another feed of
+    /// one item outside it, and return the base URL.
+    async fn serve() -> String {
+        let db = test_db();
+        let feed_id = add_feed(&db, "https://example.com/feed.xml", "Example");
+        let folder_id = db.create_folder("Tech", None).unwrap();
+        db.set_feed_folder(feed_id, Some(folder_id)).unwrap();
+        for i in 1..=2 {
+            add_item(
+                &db,
+                feed_id,
+                &ParsedFeedItem {
+                    title: Some(format!("Item {}", i)),
+                    link: Some(format!("https://example.com/{}", i)),
+                    ..Default::default()
+                },
+            );
+        }
+        let other_id = add_feed(&db, "https://other.example.com/feed.xml", "Other");
+        add_item(
+            &db,
+            other_id,
+            &ParsedFeedItem {
+                title: Some("Other item".to_string()),
+                link: Some("https://other.example.com/1".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let app = router(db, credentials().api_key());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        base
+    }
+
+    /// Call the API with the given parameters and the test API key.
+    async fn call(base: &str, params: &[(&str, &str)]) -> Value {
+        let key = credentials().api_key();
+        let mut form = vec![("api_key", key.as_str())];
+        form.extend_from_slice(params);
+        reqwest::Client::new()
+            .post(format!("{}/fever/?api", base))
+            .form(&form)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fever_api() {
+        let base = serve().await;
+
+        let denied: Value = reqwest::get(format!("{}/?api&api_key=wrong", base))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(denied, json!({ "api_version": 3, "auth": 0 }));
+
+        let response = call(&base, &[("groups", ""), ("feeds", "")]).await;
+        assert_eq!(response["auth"], 1);
+        assert_eq!(response["groups"][0]["title"], "Tech");
+        assert_eq!(response["feeds"][0]["title"], "Example");
+        assert_eq!(
+            response["feeds_groups"][0]["feed_ids"],
+            response["feeds"][0]["id"].to_string()
+        );
+
+        let response = call(&base, &[("items", ""), ("since_id", "0")]).await;
+        assert_eq!(response["total_items"], 3);
+        let items = response["items"].as_array().unwrap();
+        assert_eq!(items[0]["title"], "Item 1");
+        let id = items[0]["id"].to_string();
+
+        call(&base, &[("mark", "item"), ("as", "read"), ("id", &id)]).await;
+        call(&base, &[("mark", "item"), ("as", "saved"), ("id", &id)]).await;
+        let response = call(&base, &[("unread_item_ids", ""), ("saved_item_ids", "")]).await;
+        assert_eq!(
+            response["unread_item_ids"],
+            format!("{},{}", items[1]["id"], items[2]["id"]).as_str()
+        );
+        assert_eq!(response["saved_item_ids"], id.as_str());
+
+        call(&base, &[("mark", "group"), ("as", "read"), ("id", "0")]).await;
+        let response = call(&base, &[("unread_item_ids", "")]).await;
+        assert_eq!(response["unread_item_ids"], "");
+
+        // API keys are accepted in uppercase too
+        let upper = credentials().api_key().to_uppercase();
+        let response: Value = reqwest::get(format!("{}/?api&api_key={}", base, upper))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(response["auth"], 1);
+    }
+
+    /// The IDs of the items of a response.
+    fn item_ids(response: &Value) -> Vec<u64> {
+        response["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_u64().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_fever_item_paging() {
+        let base = serve().await;
+        let ids = item_ids(&call(&base, &[("items", ""), ("since_id", "0")]).await);
+        assert_eq!(ids.len(), 3);
+
+        let since = ids[0].to_string();
+        let response = call(&base, &[("items", ""), ("since_id", &since)]).await;
+        assert_eq!(item_ids(&response), ids[1..]);
+
+        let max = ids[2].to_string();
+        let response = call(&base, &[("items", ""), ("max_id", &max)]).await;
+        assert_eq!(item_ids(&response), vec![ids[1], ids[0]]);
+
+        let with_ids = format!("{},{}", ids[2], ids[0]);
+        let response = call(&base, &[("items", ""), ("with_ids", &with_ids)]).await;
+        assert_eq!(item_ids(&response), vec![ids[0], ids[2]]);
+    }
+
+    #[tokio::test]
+    async fn test_fever_mark_feed_and_group() {
+        let base = serve().await;
+        let response = call(&base, &[("groups", ""), ("feeds", ""), ("items", "")]).await;
+        let group = response["groups"][0]["id"].to_string();
+        let other = response["feeds"][1]["id"].to_string();
+        let ids = item_ids(&response);
+        let unread = async || {
+            call(&base, &[("unread_item_ids", "")]).await["unread_item_ids"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        // Only items added before the time are marked
+        let mark_other = [("mark", "feed"), ("as", "read"), ("id", &other)];
+        call(&base, &[&mark_other[..], &[("before", "1")]].concat()).await;
+        assert_eq!(unread().await, format!("{},{},{}", ids[0], ids[1], ids[2]));
+
+        call(&base, &mark_other).await;
+        assert_eq!(unread().await, format!("{},{}", ids[0], ids[1]));
+
+        let third = ids[2].to_string();
+        call(&base, &[("mark", "item"), ("as", "unread"), ("id", &third)]).await;
+        call(&base, &[("mark", "group"), ("as", "read"), ("id", &group)]).await;
+        assert_eq!(unread().await, third);
+
+        // Negative IDs of items and feeds are rejected
+        let key = credentials().api_key();
+        for kind in ["item", "feed"] {
+            let response = reqwest::Client::new()
+                .post(format!("{}/fever/?api", base))
+                .form(&[
+                    ("api_key", key.as_str()),
+                    ("mark", kind),
+                    ("as", "read"),
+                    ("id", "-1"),
+                ])
+                .send()
+                .await
+                .unwrap();
+            assert!(response.status().is_server_error());
+            assert_eq!(response.text().await.unwrap(), "invalid id: -1");
+        }
+        assert_eq!(unread().await, third);
+    }
+}
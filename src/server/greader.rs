@@ -0,0 +1,773 @@
+/*!
+The Google Reader API, as implemented by FreshRSS and Miniflux.
+
+* Clients log in at `/accounts/ClientLogin` and send the token they get back in an
+  `Authorization: GoogleLogin auth=<token>` header.
+* Feeds are the streams `feed/<id>`. Folders and item tags are both labels,
+  `user/-/label/<name>`, folders being named by their full path.
+* Read and starred items are the `read` and `starred` state streams, and `reading-list` is
+  every item. Pages of items continue from the ID of the last item of the previous page.
+*/
+use anyhow::Result;
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION},
+    response::{IntoResponse, Response},
+    routing::any,
+};
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+
+use super::{Credentials, Params, constant_time_eq};
+use crate::db::{Db, Feed, FeedItem, ItemPage, ItemSelection};
+
+/// Stream of every item.
+const READING_LIST: &str = "user/-/state/com.google/reading-list";
+/// Stream of starred items.
+const STARRED: &str = "user/-/state/com.google/starred";
+/// Stream of read items.
+const READ: &str = "user/-/state/com.google/read";
+/// Tag that clients add to mark items as unread.
+const KEPT_UNREAD: &str = "user/-/state/com.google/kept-unread";
+/// Prefix of the streams of folders and item tags.
+const LABEL_PREFIX: &str = "user/-/label/";
+/// Prefix of the long form of item IDs, followed by the ID in hex.
+const ITEM_ID_PREFIX: &str = "tag:google.com,2005:reader/item/";
+
+/// Number of items returned by default.
+const DEFAULT_ITEMS: usize = 20;
+/// Maximum number of items returned at once.
+const MAX_ITEMS: usize = 1000;
+/// Maximum number of item IDs returned at once.
+const MAX_ITEM_IDS: usize = 10000;
+
+/// State shared by the request handlers.
+struct GreaderState {
+    db: Mutex<Db>,
+    credentials: Credentials,
+    /// Token clients authorize requests with.
+    token: String,
+}
+
+/// The response to a request, or an error status with a message.
+type ApiResult = Result<Response, (StatusCode, String)>;
+
+/// The routes of the Google Reader API, for clients logging in with the given credentials.
+pub fn router(db: Db, credentials: Credentials) -> Router {
+    let state = Arc::new(GreaderState {
+        db: Mutex::new(db),
+        token: credentials.api_key(),
+        credentials,
+    });
+    Router::new()
+        .route("/accounts/ClientLogin", any(login))
+        .route("/reader/api/0/{*endpoint}", any(handle))
+        .with_state(state)
+}
+
+/// Log a client in with the `Email` and `Passwd` parameters, responding with the token.
+async fn login(
+    State(state): State<Arc<GreaderState>>,
+    Query(query): Query<Vec<(String, String)>>,
+    body: Bytes,
+) -> ApiResult {
+    let params = Params::new(query, &body);
+    let credentials = &state.credentials;
+    let matches = |name, expected: &str| {
+        params
+            .get(name)
+            .is_some_and(|value| constant_time_eq(value.as_bytes(), expected.as_bytes()))
+    };
+    // Check both, so that how long the check takes doesn't tell whether the user exists
+    let (user_ok, password_ok) = (
+        matches("Email", &credentials.username),
+        matches("Passwd", &credentials.password),
+    );
+    if !(user_ok && password_ok) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "Error=BadAuthentication\n".to_string(),
+        ));
+    }
+    let token = &state.token;
+    Ok(format!("SID={token}\nLSID={token}\nAuth={token}\n").into_response())
+}
+
+/// Handle an authorized API request.
+async fn handle(
+    State(state): State<Arc<GreaderState>>,
+    Path(endpoint): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<Vec<(String, String)>>,
+    body: Bytes,
+) -> ApiResult {
+    let authorized = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("GoogleLogin auth="))
+        .is_some_and(|token| constant_time_eq(token.trim().as_bytes(), state.token.as_bytes()));
+    if !authorized {
+        return Err((StatusCode::UNAUTHORIZED, "Unauthorized".to_string()));
+    }
+    let params = Params::new(query, &body);
+
+    let db = state.db.lock().unwrap_or_else(|e| e.into_inner());
+    respond(&state, &db, &endpoint, &params)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))
+}
+
+/// Build the response to a request for an endpoint under `/reader/api/0/`.
+fn respond(state: &GreaderState, db: &Db, endpoint: &str, params: &Params) -> Result<Response> {
+    let response = match endpoint {
+        "token" => return Ok(state.token.clone().into_response()),
+        "user-info" => {
+            let username = &state.credentials.username;
+            json!({
+                "userId": "1",
+                "userName": username,
+                "userProfileId": "1",
+                "userEmail": username,
+            })
+        }
+        "subscription/list" => subscriptions(db)?,
+        "tag/list" => tags(db)?,
+        "unread-count" => unread_counts(db)?,
+        "stream/items/ids" => item_ids(db, params)?,
+        "stream/items/contents" => {
+            let ids: Vec<usize> = params.all("i").filter_map(parse_item_id).collect();
+            let items =
+                db.get_items_page(&ItemSelection::default(), ItemPage::Ids(&ids), ids.len())?;
+            stream_json(db, READING_LIST, &items, None)?
+        }
+        "edit-tag" => {
+            for id in params.all("i").filter_map(parse_item_id) {
+                for tag in params.all("a") {
+                    set_tag(db, id, tag, true)?;
+                }
+                for tag in params.all("r") {
+                    set_tag(db, id, tag, false)?;
+                }
+            }
+            return Ok("OK".into_response());
+        }
+        "mark-all-as-read" => {
+            let stream = params.get("s").unwrap_or(READING_LIST);
+            let Some(mut selection) = selection(db, stream)? else {
+                return Ok(unknown_stream(stream));
+            };
+            selection.is_read = Some(false);
+            // `ts` is in microseconds
+            selection.until = params.number::<i64>("ts").map(|ts| ts / 1_000_000);
+            for id in db.select_item_ids(&selection)? {
+                db.mark_item_read(id)?;
+            }
+            return Ok("OK".into_response());
+        }
+        _ => match endpoint.strip_prefix("stream/contents") {
+            Some(path) => {
+                let stream = match path.trim_start_matches('/') {
+                    "" => params.get("s").unwrap_or(READING_LIST),
+                    stream => stream,
+                };
+                match stream_contents(db, stream, params)? {
+                    Some(response) => response,
+                    None => return Ok(unknown_stream(stream)),
+                }
+            }
+            None => return Ok((StatusCode::NOT_FOUND, "Not found").into_response()),
+        },
+    };
+    Ok(Json(response).into_response())
+}
+
+/// The response to a request for a stream that doesn't exist.
+fn unknown_stream(stream: &str) -> Response {
+    (StatusCode::NOT_FOUND, format!("Unknown stream: {}", stream)).into_response()
+}
+
+/// The feeds, with the folders they're in as categories.
+fn subscriptions(db: &Db) -> Result<Value> {
+    let subscriptions = db
+        .list_feeds()?
+        .iter()
+        .map(|feed| {
+            let categories = match feed.folder_id {
+                Some(folder_id) => {
+                    let path = db.folder_path(folder_id)?;
+                    vec![json!({ "id": format!("{}{}", LABEL_PREFIX, path), "label": path })]
+                }
+                None => Vec::new(),
+            };
+            Ok(json!({
+                "id": feed_stream(feed.id),
+                "title": feed.title.as_deref().unwrap_or(&feed.url),
+                "categories": categories,
+                "url": feed.url,
+                "htmlUrl": db.get_feed_metadata(feed.id)?.site_link.unwrap_or_default(),
+                "iconUrl": "",
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(json!({ "subscriptions": subscriptions }))
+}
+
+/// The starred state, folders and item tags.
+fn tags(db: &Db) -> Result<Value> {
+    let mut tags = vec![json!({ "id": STARRED })];
+    for (_, path) in folder_paths(db)? {
+        tags.push(json!({ "id": format!("{}{}", LABEL_PREFIX, path), "type": "folder" }));
+    }
+    for tag in db.list_item_tags()? {
+        tags.push(json!({ "id": format!("{}{}", LABEL_PREFIX, tag), "type": "tag" }));
+    }
+    Ok(json!({ "tags": tags }))
+}
+
+/// The number of unread items of each feed and folder, and in total.
+fn unread_counts(db: &Db) -> Result<Value> {
+    let feeds = db.list_feeds()?;
+    let count = |id: String, feeds: &[&Feed]| {
+        json!({
+            "id": id,
+            "count": feeds.iter().map(|f| f.unread_count).sum::<usize>(),
+            "newestItemTimestampUsec": usec(feeds.iter().filter_map(|f| f.last_updated).max().unwrap_or_default()),
+        })
+    };
+
+    let mut counts: Vec<Value> = feeds
+        .iter()
+        .filter(|f| f.unread_count > 0)
+        .map(|f| count(feed_stream(f.id), &[f]))
+        .collect();
+    let paths = folder_paths(db)?;
+    for (_, path) in &paths {
+        let folder_ids = label_folders(&paths, path);
+        let in_folder: Vec<&Feed> = feeds
+            .iter()
+            .filter(|f| f.folder_id.is_some_and(|id| folder_ids.contains(&id)))
+            .collect();
+        counts.push(count(format!("{}{}", LABEL_PREFIX, path), &in_folder));
+    }
+    counts.push(count(
+        READING_LIST.to_string(),
+        &feeds.iter().collect::<Vec<_>>(),
+    ));
+    Ok(json!({ "max": MAX_ITEMS, "unreadcounts": counts }))
+}
+
+/// A page of the items of a stream, or `None` if the stream doesn't exist.
+fn stream_contents(db: &Db, stream: &str, params: &Params) -> Result<Option<Value>> {
+    let Some((items, continuation)) = stream_page(db, stream, params, MAX_ITEMS)? else {
+        return Ok(None);
+    };
+    stream_json(db, stream, &items, continuation).map(Some)
+}
+
+/// A page of the IDs of the items of a stream.
+fn item_ids(db: &Db, params: &Params) -> Result<Value> {
+    let stream = params.get("s").unwrap_or(READING_LIST);
+    let Some((items, continuation)) = stream_page(db, stream, params, MAX_ITEM_IDS)? else {
+        return Ok(json!({ "itemRefs": [] }));
+    };
+    let refs: Vec<Value> = items
+        .iter()
+        .map(|item| {
+            json!({
+                "id": item.id.to_string(),
+                "directStreamIds": [],
+                "timestampUsec": usec(item.created_at),
+            })
+        })
+        .collect();
+    let mut response = json!({ "itemRefs": refs });
+    if let Some(continuation) = continuation {
+        response["continuation"] = json!(continuation);
+    }
+    Ok(response)
+}
+
+/// A page of the items of a stream, filtered and paged by the request parameters, and the
+/// continuation for the next page if there may be more. `None` if the stream doesn't exist.
+fn stream_page(
+    db: &Db,
+    stream: &str,
+    params: &Params,
+    max: usize,
+) -> Result<Option<(Vec<FeedItem>, Option<String>)>> {
+    let Some(mut selection) = selection(db, stream)? else {
+        return Ok(None);
+    };
+    for tag in params.all("xt") {
+        match normalize(tag).as_str() {
+            READ => selection.is_read = Some(false),
+            STARRED => selection.is_starred = Some(false),
+            _ => {}
+        }
+    }
+    for tag in params.all("it") {
+        match normalize(tag).as_str() {
+            READ => selection.is_read = Some(true),
+            KEPT_UNREAD => selection.is_read = Some(false),
+            STARRED => selection.is_starred = Some(true),
+            _ => {}
+        }
+    }
+    // `ot` is the oldest and `nt` the newest time of the items, in seconds
+    selection.since = params.number("ot");
+    selection.until = params.number("nt");
+
+    let oldest_first = params.get("r") == Some("o");
+    let page = match (oldest_first, params.number("c")) {
+        (true, continuation) => ItemPage::After(continuation.unwrap_or_default()),
+        (false, Some(continuation)) => ItemPage::Before(continuation),
+        (false, None) => ItemPage::Latest,
+    };
+    let limit = params.number("n").unwrap_or(DEFAULT_ITEMS).clamp(1, max);
+    let items = db.get_items_page(&selection, page, limit)?;
+    let continuation = (items.len() == limit)
+        .then(|| items.last().map(|item| item.id.to_string()))
+        .flatten();
+    Ok(Some((items, continuation)))
+}
+
+/// The items selected by a stream, or `None` if the stream doesn't exist. A label is a
+/// folder if there's a folder with that path, and an item tag otherwise.
+fn selection(db: &Db, stream: &str) -> Result<Option<ItemSelection>> {
+    let stream = normalize(stream);
+    let mut selection = ItemSelection::default();
+    match stream.as_str() {
+        READING_LIST => {}
+        STARRED => selection.is_starred = Some(true),
+        READ => selection.is_read = Some(true),
+        _ => {
+            if let Some(id) = stream.strip_prefix("feed/") {
+                let Ok(id) = id.parse() else {
+                    return Ok(None);
+                };
+                selection.feed_ids = Some(vec![id]);
+            } else if let Some(label) = stream.strip_prefix(LABEL_PREFIX) {
+                let folder_ids = label_folders(&folder_paths(db)?, label);
+                if folder_ids.is_empty() {
+                    selection.tag = Some(label.to_string());
+                } else {
+                    let feed_ids = db
+                        .list_feeds()?
+                        .into_iter()
+                        .filter(|f| f.folder_id.is_some_and(|id| folder_ids.contains(&id)))
+                        .map(|f| f.id)
+                        .collect();
+                    selection.feed_ids = Some(feed_ids);
+                }
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+    Ok(Some(selection))
+}
+
+/// Add or remove a state or label of an item.
+fn set_tag(db: &Db, item_id: usize, tag: &str, add: bool) -> Result<()> {
+    match normalize(tag).as_str() {
+        READ if add => db.mark_item_read(item_id),
+        READ | KEPT_UNREAD => db.mark_item_unread(item_id),
+        STARRED => db.set_item_starred(item_id, add),
+        tag => match tag.strip_prefix(LABEL_PREFIX) {
+            Some(label) if add => db.add_item_tag(item_id, label),
+            Some(label) => db.remove_item_tag(item_id, label),
+            None => Ok(()),
+        },
+    }
+}
+
+/// A stream of items as returned by the API.
+fn stream_json(
+    db: &Db,
+    stream: &str,
+    items: &[FeedItem],
+    continuation: Option<String>,
+) -> Result<Value> {
+    let feeds = db.list_feeds()?;
+    let items: Vec<Value> = items
+        .iter()
+        .map(|item| item_json(item, feeds.iter().find(|f| f.id == item.feed_id)))
+        .collect();
+    let mut response = json!({
+        "id": stream,
+        "updated": time::OffsetDateTime::now_utc().unix_timestamp(),
+        "items": items,
+    });
+    if let Some(continuation) = continuation {
+        response["continuation"] = json!(continuation);
+    }
+    Ok(response)
+}
+
+/// An item as returned by the API.
+fn item_json(item: &FeedItem, feed: Option<&Feed>) -> Value {
+    let mut categories = vec![READING_LIST.to_string()];
+    if item.is_read {
+        categories.push(READ.to_string());
+    }
+    if item.is_starred {
+        categories.push(STARRED.to_string());
+    }
+    categories.extend(item.tags.iter().map(|t| format!("{}{}", LABEL_PREFIX, t)));
+    let link = item.link.as_deref().unwrap_or_default();
+    let published = item.published.unwrap_or(item.created_at);
+    json!({
+        "id": format!("{}{:016x}", ITEM_ID_PREFIX, item.id),
+        "crawlTimeMsec": (item.created_at * 1000).to_string(),
+        "timestampUsec": usec(item.created_at),
+        "published": published,
+        "updated": published,
+        "title": item.title.as_deref().unwrap_or_default(),
+        "canonical": [{ "href": link }],
+        "alternate": [{ "href": link, "type": "text/html" }],
+        "summary": { "direction": "ltr", "content": item.description.as_deref().unwrap_or_default() },
+        "author": item.authors.join(", "),
+        "categories": categories,
+        "origin": {
+            "streamId": feed_stream(item.feed_id),
+            "title": feed.and_then(|f| f.title.as_deref()).unwrap_or_default(),
+        },
+    })
+}
+
+/// The stream ID of a feed.
+fn feed_stream(feed_id: usize) -> String {
+    format!("feed/{}", feed_id)
+}
+
+/// A Unix timestamp in microseconds, as the API returns them.
+fn usec(timestamp: i64) -> String {
+    (timestamp * 1_000_000).to_string()
+}
+
+/// Parse an item ID in its long form (`tag:google.com,2005:reader/item/<hex>`) or as a
+/// decimal number.
+fn parse_item_id(id: &str) -> Option<usize> {
+    match id.strip_prefix(ITEM_ID_PREFIX) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => id.parse().ok(),
+    }
+}
+
+/// Replace the user ID in a stream or tag ID with `-`, the current user.
+fn normalize(id: &str) -> String {
+    match id
+        .strip_prefix("user/")
+        .and_then(|rest| rest.split_once('/'))
+    {
+        Some((_, path)) => format!("user/-/{}", path),
+        None => id.to_string(),
+    }
+}
+
+/// The ID and full path of every folder.
+fn folder_paths(db: &Db) -> Result<Vec<(usize, String)>> {
+    db.list_folders()?
+        .iter()
+        .map(|folder| Ok((folder.id, db.folder_path(folder.id)?)))
+        .collect()
+}
+
+/// The IDs of the folder with a path and its subfolders.
+fn label_folders(paths: &[(usize, String)], label: &str) -> Vec<usize> {
+    paths
+        .iter()
+        .filter(|(_, path)| {
+            path == label
+                || path
+                    .strip_prefix(label)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::ParsedFeedItem,
+        db::test_util::{add_feed, add_item, test_db},
+    };
+
+    /// When the first test item was published, an hour before the second.
+    const PUBLISHED: i64 = 1_714_564_800;
+
+    /// Serve the API for a database with one feed of three items, published an hour apart, in a
+    /// folder, and return the base URL.
+    async fn serve() -> String {
+        let db = test_db();
+        let feed_id = add_feed(&db, "https://example.com/feed.xml", "Example");
+        let folder_id = db.create_folder_path("Tech/Rust").unwrap().unwrap();
+        db.set_feed_folder(feed_id, Some(folder_id)).unwrap();
+        for i in 1..=3 {
+            add_item(
+                &db,
+                feed_id,
+                &ParsedFeedItem {
+                    title: Some(format!("Item {}", i)),
+                    link: Some(format!("https://example.com/{}", i)),
+                    published: Some(PUBLISHED + i * 3600),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let credentials = Credentials {
+            username: "me".to_string(),
+            password: "secret".to_string(),
+        };
+        let app = router(db, credentials);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        base
+    }
+
+    /// Log in and return the authorization header value.
+    async fn login(client: &reqwest::Client, base: &str) -> String {
+        let body = client
+            .post(format!("{}/accounts/ClientLogin", base))
+            .form(&[("Email", "me"), ("Passwd", "secret")])
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let token = body
+            .lines()
+            .find_map(|line| line.strip_prefix("Auth="))
+            .expect("no token");
+        format!("GoogleLogin auth={}", token)
+    }
+
+    #[tokio::test]
+    async fn test_greader_api() {
+        let base = serve().await;
+        let client = reqwest::Client::new();
+        let bad_login = client
+            .post(format!("{}/accounts/ClientLogin", base))
+            .form(&[("Email", "me"), ("Passwd", "wrong")])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(bad_login.status(), StatusCode::UNAUTHORIZED);
+
+        let auth = login(&client, &base).await;
+        let get = |path: &str| {
+            client
+                .get(format!("{}/reader/api/0/{}", base, path))
+                .header(AUTHORIZATION, &auth)
+                .send()
+        };
+        let json = |response: reqwest::Response| async { response.json::<Value>().await.unwrap() };
+
+        let subscriptions = json(get("subscription/list?output=json").await.unwrap()).await;
+        let feed = &subscriptions["subscriptions"][0];
+        assert_eq!(feed["title"], "Example");
+        assert_eq!(feed["categories"][0]["id"], "user/-/label/Tech/Rust");
+
+        // Page through the unread items of the parent folder, newest first
+        let page = json(
+            get("stream/contents/user/-/label/Tech?n=2&xt=user/-/state/com.google/read")
+                .await
+                .unwrap(),
+        )
+        .await;
+        let items = page["items"].as_array().unwrap();
+        assert_eq!(items[0]["title"], "Item 3");
+        assert_eq!(items.len(), 2);
+        let continuation = page["continuation"].as_str().unwrap();
+        let page = json(
+            get(&format!(
+                "stream/contents/user/-/label/Tech?n=2&c={}",
+                continuation
+            ))
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(page["items"][0]["title"], "Item 1");
+        assert!(page.get("continuation").is_none());
+
+        // Mark the newest item read and starred, and tag it
+        let id = items[0]["id"].as_str().unwrap();
+        let response = client
+            .post(format!("{}/reader/api/0/edit-tag", base))
+            .header(AUTHORIZATION, &auth)
+            .form(&[
+                ("i", id),
+                ("a", "user/1/state/com.google/read"),
+                ("a", "user/-/state/com.google/starred"),
+                ("a", "user/-/label/later"),
+            ])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "OK");
+
+        let starred = json(
+            get("stream/items/ids?s=user/-/state/com.google/starred")
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(starred["itemRefs"].as_array().unwrap().len(), 1);
+        let tagged = json(get("stream/contents/user/-/label/later").await.unwrap()).await;
+        assert_eq!(tagged["items"][0]["title"], "Item 3");
+        let tags = json(get("tag/list").await.unwrap()).await;
+        assert!(tags["tags"].as_array().unwrap().contains(&json!({
+            "id": "user/-/label/later",
+            "type": "tag"
+        })));
+
+        let counts = json(get("unread-count").await.unwrap()).await;
+        let reading_list = counts["unreadcounts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["id"] == READING_LIST)
+            .unwrap();
+        assert_eq!(reading_list["count"], 2);
+
+        let response = client
+            .post(format!("{}/reader/api/0/mark-all-as-read", base))
+            .header(AUTHORIZATION, &auth)
+            .form(&[("s", feed["id"].as_str().unwrap())])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "OK");
+        let unread = json(
+            get("stream/items/ids?s=user/-/state/com.google/reading-list&xt=user/-/state/com.google/read")
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert!(unread["itemRefs"].as_array().unwrap().is_empty());
+
+        let unauthorized = client
+            .get(format!("{}/reader/api/0/tag/list", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unauthorized.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_greader_streams() {
+        let base = serve().await;
+        let client = reqwest::Client::new();
+        let bad_login = client
+            .post(format!("{}/accounts/ClientLogin", base))
+            .form(&[("Email", "someone"), ("Passwd", "secret")])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(bad_login.status(), StatusCode::UNAUTHORIZED);
+
+        let auth = login(&client, &base).await;
+        let get = async |path: &str| -> Value {
+            client
+                .get(format!("{}/reader/api/0/{}", base, path))
+                .header(AUTHORIZATION, &auth)
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap()
+        };
+        let titles = |page: &Value| -> Vec<String> {
+            page["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["title"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // Oldest first, continuing after the last item of the page
+        let page = get("stream/contents/user/-/state/com.google/reading-list?r=o&n=2").await;
+        assert_eq!(titles(&page), ["Item 1", "Item 2"]);
+        let continuation = page["continuation"].as_str().unwrap();
+        let page = get(&format!(
+            "stream/contents/user/-/state/com.google/reading-list?r=o&n=2&c={}",
+            continuation
+        ))
+        .await;
+        assert_eq!(titles(&page), ["Item 3"]);
+        assert!(page.get("continuation").is_none());
+
+        // Only items published from `ot` on and before `nt`
+        let page = get(&format!(
+            "stream/contents/user/-/state/com.google/reading-list?ot={}",
+            PUBLISHED + 2 * 3600
+        ))
+        .await;
+        assert_eq!(titles(&page), ["Item 3", "Item 2"]);
+        let page = get(&format!(
+            "stream/contents/user/-/state/com.google/reading-list?ot={}&nt={}",
+            PUBLISHED + 2 * 3600,
+            PUBLISHED + 3 * 3600
+        ))
+        .await;
+        assert_eq!(titles(&page), ["Item 2"]);
+
+        // Nested folders are labels of their full path, and parents include their subfolders
+        let tags = get("tag/list").await;
+        for label in ["user/-/label/Tech", "user/-/label/Tech/Rust"] {
+            assert!(
+                tags["tags"]
+                    .as_array()
+                    .unwrap()
+                    .contains(&json!({ "id": label, "type": "folder" }))
+            );
+        }
+        let page = get("stream/contents/user/-/label/Tech/Rust").await;
+        assert_eq!(titles(&page), ["Item 3", "Item 2", "Item 1"]);
+        let counts = get("unread-count").await;
+        let count = |id: &str| {
+            counts["unreadcounts"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|c| c["id"] == id)
+                .map(|c| c["count"].clone())
+        };
+        assert_eq!(count("user/-/label/Tech"), Some(json!(3)));
+        assert_eq!(count("user/-/label/Tech/Rust"), Some(json!(3)));
+        assert_eq!(
+            get("stream/contents/user/-/label/Tech/Go")
+                .await
+                .get("items")
+                .map(|items| items.as_array().unwrap().len()),
+            Some(0)
+        );
+
+        // Unknown states are ignored
+        let id = page["items"][0]["id"].as_str().unwrap();
+        let response = client
+            .post(format!("{}/reader/api/0/edit-tag", base))
+            .header(AUTHORIZATION, &auth)
+            .form(&[("i", id), ("a", "user/-/state/com.google/broadcast")])
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.text().await.unwrap(), "OK");
+        let page = get(&format!("stream/items/contents?i={}", id)).await;
+        assert_eq!(
+            page["items"][0]["categories"],
+            json!(["user/-/state/com.google/reading-list"])
+        );
+    }
+}
@@ -3,6 +3,7 @@ use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     DefaultTerminal, Frame,
+    layout::{Constraint, Layout, Rect},
     prelude::Stylize,
     style::{Color, Modifier, Style},
     text::{Line, Span},
@@ -11,12 +12,38 @@ use ratatui::{
         ScrollbarState, Wrap,
     },
 };
+use std::time::Duration;
+
+use ratatui_image::StatefulImage;
 
 use crate::{
-    db::{Db, Feed, FeedItem},
-    ui::popup::{PopupState, get_centered_popup_area, pad_top_lines_center},
+    client::FeedClient,
+    clock::format_date,
+    config::{Config, LayoutMode, ReaderConfig},
+    db::{Db, Feed, FeedItem, FeedQuery, FeedSettings, ItemChange, ItemFilter, ItemQuery},
+    rules::RuleSet,
+    ui::{
+        feed_tree::{FeedRow, feed_rows},
+        images::{ImageCache, InlineImages, resolve_image_url},
+        popup::{EditFeedField, PopupState, get_centered_popup_area, pad_top_lines_center},
+        rich_text::{Reference, ReferenceKind, RichDocument, wrap_line},
+    },
 };
 
+/// Maximum number of blank lines between lines of post text.
+const MAX_LINE_SPACING: u16 = 3;
+
+/// Feed ID of the "All Items" view of every feed's items. Real feed IDs start at 1.
+const ALL_ITEMS_FEED_ID: usize = 0;
+
+/// Scale of stored post scroll positions, which are fractions of the post in ten-thousandths.
+const SCROLL_SCALE: usize = 10_000;
+
+/// How often to check for downloaded images while waiting for input.
+const IMAGE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub(crate) mod feed_tree;
+pub(crate) mod images;
 pub(crate) mod popup;
 pub(crate) mod rich_text;
 
@@ -40,29 +67,68 @@ pub struct App<'a> {
     help_scroll: u16,
     /// Scrollbar state for post view
     post_scroll_state: ScrollbarState,
+    /// Horizontal scroll position for post view, for preformatted text wider than the view
+    post_hscroll: u16,
+    /// Number of rows of the open post as last rendered, to keep the scroll position in place
+    /// when the post is re-wrapped. Zero until the post is first rendered.
+    post_line_count: usize,
+    /// Stored scroll position to resume the open post at, once its rows are known
+    post_resume: Option<u16>,
+    /// The most recently rendered post content, keyed by item ID and render width
+    post_render_cache: Option<(usize, u16, RichDocument)>,
+    /// Inline image rendering state, if enabled in the config
+    inline_images: Option<InlineImages>,
+    /// Post view layout options, adjustable at runtime
+    reader: ReaderConfig,
+    /// Page layout, toggleable at runtime
+    layout: LayoutMode,
+    /// Feeds shown beside the items list and post in the three-pane layout, refreshed when
+    /// they are left or an item is read rather than on every frame
+    side_feed_rows: Vec<FeedRow>,
+    /// Items of the highlighted feed shown in the preview pane, keyed by feed ID
+    preview_items: Option<(usize, Vec<FeedItem>)>,
+    /// Sort order and filters for the feeds list
+    feed_query: FeedQuery,
+    /// Sort order and filters for the items list
+    item_query: ItemQuery,
+    /// Rules applied to items of newly added feeds
+    rules: RuleSet,
+    /// HTTP client for fetching feeds
+    client: FeedClient,
+}
+
+/// How a page is shown in the current layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PaneRole {
+    /// The page has focus and receives key input.
+    Focused,
+    /// The page is shown next to the focused page for context.
+    Unfocused,
+    /// The page previews the selection of the focused page.
+    Preview,
 }
 
 /// The current page
 enum CurrentScreen {
-    /// The home library page showing feeds.
-    Feeds { feeds: Vec<Feed> },
+    /// The home library page showing feeds and their folders.
+    Feeds { rows: Vec<FeedRow> },
     /// Viewing items for a selected feed
     Items { feed: Feed, items: Vec<FeedItem> },
     /// Viewing content of a specific item
     ViewPost {
         feed: Feed,
         items: Vec<FeedItem>,
-        item: FeedItem,
+        item: Box<FeedItem>,
         scroll: u16,
     },
 }
 
 /// Initialize the TUI.
-pub fn init(db: &Db) -> anyhow::Result<()> {
+pub fn init(db: &Db, config: &Config) -> anyhow::Result<()> {
     let terminal = ratatui::init();
 
     // Enter main event loop
-    let result = App::new(db)?.run(terminal);
+    let result = App::new(db, config).and_then(|app| app.run(terminal));
 
     // Restore previous terminal state
     ratatui::restore();
@@ -71,23 +137,48 @@ pub fn init(db: &Db) -> anyhow::Result<()> {
 
 impl<'a> App<'a> {
     /// Construct a new instance of [`App`].
-    fn new(db: &'a Db) -> Result<Self> {
-        let feeds = db.list_feeds()?;
+    fn new(db: &'a Db, config: &Config) -> Result<Self> {
+        let feed_query = FeedQuery::default();
+        let rows = load_feed_rows(db, &feed_query)?;
         let mut feed_list_state = ListState::default();
-        if !feeds.is_empty() {
+        if !rows.is_empty() {
             feed_list_state.select(Some(0));
         }
+        let client = FeedClient::from_config(config)?;
+
+        // Query the terminal for graphics support now, before any events are read
+        let inline_images = match (&config.images.cache_dir, config.images.enabled) {
+            (Some(cache_dir), true) => Some(InlineImages::new(
+                ImageCache::new(cache_dir),
+                client.clone(),
+                config.images.max_height,
+            )),
+            _ => None,
+        };
 
         Ok(Self {
             running: true,
             popup: None,
-            current_page: CurrentScreen::Feeds { feeds },
+            current_page: CurrentScreen::Feeds { rows },
             db,
             feed_list_state,
             item_list_state: ListState::default(),
             help_scroll_state: ScrollbarState::default(),
             help_scroll: 0,
             post_scroll_state: ScrollbarState::default(),
+            post_hscroll: 0,
+            post_line_count: 0,
+            post_resume: None,
+            post_render_cache: None,
+            inline_images,
+            reader: config.reader.clone(),
+            layout: config.ui.layout,
+            side_feed_rows: Vec::new(),
+            preview_items: None,
+            feed_query,
+            item_query: ItemQuery::default(),
+            rules: RuleSet::from_config(&config.rules)?,
+            client,
         })
     }
 
@@ -95,6 +186,9 @@ impl<'a> App<'a> {
     fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
         self.running = true;
         while self.running {
+            if let Some(images) = &mut self.inline_images {
+                images.poll();
+            }
             terminal.draw(|frame| self.render(frame))?;
             self.handle_crossterm_event()?;
         }
@@ -105,9 +199,7 @@ impl<'a> App<'a> {
     fn render(&mut self, frame: &mut Frame) {
         // Clone the current page to avoid borrow checker issues
         let current_page = match &self.current_page {
-            CurrentScreen::Feeds { feeds } => CurrentScreen::Feeds {
-                feeds: feeds.clone(),
-            },
+            CurrentScreen::Feeds { rows } => CurrentScreen::Feeds { rows: rows.clone() },
             CurrentScreen::Items { feed, items } => CurrentScreen::Items {
                 feed: feed.clone(),
                 items: items.clone(),
@@ -125,20 +217,76 @@ impl<'a> App<'a> {
             },
         };
 
-        match &current_page {
-            CurrentScreen::Feeds { feeds } => {
-                self.render_feeds_page(frame, feeds);
-            }
-            CurrentScreen::Items { feed, items } => {
-                self.render_items_page(frame, feed, items);
+        let area = frame.area();
+        match self.layout {
+            LayoutMode::Stack => match &current_page {
+                CurrentScreen::Feeds { rows } => {
+                    self.render_feeds_page(frame, area, rows, PaneRole::Focused);
+                }
+                CurrentScreen::Items { feed, items } => {
+                    self.render_items_page(frame, area, feed, items, PaneRole::Focused);
+                }
+                CurrentScreen::ViewPost { item, scroll, .. } => {
+                    self.render_post_page(frame, area, item, *scroll, PaneRole::Focused);
+                }
+            },
+            LayoutMode::TwoPane => {
+                let [left, right] =
+                    Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                        .areas(area);
+                match &current_page {
+                    CurrentScreen::Feeds { rows } => {
+                        self.render_feeds_page(frame, left, rows, PaneRole::Focused);
+                        self.render_feed_preview(frame, right, rows);
+                    }
+                    CurrentScreen::Items { feed, items } => {
+                        self.render_items_page(frame, left, feed, items, PaneRole::Focused);
+                        self.render_item_preview(frame, right, items);
+                    }
+                    CurrentScreen::ViewPost {
+                        feed,
+                        items,
+                        item,
+                        scroll,
+                    } => {
+                        self.render_items_page(frame, left, feed, items, PaneRole::Unfocused);
+                        self.render_post_page(frame, right, item, *scroll, PaneRole::Focused);
+                    }
+                }
             }
-            CurrentScreen::ViewPost {
-                feed,
-                items,
-                item,
-                scroll,
-            } => {
-                self.render_post_page(frame, feed, items, item, *scroll);
+            LayoutMode::ThreePane => {
+                let [left, middle, right] = Layout::horizontal([
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(50),
+                ])
+                .areas(area);
+                match &current_page {
+                    CurrentScreen::Feeds { rows } => {
+                        self.render_feeds_page(frame, left, rows, PaneRole::Focused);
+                        self.render_feed_preview(frame, middle, rows);
+                        render_empty_preview(frame, right, "Select a feed to see its items.");
+                    }
+                    CurrentScreen::Items { feed, items } => {
+                        let rows = std::mem::take(&mut self.side_feed_rows);
+                        self.render_feeds_page(frame, left, &rows, PaneRole::Unfocused);
+                        self.side_feed_rows = rows;
+                        self.render_items_page(frame, middle, feed, items, PaneRole::Focused);
+                        self.render_item_preview(frame, right, items);
+                    }
+                    CurrentScreen::ViewPost {
+                        feed,
+                        items,
+                        item,
+                        scroll,
+                    } => {
+                        let rows = std::mem::take(&mut self.side_feed_rows);
+                        self.render_feeds_page(frame, left, &rows, PaneRole::Unfocused);
+                        self.side_feed_rows = rows;
+                        self.render_items_page(frame, middle, feed, items, PaneRole::Unfocused);
+                        self.render_post_page(frame, right, item, *scroll, PaneRole::Focused);
+                    }
+                }
             }
         }
 
@@ -147,12 +295,55 @@ impl<'a> App<'a> {
         }
     }
 
+    /// Render the items of the highlighted feed as a preview pane.
+    fn render_feed_preview(&mut self, frame: &mut Frame, area: Rect, rows: &[FeedRow]) {
+        let Some(feed) = self
+            .feed_list_state
+            .selected()
+            .and_then(|selected| rows.get(selected))
+            .and_then(FeedRow::feed)
+        else {
+            render_empty_preview(frame, area, "No feed selected.");
+            return;
+        };
+
+        // Reload the preview items only when the highlighted feed changes
+        let items = match &self.preview_items {
+            Some((feed_id, items)) if *feed_id == feed.id => items.clone(),
+            _ => {
+                let items = self.db.get_feed_items(feed.id).unwrap_or_default();
+                self.preview_items = Some((feed.id, items.clone()));
+                items
+            }
+        };
+        self.render_items_page(frame, area, feed, &items, PaneRole::Preview);
+    }
+
+    /// Render the highlighted item of an items list as a preview pane.
+    fn render_item_preview(&mut self, frame: &mut Frame, area: Rect, items: &[FeedItem]) {
+        match self
+            .item_list_state
+            .selected()
+            .and_then(|selected| items.get(selected))
+        {
+            Some(item) => self.render_post_page(frame, area, item, 0, PaneRole::Preview),
+            None => render_empty_preview(frame, area, "No item selected."),
+        }
+    }
+
     /// Render the feeds list page.
-    fn render_feeds_page(&mut self, frame: &mut Frame, feeds: &[Feed]) {
-        let title = Line::from(" ferrofeed - Feeds ")
-            .bold()
-            .blue()
-            .left_aligned();
+    fn render_feeds_page(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        rows: &[FeedRow],
+        role: PaneRole,
+    ) {
+        let title = Line::from(vec![
+            " ferrofeed - Feeds ".bold().blue(),
+            feeds_status(&self.feed_query).yellow(),
+        ])
+        .left_aligned();
 
         let instructions = Line::default().spans(vec![
             " Navigate: ".into(),
@@ -161,6 +352,24 @@ impl<'a> App<'a> {
             " Select: ".into(),
             "<Enter> ".blue(),
             " | ".into(),
+            " Sort: ".into(),
+            "o ".blue(),
+            " | ".into(),
+            " Unread: ".into(),
+            "u ".blue(),
+            " | ".into(),
+            " All Items: ".into(),
+            "A ".blue(),
+            " | ".into(),
+            " Info: ".into(),
+            "i ".blue(),
+            " | ".into(),
+            " Edit: ".into(),
+            "e ".blue(),
+            " | ".into(),
+            " Move: ".into(),
+            "m ".blue(),
+            " | ".into(),
             " Add: ".into(),
             "a ".blue(),
             " | ".into(),
@@ -174,7 +383,7 @@ impl<'a> App<'a> {
             "q ".blue(),
         ]);
 
-        if feeds.is_empty() {
+        if rows.is_empty() {
             let mut lines: Vec<Line> = vec![
                 Line::from("🦀 Looks like your feed list is feeling a bit... empty!"),
                 Line::from("No worries though! Here's how to fill it up:"),
@@ -187,39 +396,67 @@ impl<'a> App<'a> {
                 Line::from("https://blog.rust-lang.org/feed.xml").fg(Color::Yellow),
                 Line::from("https://this-week-in-rust.org/rss.xml").fg(Color::Yellow),
             ];
-            lines = pad_top_lines_center(lines, frame.area(), true);
+            lines = pad_top_lines_center(lines, area, true);
 
             frame.render_widget(
                 Paragraph::new(lines)
-                    .block(
-                        Block::bordered()
-                            .title(title)
-                            .title_bottom(instructions.right_aligned()),
-                    )
+                    .block(pane_block(title, instructions, role))
                     .centered(),
-                frame.area(),
+                area,
             );
             return;
         }
 
         // Create list items
-        let items: Vec<ListItem> = feeds
+        let unread_label = |count: usize| {
+            if count > 0 {
+                format!(" ({})", count)
+            } else {
+                String::new()
+            }
+        };
+        let items: Vec<ListItem> = rows
             .iter()
-            .map(|feed| {
-                let title = feed.title.as_deref().unwrap_or("(no title)");
-                ListItem::new(Line::from(vec![Span::styled(
-                    title,
-                    Style::default().fg(Color::Cyan),
-                )]))
+            .map(|row| match row {
+                FeedRow::Folder {
+                    folder,
+                    depth,
+                    unread_count,
+                } => ListItem::new(Line::from(vec![
+                    "  ".repeat(*depth).into(),
+                    if folder.is_collapsed { "▸ " } else { "▾ " }.into(),
+                    Span::styled(
+                        folder.name.as_str(),
+                        Style::default().fg(Color::Blue).bold(),
+                    ),
+                    Span::styled(
+                        unread_label(*unread_count),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                ])),
+                FeedRow::Feed { feed, depth } => ListItem::new(Line::from(vec![
+                    "  ".repeat(*depth).into(),
+                    Span::styled(
+                        feed.title.as_deref().unwrap_or("(no title)"),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(
+                        unread_label(feed.unread_count),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    if feed.is_gone {
+                        " [gone]".red()
+                    } else if feed.is_paused {
+                        " [paused]".dim()
+                    } else {
+                        "".into()
+                    },
+                ])),
             })
             .collect();
 
         let list = List::new(items)
-            .block(
-                Block::bordered()
-                    .title(title)
-                    .title_bottom(instructions.right_aligned()),
-            )
+            .block(pane_block(title, instructions, role))
             .highlight_style(
                 Style::default()
                     .bg(Color::DarkGray)
@@ -227,23 +464,42 @@ impl<'a> App<'a> {
             )
             .highlight_symbol(">> ");
 
-        frame.render_stateful_widget(list, frame.area(), &mut self.feed_list_state);
+        frame.render_stateful_widget(list, area, &mut self.feed_list_state);
     }
 
     /// Render the items list page for a selected feed.
-    fn render_items_page(&mut self, frame: &mut Frame, feed: &Feed, items: &[FeedItem]) {
-        let title = Line::from(format!(
-            " {} - Items ",
-            feed.title.as_deref().unwrap_or("Feed")
-        ))
-        .bold()
-        .blue()
-        .left_aligned();
+    fn render_items_page(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        feed: &Feed,
+        items: &[FeedItem],
+        role: PaneRole,
+    ) {
+        let mut title_spans = vec![
+            format!(" {} - Items ", feed.title.as_deref().unwrap_or("Feed"))
+                .bold()
+                .blue(),
+        ];
+        // Previews always show the feed's items unfiltered
+        if role != PaneRole::Preview {
+            title_spans.push(items_status(&self.item_query).yellow());
+        }
+        let title = Line::from(title_spans).left_aligned();
 
         let instructions = Line::default().spans(vec![
             " Navigate: ".into(),
             "j/k ".blue(),
             " | ".into(),
+            " Sort: ".into(),
+            "o ".blue(),
+            " | ".into(),
+            " Filter: ".into(),
+            "F ".blue(),
+            " | ".into(),
+            " Star: ".into(),
+            "* ".blue(),
+            " | ".into(),
             " Back: ".into(),
             "<ESC> ".blue(),
             " | ".into(),
@@ -255,16 +511,16 @@ impl<'a> App<'a> {
         ]);
 
         if items.is_empty() {
-            let text = "No items found for this feed.\n\nRun 'ferrofeed sync' to fetch items.";
+            let text = if role != PaneRole::Preview && !self.item_query.filter.is_empty() {
+                "No items match the current filter.\n\nPress 'F' to change it."
+            } else {
+                "No items found for this feed.\n\nRun 'ferrofeed sync' to fetch items."
+            };
             frame.render_widget(
                 Paragraph::new(text)
-                    .block(
-                        Block::bordered()
-                            .title(title)
-                            .title_bottom(instructions.right_aligned()),
-                    )
+                    .block(pane_block(title, instructions, role))
                     .centered(),
-                frame.area(),
+                area,
             );
             return;
         }
@@ -288,19 +544,22 @@ impl<'a> App<'a> {
                         .add_modifier(Modifier::BOLD)
                 };
 
+                let star = if item.is_starred { "★ " } else { "" };
+                let updated = if item.is_updated { "[updated] " } else { "" };
+                let tags: String = item.tags.iter().map(|t| format!(" #{}", t)).collect();
+
                 ListItem::new(Line::from(vec![
+                    Span::styled(star, Style::default().fg(Color::Yellow)),
+                    Span::styled(updated, Style::default().fg(Color::Green)),
                     Span::styled(title, style),
                     Span::styled(author, Style::default().fg(Color::Yellow)),
+                    Span::styled(tags, Style::default().fg(Color::Magenta)),
                 ]))
             })
             .collect();
 
         let list = List::new(list_items)
-            .block(
-                Block::bordered()
-                    .title(title)
-                    .title_bottom(instructions.right_aligned()),
-            )
+            .block(pane_block(title, instructions, role))
             .highlight_style(
                 Style::default()
                     .bg(Color::DarkGray)
@@ -308,17 +567,23 @@ impl<'a> App<'a> {
             )
             .highlight_symbol(">> ");
 
-        frame.render_stateful_widget(list, frame.area(), &mut self.item_list_state);
+        // A preview of a feed that isn't open has no selection
+        let mut preview_state = ListState::default();
+        let list_state = match role {
+            PaneRole::Preview => &mut preview_state,
+            _ => &mut self.item_list_state,
+        };
+        frame.render_stateful_widget(list, area, list_state);
     }
 
     /// Render the post content page for a selected item.
     fn render_post_page(
         &mut self,
         frame: &mut Frame,
-        _feed: &Feed,
-        _items: &[FeedItem],
+        area: Rect,
         item: &FeedItem,
         scroll: u16,
+        role: PaneRole,
     ) {
         let title = Line::from(format!(" {} ", item.title.as_deref().unwrap_or("Post")))
             .bold()
@@ -329,6 +594,15 @@ impl<'a> App<'a> {
             " Scroll: ".into(),
             "j/k ".blue(),
             " | ".into(),
+            " Pan: ".into(),
+            "h/l ".blue(),
+            " | ".into(),
+            " Links: ".into(),
+            "f ".blue(),
+            " | ".into(),
+            " Focus: ".into(),
+            "z ".blue(),
+            " | ".into(),
             " Back: ".into(),
             "<ESC> ".blue(),
             " | ".into(),
@@ -336,77 +610,155 @@ impl<'a> App<'a> {
             "q ".blue(),
         ]);
 
+        let block = if self.reader.distraction_free {
+            pane_block(title, Line::default(), role)
+        } else {
+            pane_block(title, instructions, role)
+        };
+        // Only the open post can be panned or show inline images; previews are static
+        let is_open = role == PaneRole::Focused;
+
+        // Limit the text column to the configured max width, centered in the view
+        let inner = block.inner(area);
+        let text_width = match self.reader.max_width {
+            Some(max_width) if max_width > 0 && max_width < inner.width => max_width,
+            _ => inner.width,
+        };
+        let text_area = Rect {
+            x: inner.x + (inner.width - text_width) / 2,
+            width: text_width,
+            ..inner
+        };
+        let viewport_height = text_area.height;
+        let viewport_width = text_area.width;
+
         // Build content
         let mut lines: Vec<Line> = Vec::new();
 
-        if let Some(link) = &item.link {
-            lines.push(Line::from(vec![
-                "Link: ".fg(Color::Yellow),
-                Span::styled(
-                    link.as_str(),
-                    Style::default().underlined().underline_color(Color::Cyan),
-                ),
-            ]));
-        }
-
-        if !item.authors.is_empty() {
-            lines.push(Line::from(vec![
-                "Author".fg(Color::Yellow),
-                if item.authors.len() > 1 { "s" } else { "" }.fg(Color::Yellow),
-                ": ".fg(Color::Yellow),
-                item.authors.join(", ").into(),
-            ]));
+        // Metadata header; post content is already wrapped to the viewport, so wrap it to match
+        if !self.reader.distraction_free {
+            lines.extend(
+                post_header_lines(item)
+                    .into_iter()
+                    .flat_map(|line| wrap_line(line, viewport_width as usize)),
+            );
         }
 
-        if let Some(published) = item.published {
-            use time::OffsetDateTime;
-            if let Ok(dt) = OffsetDateTime::from_unix_timestamp(published) {
-                let formatted = format!("{:04}-{:02}-{:02}", dt.year(), dt.month() as u8, dt.day());
-                lines.push(Line::from(vec![
-                    "Published: ".fg(Color::Yellow),
-                    formatted.into(),
-                ]));
-            } else {
-                lines.push(Line::from("N/A".italic()));
-            }
+        // Separator
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
         }
 
-        // Separator
-        lines.push(Line::from(""));
+        // Rows reserved for inline images: (index of first reserved line, image src, height)
+        let mut image_slots: Vec<(usize, String, u16)> = Vec::new();
 
         if let Some(desc) = &item.description {
-            match rich_text::html_to_rich_text(desc) {
-                Ok(styled_lines) => lines.extend(styled_lines),
+            match self.render_post_content(item.id, desc, viewport_width) {
+                Ok(doc) => {
+                    let line_spacing = self.reader.line_spacing as usize;
+                    for (line_idx, (line, preformatted)) in
+                        doc.lines.into_iter().zip(doc.preformatted).enumerate()
+                    {
+                        let is_blank = line.width() == 0;
+                        lines.push(line);
+                        // Code keeps its original spacing
+                        if !is_blank && !preformatted {
+                            lines.extend(std::iter::repeat_n(Line::from(""), line_spacing));
+                        }
+                        for reference in doc
+                            .references
+                            .iter()
+                            .filter(|r| r.kind == ReferenceKind::Image && r.line == line_idx)
+                        {
+                            match &self.inline_images {
+                                Some(_) if !is_open => {}
+                                Some(images) if images.loaded.contains_key(&reference.url) => {
+                                    image_slots.push((
+                                        lines.len(),
+                                        reference.url.clone(),
+                                        images.max_height,
+                                    ));
+                                    lines.extend(std::iter::repeat_n(
+                                        Line::from(""),
+                                        images.max_height as usize,
+                                    ));
+                                }
+                                Some(images) if images.pending.contains(&reference.url) => lines
+                                    .extend(wrap_line(
+                                        Line::from(
+                                            format!("[loading image: {}]", reference.text)
+                                                .italic()
+                                                .dim(),
+                                        ),
+                                        viewport_width as usize,
+                                    )),
+                                Some(_) => lines.extend(wrap_line(
+                                    Line::from(
+                                        format!("[image unavailable: {}]", reference.text)
+                                            .italic()
+                                            .dim(),
+                                    ),
+                                    viewport_width as usize,
+                                )),
+                                None => {}
+                            }
+                        }
+                    }
+                    if !doc.references.is_empty() {
+                        lines.push(Line::from(""));
+                        lines.push(Line::from("References:".fg(Color::Yellow)));
+                        for (i, reference) in doc.references.into_iter().enumerate() {
+                            lines.extend(wrap_line(
+                                reference_line(i + 1, reference),
+                                viewport_width as usize,
+                            ));
+                        }
+                    }
+                }
                 Err(_) => lines.push(Line::from("Error rendering HTML".italic())),
             }
         } else {
             lines.push(Line::from("No description available.".italic()));
         }
 
-        let area = frame.area();
-        let viewport_height = area.height.saturating_sub(2); // subtract borders
-        let viewport_width = area.width.saturating_sub(2); // subtract borders
+        // All lines are pre-wrapped (or deliberately unwrapped), so each takes up one row
+        let actual_line_count = lines.len();
 
-        // Calculate wrapped line count
-        let actual_line_count: usize = lines
-            .iter()
-            .map(|line| {
-                let line_width = line.width();
-                if line_width == 0 {
-                    1 // Empty lines still take one line
-                } else {
-                    // Calculate how many lines this will wrap to
-                    (line_width as u16).div_ceil(viewport_width.max(1)).max(1) as usize
-                }
-            })
-            .sum();
+        // Clamp horizontal scroll to the widest line
+        let max_line_width = lines.iter().map(|l| l.width()).max().unwrap_or(0) as u16;
+        let hscroll = if is_open {
+            self.post_hscroll = self
+                .post_hscroll
+                .min(max_line_width.saturating_sub(viewport_width));
+            self.post_hscroll
+        } else {
+            0
+        };
+
+        // Map a resumed position to rows, and keep the same part of the post in view when it
+        // re-wraps, e.g. after a resize
+        let scroll = if !is_open {
+            scroll
+        } else if let Some(position) = self.post_resume.take() {
+            scroll_row(position, actual_line_count)
+        } else if self.post_line_count != 0 && self.post_line_count != actual_line_count {
+            scroll_row(
+                scroll_fraction(scroll, self.post_line_count),
+                actual_line_count,
+            )
+        } else {
+            scroll
+        };
+        if is_open {
+            self.post_line_count = actual_line_count;
+        }
 
         // Calculate scroll bounds
         let max_scroll = (actual_line_count as u16).saturating_sub(viewport_height);
         let clamped_scroll = scroll.min(max_scroll);
 
         // Update the scroll position in current_page to reflect the clamped value
-        if let CurrentScreen::ViewPost { scroll, .. } = &mut self.current_page {
+        if is_open && let CurrentScreen::ViewPost { scroll, .. } = &mut self.current_page {
             *scroll = clamped_scroll;
         }
 
@@ -417,30 +769,69 @@ impl<'a> App<'a> {
             .viewport_content_length(viewport_height as usize)
             .position(clamped_scroll as usize);
 
+        frame.render_widget(block, area);
         frame.render_widget(
-            Paragraph::new(lines)
-                .block(
-                    Block::bordered()
-                        .title(title)
-                        .title_bottom(instructions.right_aligned()),
-                )
-                .wrap(Wrap { trim: true })
-                .scroll((clamped_scroll, 0)),
-            area,
+            Paragraph::new(lines).scroll((clamped_scroll, hscroll)),
+            text_area,
         );
 
+        // Draw inline images over their reserved rows, if they are fully in view (graphics
+        // protocols can't be partially clipped)
+        if is_open && let Some(images) = &mut self.inline_images {
+            for (line_idx, src, height) in image_slots {
+                let row = line_idx as i64 - clamped_scroll as i64;
+                if row < 0 || row + height as i64 > viewport_height as i64 {
+                    continue;
+                }
+                if let Some(protocol) = images.loaded.get_mut(&src) {
+                    let image_area = Rect::new(
+                        text_area.x,
+                        text_area.y + row as u16,
+                        viewport_width.saturating_sub(1),
+                        height,
+                    );
+                    frame.render_stateful_widget(StatefulImage::default(), image_area, protocol);
+                }
+            }
+        }
+
         // Render scrollbar if content is longer than viewport
-        if actual_line_count > viewport_height as usize {
+        if is_open && actual_line_count > viewport_height as usize {
             let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
             frame.render_stateful_widget(scrollbar, area, &mut self.post_scroll_state);
         }
     }
 
+    /// Render the HTML content of a post at the given width, reusing the previous rendering
+    /// if neither the post nor the width have changed.
+    fn render_post_content(
+        &mut self,
+        item_id: usize,
+        html: &str,
+        width: u16,
+    ) -> Result<RichDocument> {
+        if let Some((cached_id, cached_width, doc)) = &self.post_render_cache
+            && *cached_id == item_id
+            && *cached_width == width
+        {
+            return Ok(doc.clone());
+        }
+        let doc = rich_text::html_to_rich_text(html, width as usize)?;
+        self.post_render_cache = Some((item_id, width, doc.clone()));
+        Ok(doc)
+    }
+
     /// Reads the [`crossterm`] events and updates the state of [`App`].
     ///
     /// NOTE: `event::read()` is blocking, so if work needs to be down between event handling, use
     /// [`event::poll`] function to check for available events with a timeout.
     fn handle_crossterm_event(&mut self) -> Result<()> {
+        // Redraw now and then while images are downloading, so they show up as they arrive
+        if self.inline_images.as_ref().is_some_and(|i| i.is_loading())
+            && !event::poll(IMAGE_POLL_INTERVAL)?
+        {
+            return Ok(());
+        }
         match event::read()? {
             Event::Key(key) if key.kind.is_press() => self.on_key_event(key),
             Event::Mouse(_) => {}
@@ -501,6 +892,160 @@ impl<'a> App<'a> {
                         _ => {}
                     }
                 }
+                PopupState::FeedInfo { metadata, .. } => match key.code {
+                    KeyCode::Char('x') => {
+                        if let Some(link) = &metadata.site_link
+                            && let Err(err) = open::that(link)
+                        {
+                            eprintln!("Failed to open link in browser: {}", err);
+                        }
+                    }
+                    KeyCode::Char('i') | KeyCode::Esc => {
+                        self.popup = None;
+                    }
+                    KeyCode::Char('q') => {
+                        self.quit();
+                    }
+                    _ => {}
+                },
+                PopupState::Filter { input, error } => match key.code {
+                    KeyCode::Char(c) => {
+                        input.push(c);
+                        *error = None;
+                    }
+                    KeyCode::Backspace => {
+                        input.pop();
+                        *error = None;
+                    }
+                    KeyCode::Enter => match ItemFilter::parse(input) {
+                        Ok(filter) => {
+                            self.popup = None;
+                            self.item_query.filter = filter;
+                            self.reload_page();
+                        }
+                        Err(err) => *error = Some(err.to_string()),
+                    },
+                    KeyCode::Esc => {
+                        self.popup = None;
+                    }
+                    _ => {}
+                },
+                PopupState::LinkHints {
+                    references,
+                    input,
+                    selected,
+                } => {
+                    match key.code {
+                        KeyCode::Char(c) if c.is_ascii_digit() => {
+                            input.push(c);
+                            // Jump to the typed reference number, if it exists
+                            match input.parse::<usize>() {
+                                Ok(n) if (1..=references.len()).contains(&n) => {
+                                    *selected = n - 1;
+                                }
+                                _ => {
+                                    input.clear();
+                                }
+                            }
+                        }
+                        KeyCode::Backspace => {
+                            input.pop();
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => {
+                            input.clear();
+                            *selected = (*selected + 1).min(references.len().saturating_sub(1));
+                        }
+                        KeyCode::Char('k') | KeyCode::Up => {
+                            input.clear();
+                            *selected = selected.saturating_sub(1);
+                        }
+                        KeyCode::Enter | KeyCode::Char('o') => {
+                            if let Some(reference) = references.get(*selected)
+                                && let Err(err) = open::that(&reference.url)
+                            {
+                                eprintln!("Failed to open link in browser: {}", err);
+                            }
+                            self.popup = None;
+                        }
+                        KeyCode::Char('y') => {
+                            if let Some(reference) = references.get(*selected) {
+                                copy_to_clipboard(&reference.url);
+                            }
+                            self.popup = None;
+                        }
+                        KeyCode::Char('a') => {
+                            // Add the link as a new feed
+                            let url = references.get(*selected).map(|r| r.url.clone());
+                            self.popup = None;
+                            if let Some(url) = url {
+                                self.add_feed_async(url);
+                            }
+                        }
+                        KeyCode::Char('f') | KeyCode::Esc => {
+                            self.popup = None;
+                        }
+                        _ => {}
+                    }
+                }
+                PopupState::MoveFeed { feed, input, .. } => match key.code {
+                    KeyCode::Char(c) => {
+                        input.push(c);
+                    }
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Enter => {
+                        let (feed_id, path) = (feed.id, input.clone());
+                        self.popup = None;
+                        self.move_feed(feed_id, &path);
+                    }
+                    KeyCode::Esc => {
+                        self.popup = None;
+                    }
+                    _ => {}
+                },
+                PopupState::EditFeed {
+                    url,
+                    title,
+                    is_paused,
+                    notify,
+                    field,
+                    error,
+                    ..
+                } => match (key.code, *field) {
+                    (KeyCode::Tab | KeyCode::Down, _) => *field = field.next(),
+                    (KeyCode::BackTab | KeyCode::Up, _) => *field = field.prev(),
+                    (KeyCode::Char(' '), EditFeedField::Paused) => *is_paused = !*is_paused,
+                    (KeyCode::Char(' '), EditFeedField::Notify) => *notify = !*notify,
+                    (KeyCode::Char(c), EditFeedField::Url) => {
+                        url.push(c);
+                        *error = None;
+                    }
+                    (KeyCode::Char(c), EditFeedField::Title) => title.push(c),
+                    (KeyCode::Backspace, EditFeedField::Url) => {
+                        url.pop();
+                        *error = None;
+                    }
+                    (KeyCode::Backspace, EditFeedField::Title) => {
+                        title.pop();
+                    }
+                    (KeyCode::Enter, _) => self.save_feed_settings(),
+                    (KeyCode::Esc, _) => {
+                        self.popup = None;
+                    }
+                    _ => {}
+                },
+                PopupState::DeleteFolder { folder } => match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => {
+                        let folder_id = folder.id;
+                        self.popup = None;
+                        self.delete_folder(folder_id);
+                    }
+                    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                        self.popup = None;
+                    }
+                    _ => {}
+                },
                 PopupState::DeleteFeed { feed_url } => {
                     match key.code {
                         KeyCode::Char('y') | KeyCode::Char('Y') => {
@@ -550,12 +1095,112 @@ impl<'a> App<'a> {
             (_, KeyCode::Char('G')) => {
                 self.move_bottom();
             }
+            (_, KeyCode::Char('L')) => {
+                self.layout = self.layout.next();
+            }
+            (_, KeyCode::Char('o')) => match self.current_page {
+                CurrentScreen::Feeds { .. } => {
+                    self.feed_query.sort = self.feed_query.sort.next();
+                    self.reload_page();
+                }
+                CurrentScreen::Items { .. } => {
+                    self.item_query.sort = self.item_query.sort.next();
+                    self.reload_page();
+                }
+                CurrentScreen::ViewPost { .. } => {}
+            },
+            (_, KeyCode::Char('u')) => match self.current_page {
+                CurrentScreen::Feeds { .. } => {
+                    self.feed_query.unread_only = !self.feed_query.unread_only;
+                    self.reload_page();
+                }
+                CurrentScreen::Items { .. } => {
+                    self.item_query.filter.unread_only = !self.item_query.filter.unread_only;
+                    self.reload_page();
+                }
+                CurrentScreen::ViewPost { .. } => {}
+            },
+            (_, KeyCode::Char('S')) => {
+                if matches!(self.current_page, CurrentScreen::Items { .. }) {
+                    self.item_query.filter.starred_only = !self.item_query.filter.starred_only;
+                    self.reload_page();
+                }
+            }
+            (_, KeyCode::Char('F')) => {
+                if matches!(self.current_page, CurrentScreen::Items { .. }) {
+                    self.popup = Some(PopupState::Filter {
+                        input: self.item_query.filter.to_string(),
+                        error: None,
+                    });
+                }
+            }
+            (_, KeyCode::Char('*')) => {
+                self.toggle_star();
+            }
+            (_, KeyCode::Char('i')) => {
+                self.open_feed_info();
+            }
+            (_, KeyCode::Char('m')) => {
+                self.open_move_feed();
+            }
+            (_, KeyCode::Char('e')) => {
+                self.open_edit_feed();
+            }
+            (_, KeyCode::Char('A')) => {
+                if matches!(self.current_page, CurrentScreen::Feeds { .. }) {
+                    self.open_items(Feed {
+                        id: ALL_ITEMS_FEED_ID,
+                        url: String::new(),
+                        title: Some("All Items".to_string()),
+                        created_at: 0,
+                        unread_count: 0,
+                        last_updated: None,
+                        folder_id: None,
+                        is_paused: false,
+                        is_gone: false,
+                    });
+                }
+            }
+            (_, KeyCode::Tab) => {
+                self.cycle_focus();
+            }
+            (_, KeyCode::BackTab) if self.layout != LayoutMode::Stack => {
+                self.go_back();
+            }
+            (_, KeyCode::Char('z')) => {
+                if matches!(self.current_page, CurrentScreen::ViewPost { .. }) {
+                    self.reader.distraction_free = !self.reader.distraction_free;
+                }
+            }
+            (_, KeyCode::Char('+') | KeyCode::Char('=')) => {
+                if matches!(self.current_page, CurrentScreen::ViewPost { .. }) {
+                    self.reader.line_spacing = (self.reader.line_spacing + 1).min(MAX_LINE_SPACING);
+                }
+            }
+            (_, KeyCode::Char('-')) => {
+                if matches!(self.current_page, CurrentScreen::ViewPost { .. }) {
+                    self.reader.line_spacing = self.reader.line_spacing.saturating_sub(1);
+                }
+            }
+            (_, KeyCode::Char('h') | KeyCode::Left) => {
+                if matches!(self.current_page, CurrentScreen::ViewPost { .. }) {
+                    self.post_hscroll = self.post_hscroll.saturating_sub(4);
+                }
+            }
+            (_, KeyCode::Char('l') | KeyCode::Right) => {
+                if matches!(self.current_page, CurrentScreen::ViewPost { .. }) {
+                    self.post_hscroll = self.post_hscroll.saturating_add(4);
+                }
+            }
             (_, KeyCode::Enter) => {
                 self.select_item();
             }
             (_, KeyCode::Char('x')) => {
                 self.open_in_browser();
             }
+            (_, KeyCode::Char('f')) => {
+                self.open_link_hints();
+            }
             (_, KeyCode::Esc) => {
                 self.go_back();
             }
@@ -566,13 +1211,13 @@ impl<'a> App<'a> {
     /// Move selection down.
     fn move_down(&mut self) {
         match &mut self.current_page {
-            CurrentScreen::Feeds { feeds } => {
-                if feeds.is_empty() {
+            CurrentScreen::Feeds { rows } => {
+                if rows.is_empty() {
                     return;
                 }
                 let i = match self.feed_list_state.selected() {
                     Some(i) => {
-                        if i >= feeds.len() - 1 {
+                        if i >= rows.len() - 1 {
                             i
                         } else {
                             i + 1
@@ -642,8 +1287,8 @@ impl<'a> App<'a> {
     /// Move to the top of the list.
     fn move_top(&mut self) {
         match &mut self.current_page {
-            CurrentScreen::Feeds { feeds } => {
-                if !feeds.is_empty() {
+            CurrentScreen::Feeds { rows } => {
+                if !rows.is_empty() {
                     self.feed_list_state.select(Some(0));
                 }
             }
@@ -661,9 +1306,9 @@ impl<'a> App<'a> {
     /// Move to the bottom of the list.
     fn move_bottom(&mut self) {
         match &mut self.current_page {
-            CurrentScreen::Feeds { feeds } => {
-                if !feeds.is_empty() {
-                    self.feed_list_state.select(Some(feeds.len() - 1));
+            CurrentScreen::Feeds { rows } => {
+                if !rows.is_empty() {
+                    self.feed_list_state.select(Some(rows.len() - 1));
                 }
             }
             CurrentScreen::Items { items, .. } => {
@@ -680,32 +1325,53 @@ impl<'a> App<'a> {
     /// Select the currently highlighted item.
     fn select_item(&mut self) {
         match &self.current_page {
-            CurrentScreen::Feeds { feeds } => {
-                if let Some(selected) = self.feed_list_state.selected()
-                    && let Some(feed) = feeds.get(selected)
+            CurrentScreen::Feeds { rows } => {
+                match self
+                    .feed_list_state
+                    .selected()
+                    .and_then(|selected| rows.get(selected))
                 {
-                    // Load items for the selected feed
-                    if let Ok(items) = self.db.get_feed_items(feed.id) {
-                        self.item_list_state
-                            .select(if items.is_empty() { None } else { Some(0) });
-                        self.current_page = CurrentScreen::Items {
-                            feed: feed.clone(),
-                            items,
-                        };
+                    Some(FeedRow::Feed { feed, .. }) => {
+                        // Load items for the selected feed
+                        let feed = feed.clone();
+                        self.open_items(feed);
                     }
+                    Some(FeedRow::Folder { folder, .. }) => {
+                        let (folder_id, collapsed) = (folder.id, !folder.is_collapsed);
+                        self.collapse_folder(folder_id, collapsed);
+                    }
+                    None => {}
                 }
             }
             CurrentScreen::Items { feed, items } => {
                 if let Some(selected) = self.item_list_state.selected()
                     && let Some(item) = items.get(selected)
                 {
-                    // Open post content view
+                    // Open post content view, marking it read
+                    let mut item = item.clone();
+                    let mut items = items.clone();
+                    if (!item.is_read || item.is_updated) && self.db.mark_item_read(item.id).is_ok()
+                    {
+                        item.is_read = true;
+                        item.is_updated = false;
+                        if let Some(stored) = items.iter_mut().find(|i| i.id == item.id) {
+                            stored.is_read = true;
+                            stored.is_updated = false;
+                        }
+                    }
                     self.current_page = CurrentScreen::ViewPost {
                         feed: feed.clone(),
-                        items: items.clone(),
-                        item: item.clone(),
+                        items,
+                        item: Box::new(item.clone()),
                         scroll: 0,
                     };
+                    // Resume where the post was last left off
+                    self.post_resume = Some(item.scroll_position);
+                    self.post_line_count = 0;
+                    self.post_hscroll = 0;
+                    self.load_post_images(&item);
+                    // The unread count of its feed may have changed
+                    self.refresh_side_feed_rows();
                 }
             }
             CurrentScreen::ViewPost { .. } => {
@@ -714,6 +1380,19 @@ impl<'a> App<'a> {
         }
     }
 
+    /// Move focus to the next pane in the split-pane layouts: feeds, then items, then the post
+    /// and back to feeds.
+    fn cycle_focus(&mut self) {
+        match self.current_page {
+            _ if self.layout == LayoutMode::Stack => {}
+            CurrentScreen::Feeds { .. } | CurrentScreen::Items { .. } => self.select_item(),
+            CurrentScreen::ViewPost { .. } => {
+                self.go_back();
+                self.go_back();
+            }
+        }
+    }
+
     /// Go back to the previous screen.
     fn go_back(&mut self) {
         match &self.current_page {
@@ -722,25 +1401,39 @@ impl<'a> App<'a> {
             }
             CurrentScreen::Items { .. } => {
                 // Go back to feeds list
-                if let Ok(feeds) = self.db.list_feeds() {
+                if let Ok(rows) = load_feed_rows(self.db, &self.feed_query) {
                     // Restore selection or select first item if available
                     let selected = self.feed_list_state.selected();
-                    if selected.is_none() && !feeds.is_empty() {
+                    if selected.is_none() && !rows.is_empty() {
                         self.feed_list_state.select(Some(0));
                     } else if let Some(sel) = selected {
                         // Clamp selection to valid range
-                        if sel >= feeds.len() && !feeds.is_empty() {
-                            self.feed_list_state.select(Some(feeds.len() - 1));
+                        if sel >= rows.len() && !rows.is_empty() {
+                            self.feed_list_state.select(Some(rows.len() - 1));
                         }
                     }
-                    self.current_page = CurrentScreen::Feeds { feeds };
+                    self.current_page = CurrentScreen::Feeds { rows };
                 }
             }
-            CurrentScreen::ViewPost { feed, items, .. } => {
+            CurrentScreen::ViewPost {
+                feed,
+                items,
+                item,
+                scroll,
+            } => {
+                // Remember where the post was left off
+                let mut items = items.clone();
+                let position = self.post_scroll_position(*scroll);
+                if self.db.set_item_scroll_position(item.id, position).is_ok()
+                    && let Some(stored) = items.iter_mut().find(|i| i.id == item.id)
+                {
+                    stored.scroll_position = position;
+                }
+
                 // Go back to items list
                 self.current_page = CurrentScreen::Items {
                     feed: feed.clone(),
-                    items: items.clone(),
+                    items,
                 };
             }
         }
@@ -775,6 +1468,17 @@ impl<'a> App<'a> {
                         Span::raw("  To Bottom: "),
                         Span::styled("G", key_style),
                     ]),
+                    Line::from(vec![
+                        Span::raw("  Pan Left/Right: "),
+                        Span::styled("←", key_style),
+                        Span::raw(" / "),
+                        Span::styled("h", key_style),
+                        Span::raw(", "),
+                        Span::styled("→", key_style),
+                        Span::raw(" / "),
+                        Span::styled("l", key_style),
+                        Span::raw(" (Post page only)").dim(),
+                    ]),
                     Line::from(""),
                     // Actions
                     section_title("Actions"),
@@ -786,30 +1490,113 @@ impl<'a> App<'a> {
                         Span::raw("  Go Back: "),
                         Span::styled("<ESC>", key_style),
                     ]),
+                    Line::from(vec![
+                        Span::raw("  Toggle Layout: "),
+                        Span::styled("L", key_style),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Cycle Pane Focus: "),
+                        Span::styled("Tab", key_style),
+                        Span::raw(" / "),
+                        Span::styled("Shift+Tab", key_style),
+                        Span::raw(" (Split layouts only)").dim(),
+                    ]),
                     Line::from(vec![
                         Span::raw("  Add Feed: "),
                         Span::styled("a", key_style),
                         Span::raw(" (Feeds page only)").dim(),
                     ]),
                     Line::from(vec![
-                        Span::raw("  Delete Feed: "),
+                        Span::raw("  Delete Feed/Folder: "),
                         Span::styled("d", key_style),
                         Span::raw(" (Feeds page only)").dim(),
                     ]),
-                    Line::from(""),
-                    // Other
-                    section_title("Other"),
                     Line::from(vec![
-                        Span::raw("  Toggle Help: "),
-                        Span::styled("?", key_style),
+                        Span::raw("  Edit Feed: "),
+                        Span::styled("e", key_style),
+                        Span::raw(" (Feeds page only)").dim(),
                     ]),
-                    Line::from(vec![Span::raw("  Quit: "), Span::styled("q", key_style)]),
-                ];
-
-                let quit_instruction = Line::from(vec![
-                    " Exit Help: ".into(),
-                    Span::styled("<ESC>", key_style),
-                    Span::raw(" / "),
+                    Line::from(vec![
+                        Span::raw("  Move Feed to Folder: "),
+                        Span::styled("m", key_style),
+                        Span::raw(" (Feeds page only)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Collapse/Expand Folder: "),
+                        Span::styled("<Enter>", key_style),
+                        Span::raw(" (Feeds page only)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Feed Info: "),
+                        Span::styled("i", key_style),
+                        Span::raw(" (Feeds page only)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  All Items: "),
+                        Span::styled("A", key_style),
+                        Span::raw(" (Feeds page only)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Cycle Sort Order: "),
+                        Span::styled("o", key_style),
+                        Span::raw(" (Feeds/Items pages)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Toggle Unread Only: "),
+                        Span::styled("u", key_style),
+                        Span::raw(" (Feeds/Items pages)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Toggle Starred Only: "),
+                        Span::styled("S", key_style),
+                        Span::raw(" (Items page only)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Filter Items: "),
+                        Span::styled("F", key_style),
+                        Span::raw(" (Items page only)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Star/Unstar Item: "),
+                        Span::styled("*", key_style),
+                        Span::raw(" (Items/Post pages)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Open in Browser: "),
+                        Span::styled("x", key_style),
+                        Span::raw(" (Post page only)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Link Hints: "),
+                        Span::styled("f", key_style),
+                        Span::raw(" (Post page only)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Distraction-Free Mode: "),
+                        Span::styled("z", key_style),
+                        Span::raw(" (Post page only)").dim(),
+                    ]),
+                    Line::from(vec![
+                        Span::raw("  Line Spacing: "),
+                        Span::styled("+", key_style),
+                        Span::raw(" / "),
+                        Span::styled("-", key_style),
+                        Span::raw(" (Post page only)").dim(),
+                    ]),
+                    Line::from(""),
+                    // Other
+                    section_title("Other"),
+                    Line::from(vec![
+                        Span::raw("  Toggle Help: "),
+                        Span::styled("?", key_style),
+                    ]),
+                    Line::from(vec![Span::raw("  Quit: "), Span::styled("q", key_style)]),
+                ];
+
+                let quit_instruction = Line::from(vec![
+                    " Exit Help: ".into(),
+                    Span::styled("<ESC>", key_style),
+                    Span::raw(" / "),
                     Span::styled("? ", key_style),
                 ]);
 
@@ -892,6 +1679,165 @@ impl<'a> App<'a> {
                     popup_area,
                 );
             }
+            PopupState::FeedInfo { feed, metadata } => {
+                let popup_area = get_centered_popup_area(area, 70, 60);
+                let field = |label: &str, value: String| {
+                    Line::from(vec![format!("{}: ", label).fg(Color::Yellow), value.into()])
+                };
+
+                let mut lines = vec![field("URL", feed.url.clone())];
+                if let Some(link) = &metadata.site_link {
+                    lines.push(Line::from(vec![
+                        "Homepage: ".fg(Color::Yellow),
+                        Span::styled(
+                            link.clone(),
+                            Style::default().underlined().underline_color(Color::Cyan),
+                        ),
+                    ]));
+                }
+                let optional_fields = [
+                    ("Language", metadata.language.clone()),
+                    ("Generator", metadata.generator.clone()),
+                    ("Icon", metadata.icon.clone()),
+                    ("Logo", metadata.logo.clone()),
+                    (
+                        "Authors",
+                        Some(metadata.authors.join(", ")).filter(|a| !a.is_empty()),
+                    ),
+                    (
+                        "Categories",
+                        Some(metadata.categories.join(", ")).filter(|c| !c.is_empty()),
+                    ),
+                    ("Last Updated", metadata.updated.map(format_date)),
+                ];
+                for (label, value) in optional_fields {
+                    if let Some(value) = value {
+                        lines.push(field(label, value));
+                    }
+                }
+                lines.push(field("Unread Items", feed.unread_count.to_string()));
+                if feed.is_gone {
+                    lines.push(field("Status", "Gone (no longer synced)".to_string()));
+                } else if feed.is_paused {
+                    lines.push(field("Status", "Paused".to_string()));
+                }
+                if let Some(description) = &metadata.description {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(description.clone()));
+                }
+
+                let key_style = Style::default().fg(Color::Blue).bold();
+                let instructions = Line::from(vec![
+                    " Open Homepage: ".into(),
+                    Span::styled("x", key_style),
+                    " | ".into(),
+                    " Close: ".into(),
+                    Span::styled("<ESC> ", key_style),
+                ]);
+                let title = format!(" {} ", feed.title.as_deref().unwrap_or("Feed Info"));
+
+                frame.render_widget(Clear, popup_area);
+                frame.render_widget(
+                    Paragraph::new(lines)
+                        .block(
+                            Block::bordered()
+                                .title(title.blue())
+                                .title_bottom(instructions.right_aligned()),
+                        )
+                        .wrap(Wrap { trim: true }),
+                    popup_area,
+                );
+            }
+            PopupState::Filter { input, error } => {
+                let popup_area = get_centered_popup_area(area, 60, 40);
+
+                // Display input with cursor
+                let input_with_cursor = format!("{}█", input);
+
+                let mut lines = vec![
+                    Line::from("Filter items by any of:"),
+                    Line::from(
+                        "unread  starred  author:<name>  category:<name>  since:<YYYY-MM-DD>  \
+                         until:<YYYY-MM-DD>"
+                            .dim(),
+                    ),
+                    Line::from(""),
+                    Line::from(vec![Span::styled(
+                        &input_with_cursor,
+                        Style::default().fg(Color::Yellow),
+                    )]),
+                    Line::from(""),
+                ];
+                match error {
+                    Some(error) => lines.push(Line::from(error.as_str().red())),
+                    None => lines.push(Line::from(
+                        "Press Enter to apply (empty to clear), Esc to cancel.",
+                    )),
+                }
+                lines = pad_top_lines_center(lines, popup_area, true);
+
+                frame.render_widget(Clear, popup_area);
+                frame.render_widget(
+                    Paragraph::new(lines)
+                        .block(Block::bordered().title(" Filter Items ".blue()))
+                        .wrap(Wrap { trim: true }),
+                    popup_area,
+                );
+            }
+            PopupState::LinkHints {
+                references,
+                input,
+                selected,
+            } => {
+                let popup_area = get_centered_popup_area(area, 70, 60);
+                let key_style = Style::default().fg(Color::Blue).bold();
+
+                let list_items: Vec<ListItem> = references
+                    .iter()
+                    .enumerate()
+                    .map(|(i, reference)| ListItem::new(reference_line(i + 1, reference.clone())))
+                    .collect();
+
+                let instructions = Line::from(vec![
+                    " Jump: ".into(),
+                    Span::styled("0-9", key_style),
+                    " | ".into(),
+                    " Open: ".into(),
+                    Span::styled("<Enter>", key_style),
+                    " | ".into(),
+                    " Copy: ".into(),
+                    Span::styled("y", key_style),
+                    " | ".into(),
+                    " Add as Feed: ".into(),
+                    Span::styled("a", key_style),
+                    " | ".into(),
+                    " Close: ".into(),
+                    Span::styled("<ESC> ", key_style),
+                ]);
+
+                let title = if input.is_empty() {
+                    " Links ".to_string()
+                } else {
+                    format!(" Links [{}] ", input)
+                };
+
+                let list = List::new(list_items)
+                    .block(
+                        Block::bordered()
+                            .title(title.blue())
+                            .title_bottom(instructions.right_aligned()),
+                    )
+                    .highlight_style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                    .highlight_symbol(">> ");
+                let mut list_state = ListState::default().with_selected(Some(*selected));
+
+                frame.render_widget(Clear, popup_area);
+                frame.render_stateful_widget(list, popup_area, &mut list_state);
+            }
             PopupState::DeleteFeed { feed_url } => {
                 let popup_area = get_centered_popup_area(area, 60, 30);
                 let mut lines = vec![
@@ -901,61 +1847,356 @@ impl<'a> App<'a> {
                     Line::from("This will also delete all items from this feed."),
                 ];
                 lines = pad_top_lines_center(lines, popup_area, true);
+                render_confirm_delete(frame, popup_area, lines);
+            }
+            PopupState::EditFeed {
+                url,
+                title,
+                is_paused,
+                notify,
+                field,
+                error,
+                ..
+            } => {
+                let popup_area = get_centered_popup_area(area, 60, 40);
+                let label = |name: &'static str, focused: bool| {
+                    if focused {
+                        name.fg(Color::Yellow).bold()
+                    } else {
+                        name.fg(Color::Yellow)
+                    }
+                };
+                let input = |value: &str, focused: bool| {
+                    if focused {
+                        format!("{}█", value).into()
+                    } else {
+                        Span::raw(value.to_string())
+                    }
+                };
 
-                let buttons = Line::from(vec![
-                    " ".into(),
-                    "[".into(),
-                    "Y".bold().red(),
-                    "]".into(),
-                    "es  ".into(),
-                    "[".into(),
-                    "N".bold().blue(),
-                    "]".into(),
-                    "o  ".into(),
-                    "[".into(),
-                    "Esc".bold(),
-                    "]".into(),
-                    " Cancel ".into(),
-                ]);
+                let mut lines = vec![
+                    Line::from(vec![
+                        label("URL: ", *field == EditFeedField::Url),
+                        input(url, *field == EditFeedField::Url),
+                    ]),
+                    Line::from(vec![
+                        label("Title: ", *field == EditFeedField::Title),
+                        if title.is_empty() && *field != EditFeedField::Title {
+                            "(title given by the feed)".dim()
+                        } else {
+                            input(title, *field == EditFeedField::Title)
+                        },
+                    ]),
+                    Line::from(vec![
+                        label("Sync paused: ", *field == EditFeedField::Paused),
+                        if *is_paused { "[x]" } else { "[ ]" }.into(),
+                    ]),
+                    Line::from(vec![
+                        label("Notify: ", *field == EditFeedField::Notify),
+                        if *notify { "[x]" } else { "[ ]" }.into(),
+                    ]),
+                    Line::from(""),
+                ];
+                match error {
+                    Some(error) => lines.push(Line::from(error.as_str().red())),
+                    None => lines.push(Line::from(
+                        "Tab: next field, Space: toggle, Enter: save, Esc: cancel.",
+                    )),
+                }
+                lines = pad_top_lines_center(lines, popup_area, true);
 
                 frame.render_widget(Clear, popup_area);
                 frame.render_widget(
                     Paragraph::new(lines)
-                        .block(
-                            Block::bordered()
-                                .title(" Confirm Delete ".red())
-                                .title_bottom(buttons.centered())
-                                .border_style(Style::default().fg(Color::Red)),
-                        )
-                        .centered()
+                        .block(Block::bordered().title(" Edit Feed ".blue()))
                         .wrap(Wrap { trim: true }),
                     popup_area,
                 );
             }
+            PopupState::DeleteFolder { folder } => {
+                let popup_area = get_centered_popup_area(area, 60, 30);
+                let mut lines = vec![
+                    Line::from("Are you sure you want to delete this folder?"),
+                    Line::from(vec![">> ".into(), folder.name.as_str().fg(Color::Yellow)]),
+                    Line::from(""),
+                    Line::from("Folders in it are deleted too; their feeds are kept."),
+                ];
+                lines = pad_top_lines_center(lines, popup_area, true);
+                render_confirm_delete(frame, popup_area, lines);
+            }
+            PopupState::MoveFeed {
+                feed,
+                input,
+                folders,
+            } => {
+                let popup_area = get_centered_popup_area(area, 60, 50);
+
+                // Display input with cursor
+                let input_with_cursor = format!("{}█", input);
+
+                let mut lines = vec![
+                    Line::from(vec![
+                        "Move ".into(),
+                        feed.title
+                            .as_deref()
+                            .unwrap_or(feed.url.as_str())
+                            .fg(Color::Cyan),
+                        " to folder (e.g. Tech/Rust):".into(),
+                    ]),
+                    Line::from(""),
+                    Line::from(vec![Span::styled(
+                        &input_with_cursor,
+                        Style::default().fg(Color::Yellow),
+                    )]),
+                    Line::from(""),
+                    Line::from("Press Enter to move (empty for no folder), Esc to cancel."),
+                ];
+                if !folders.is_empty() {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from("Folders:".dim()));
+                    lines.extend(folders.iter().map(|f| Line::from(f.as_str().blue())));
+                }
+                lines = pad_top_lines_center(lines, popup_area, true);
+
+                frame.render_widget(Clear, popup_area);
+                frame.render_widget(
+                    Paragraph::new(lines)
+                        .block(Block::bordered().title(" Move Feed ".blue()))
+                        .wrap(Wrap { trim: true }),
+                    popup_area,
+                );
+            }
+        }
+    }
+
+    /// Reload the feeds or items list with the current sort order and filters, keeping the
+    /// selection in range.
+    fn reload_page(&mut self) {
+        self.preview_items = None;
+        match &self.current_page {
+            CurrentScreen::Feeds { .. } => {
+                if let Ok(rows) = load_feed_rows(self.db, &self.feed_query) {
+                    clamp_selection(&mut self.feed_list_state, rows.len());
+                    self.current_page = CurrentScreen::Feeds { rows };
+                }
+            }
+            CurrentScreen::Items { feed, .. } => {
+                let feed = feed.clone();
+                self.refresh_side_feed_rows();
+                if let Ok(items) = self.load_items(&feed) {
+                    clamp_selection(&mut self.item_list_state, items.len());
+                    self.current_page = CurrentScreen::Items { feed, items };
+                }
+            }
+            CurrentScreen::ViewPost { .. } => {}
+        }
+    }
+
+    /// Load the items of a feed, or of every feed for the "All Items" view, with the current
+    /// sort order and filters.
+    fn load_items(&self, feed: &Feed) -> Result<Vec<FeedItem>> {
+        if feed.id == ALL_ITEMS_FEED_ID {
+            self.db.get_all_items_with(&self.item_query)
+        } else {
+            self.db.get_feed_items_with(feed.id, &self.item_query)
+        }
+    }
+
+    /// Open the items page of a feed.
+    fn open_items(&mut self, feed: Feed) {
+        if let Ok(items) = self.load_items(&feed) {
+            self.item_list_state
+                .select(if items.is_empty() { None } else { Some(0) });
+            self.refresh_side_feed_rows();
+            self.current_page = CurrentScreen::Items { feed, items };
+        }
+    }
+
+    /// Reload the feeds shown beside the items list and post, e.g. after unread counts changed.
+    fn refresh_side_feed_rows(&mut self) {
+        if let Ok(rows) = load_feed_rows(self.db, &self.feed_query) {
+            self.side_feed_rows = rows;
+        }
+    }
+
+    /// Star or unstar the highlighted item, or the item of the open post.
+    fn toggle_star(&mut self) {
+        let (items, item_id) = match &mut self.current_page {
+            CurrentScreen::Items { items, .. } => {
+                let Some(item) = self
+                    .item_list_state
+                    .selected()
+                    .and_then(|selected| items.get(selected))
+                else {
+                    return;
+                };
+                let item_id = item.id;
+                (items, item_id)
+            }
+            CurrentScreen::ViewPost { items, item, .. } => {
+                let starred = !item.is_starred;
+                if self.db.set_item_starred(item.id, starred).is_err() {
+                    return;
+                }
+                item.is_starred = starred;
+                if let Some(stored) = items.iter_mut().find(|i| i.id == item.id) {
+                    stored.is_starred = starred;
+                }
+                return;
+            }
+            CurrentScreen::Feeds { .. } => return,
+        };
+
+        if let Some(stored) = items.iter_mut().find(|i| i.id == item_id) {
+            let starred = !stored.is_starred;
+            if self.db.set_item_starred(item_id, starred).is_ok() {
+                stored.is_starred = starred;
+            }
         }
     }
 
-    /// Try to delete the currently selected feed (shows confirmation popup).
+    /// The highlighted row of the feeds page.
+    fn selected_feed_row(&self) -> Option<&FeedRow> {
+        match &self.current_page {
+            CurrentScreen::Feeds { rows } => self
+                .feed_list_state
+                .selected()
+                .and_then(|selected| rows.get(selected)),
+            _ => None,
+        }
+    }
+
+    /// Show the info popup for the highlighted feed.
+    fn open_feed_info(&mut self) {
+        if let Some(feed) = self.selected_feed_row().and_then(FeedRow::feed)
+            && let Ok(metadata) = self.db.get_feed_metadata(feed.id)
+        {
+            self.popup = Some(PopupState::FeedInfo {
+                feed: feed.clone(),
+                metadata: Box::new(metadata),
+            });
+        }
+    }
+
+    /// Try to delete the currently selected feed or folder (shows confirmation popup).
     fn try_delete_feed(&mut self) {
-        if let CurrentScreen::Feeds { feeds } = &self.current_page
-            && let Some(selected) = self.feed_list_state.selected()
-            && let Some(feed) = feeds.get(selected)
+        match self.selected_feed_row() {
+            Some(FeedRow::Feed { feed, .. }) => {
+                self.popup = Some(PopupState::DeleteFeed {
+                    feed_url: feed.url.clone(),
+                });
+            }
+            Some(FeedRow::Folder { folder, .. }) => {
+                self.popup = Some(PopupState::DeleteFolder {
+                    folder: folder.clone(),
+                });
+            }
+            None => {}
+        }
+    }
+
+    /// Show the popup for editing the properties of the highlighted feed.
+    fn open_edit_feed(&mut self) {
+        if let Some(feed) = self.selected_feed_row().and_then(FeedRow::feed)
+            && let Ok(settings) = self.db.get_feed_settings(feed.id)
         {
-            self.popup = Some(PopupState::DeleteFeed {
-                feed_url: feed.url.clone(),
+            self.popup = Some(PopupState::EditFeed {
+                feed_id: feed.id,
+                url: settings.url,
+                title: settings.custom_title.unwrap_or_default(),
+                is_paused: settings.is_paused,
+                notify: settings.notify,
+                field: EditFeedField::Url,
+                error: None,
+            });
+        }
+    }
+
+    /// Save the properties in the edit feed popup, keeping the popup open with the error if
+    /// they can't be saved.
+    fn save_feed_settings(&mut self) {
+        let Some(PopupState::EditFeed {
+            feed_id,
+            url,
+            title,
+            is_paused,
+            notify,
+            error,
+            ..
+        }) = &mut self.popup
+        else {
+            return;
+        };
+        let settings = FeedSettings {
+            url: url.clone(),
+            custom_title: Some(title.clone()),
+            is_paused: *is_paused,
+            notify: *notify,
+        };
+        match self.db.update_feed(*feed_id, &settings) {
+            Ok(()) => {
+                self.popup = None;
+                self.reload_page();
+            }
+            Err(err) => *error = Some(err.to_string()),
+        }
+    }
+
+    /// Show the popup for moving the highlighted feed to another folder.
+    fn open_move_feed(&mut self) {
+        if let Some(feed) = self.selected_feed_row().and_then(FeedRow::feed) {
+            let input = match feed.folder_id {
+                Some(folder_id) => self.db.folder_path(folder_id).unwrap_or_default(),
+                None => String::new(),
+            };
+            let folders = self.db.list_folders().unwrap_or_default();
+            let mut paths: Vec<String> = folders
+                .iter()
+                .filter_map(|f| self.db.folder_path(f.id).ok())
+                .collect();
+            paths.sort_by_key(|p| p.to_lowercase());
+            self.popup = Some(PopupState::MoveFeed {
+                feed: feed.clone(),
+                input,
+                folders: paths,
             });
         }
     }
 
+    /// Move a feed to the folder at a `/`-separated path, creating it if needed, and refresh
+    /// the feed list.
+    fn move_feed(&mut self, feed_id: usize, path: &str) {
+        if let Ok(folder_id) = self.db.create_folder_path(path)
+            && self.db.set_feed_folder(feed_id, folder_id).is_ok()
+        {
+            self.reload_page();
+        }
+    }
+
+    /// Collapse or expand a folder and refresh the feed list.
+    fn collapse_folder(&mut self, folder_id: usize, collapsed: bool) {
+        if self.db.set_folder_collapsed(folder_id, collapsed).is_ok() {
+            self.reload_page();
+        }
+    }
+
+    /// Delete a folder from the database and refresh the feed list.
+    fn delete_folder(&mut self, folder_id: usize) {
+        if self.db.delete_folder(folder_id).is_ok() {
+            self.reload_page();
+        }
+    }
+
     /// Delete a feed from the database and refresh the feed list.
     fn delete_feed(&mut self, url: &str) {
+        self.preview_items = None;
         if self.db.remove_feed(url).is_ok() {
             // Refresh feed list
-            if let Ok(feeds) = self.db.list_feeds() {
+            if let Ok(rows) = load_feed_rows(self.db, &self.feed_query) {
                 // Reset selection based on whether list is empty
-                let selection = if feeds.is_empty() { None } else { Some(0) };
+                let selection = if rows.is_empty() { None } else { Some(0) };
                 self.feed_list_state.select(selection);
-                self.current_page = CurrentScreen::Feeds { feeds };
+                self.current_page = CurrentScreen::Feeds { rows };
             }
         }
     }
@@ -964,6 +2205,7 @@ impl<'a> App<'a> {
     fn add_feed_async(&mut self, url: String) {
         // Clone URL for use in thread
         let url_clone = url.clone();
+        let client = self.client.clone();
 
         // Create a new runtime for this blocking operation
         // This is necessary because we're already inside a Tokio runtime
@@ -972,12 +2214,13 @@ impl<'a> App<'a> {
             let rt = tokio::runtime::Runtime::new().unwrap();
             rt.block_on(async move {
                 // Fetch and parse the feed
-                crate::client::fetch_feed(&url_clone).await
+                client.fetch_feed(&url_clone).await
             })
         })
         .join();
 
         // Process the result
+        #[allow(clippy::collapsible_match)]
         match result {
             Ok(Ok(parsed_feed)) => {
                 // Add to database
@@ -986,18 +2229,19 @@ impl<'a> App<'a> {
                     if let Ok(feeds) = self.db.list_feeds()
                         && let Some(feed) = feeds.iter().find(|f| f.url == url)
                     {
+                        let _ = self.db.update_feed_metadata(
+                            feed.id,
+                            parsed_feed.title.as_deref(),
+                            &parsed_feed.metadata,
+                        );
+
                         // Add all feed items to the database
                         for item in parsed_feed.items {
-                            let authors_refs: Vec<&str> =
-                                item.authors.iter().map(|s| s.as_str()).collect();
-                            let _ = self.db.add_feed_item(
-                                feed.id,
-                                item.title.as_deref(),
-                                item.link.as_deref(),
-                                item.description.as_deref(),
-                                Some(&authors_refs),
-                                item.published,
-                            );
+                            if let Ok(ItemChange::New(item_id)) =
+                                self.db.add_feed_item(feed.id, &item)
+                            {
+                                let _ = self.rules.apply_by_id(self.db, feed, item_id);
+                            }
                         }
                     }
                 }
@@ -1008,21 +2252,36 @@ impl<'a> App<'a> {
         }
 
         // Refresh the feed list
-        if let Ok(feeds) = self.db.list_feeds() {
+        self.preview_items = None;
+        if let Ok(rows) = load_feed_rows(self.db, &self.feed_query) {
             let mut new_list_state = ListState::default();
-            if !feeds.is_empty() {
+            if !rows.is_empty() {
                 new_list_state.select(Some(0));
             }
             self.feed_list_state = new_list_state;
-            self.current_page = CurrentScreen::Feeds { feeds };
+            self.current_page = CurrentScreen::Feeds { rows };
         }
     }
 
     /// Set the running state to false to quit the application.
     fn quit(&mut self) {
+        // Remember where the open post was left off
+        if let CurrentScreen::ViewPost { item, scroll, .. } = &self.current_page {
+            let position = self.post_scroll_position(*scroll);
+            let _ = self.db.set_item_scroll_position(item.id, position);
+        }
         self.running = false
     }
 
+    /// The position to store for the open post scrolled to `scroll` rows.
+    fn post_scroll_position(&self, scroll: u16) -> u16 {
+        match self.post_resume {
+            // Not rendered since it was opened, so still where it was
+            Some(position) => position,
+            None => scroll_fraction(scroll, self.post_line_count),
+        }
+    }
+
     /// Open the currently-visible feed in the browser if viewing a feed.
     fn open_in_browser(&self) {
         if let CurrentScreen::ViewPost { item, .. } = &self.current_page
@@ -1032,4 +2291,233 @@ impl<'a> App<'a> {
             eprintln!("Failed to open link in browser: {}", err);
         }
     }
+
+    /// Download (or load from the cache) the images of a post, if inline images are enabled.
+    fn load_post_images(&mut self, item: &FeedItem) {
+        let Some(images) = &mut self.inline_images else {
+            return;
+        };
+        let sources = item
+            .description
+            .as_deref()
+            .and_then(|desc| rich_text::html_to_rich_text(desc, usize::MAX).ok())
+            .map(|doc| {
+                doc.references
+                    .into_iter()
+                    .filter(|r| r.kind == ReferenceKind::Image)
+                    .filter_map(|r| {
+                        let url = resolve_image_url(&r.url, item.link.as_deref())?;
+                        Some((r.url, url))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        images.load(sources);
+    }
+
+    /// Open the link hint popup for the links and images of the currently-visible post.
+    fn open_link_hints(&mut self) {
+        if let CurrentScreen::ViewPost { item, .. } = &self.current_page
+            && let Some(desc) = &item.description
+            && let Ok(doc) = rich_text::html_to_rich_text(desc, usize::MAX)
+            && !doc.references.is_empty()
+        {
+            // Relative links and image sources point to the site of the post
+            let references = doc
+                .references
+                .into_iter()
+                .map(|mut r| {
+                    if let Some(url) = resolve_image_url(&r.url, item.link.as_deref()) {
+                        r.url = url;
+                    }
+                    r
+                })
+                .collect();
+            self.popup = Some(PopupState::LinkHints {
+                references,
+                input: String::new(),
+                selected: 0,
+            });
+        }
+    }
+}
+
+/// The fraction of a post of `line_count` rows, in [`SCROLL_SCALE`] units, scrolled past at
+/// `row`.
+fn scroll_fraction(row: u16, line_count: usize) -> u16 {
+    if line_count == 0 {
+        return 0;
+    }
+    (row as usize * SCROLL_SCALE / line_count).min(SCROLL_SCALE) as u16
+}
+
+/// The row of a post of `line_count` rows at a fraction in [`SCROLL_SCALE`] units.
+fn scroll_row(fraction: u16, line_count: usize) -> u16 {
+    // Round, so that a row survives the round trip through a fraction
+    ((fraction as usize * line_count + SCROLL_SCALE / 2) / SCROLL_SCALE).min(u16::MAX as usize)
+        as u16
+}
+
+/// Keep a list selection within a list of `len` entries, selecting the first entry if there
+/// was no selection.
+fn clamp_selection(state: &mut ListState, len: usize) {
+    match (state.selected(), len) {
+        (_, 0) => state.select(None),
+        (Some(selected), _) => state.select(Some(selected.min(len - 1))),
+        (None, _) => state.select(Some(0)),
+    }
+}
+
+/// Render a delete confirmation popup with the given message lines.
+fn render_confirm_delete(frame: &mut Frame, popup_area: Rect, lines: Vec<Line>) {
+    let buttons = Line::from(vec![
+        " ".into(),
+        "[".into(),
+        "Y".bold().red(),
+        "]".into(),
+        "es  ".into(),
+        "[".into(),
+        "N".bold().blue(),
+        "]".into(),
+        "o  ".into(),
+        "[".into(),
+        "Esc".bold(),
+        "]".into(),
+        " Cancel ".into(),
+    ]);
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title(" Confirm Delete ".red())
+                    .title_bottom(buttons.centered())
+                    .border_style(Style::default().fg(Color::Red)),
+            )
+            .centered()
+            .wrap(Wrap { trim: true }),
+        popup_area,
+    );
+}
+
+/// Load the rows of the feeds page: the feeds with the given sort order and filters, arranged
+/// under their folders.
+fn load_feed_rows(db: &Db, query: &FeedQuery) -> Result<Vec<FeedRow>> {
+    let folders = db.list_folders()?;
+    let feeds = db.list_feeds_with(query)?;
+    Ok(feed_rows(&folders, &feeds, query.unread_only))
+}
+
+/// Status indicator for the feeds page title listing the sort order and filters.
+fn feeds_status(query: &FeedQuery) -> String {
+    let mut status = format!("[sort: {}", query.sort.label());
+    if query.unread_only {
+        status.push_str(" | unread");
+    }
+    status.push_str("] ");
+    status
+}
+
+/// Status indicator for the items page title listing the sort order and filters.
+fn items_status(query: &ItemQuery) -> String {
+    let mut status = format!("[sort: {}", query.sort.label());
+    if !query.filter.is_empty() {
+        status.push_str(&format!(" | {}", query.filter));
+    }
+    status.push_str("] ");
+    status
+}
+
+/// Build the bordered block for a page. Unfocused pages have dimmed borders and no
+/// instructions.
+fn pane_block<'b>(title: Line<'b>, instructions: Line<'b>, role: PaneRole) -> Block<'b> {
+    let block = Block::bordered().title(title);
+    match role {
+        PaneRole::Focused => block.title_bottom(instructions.right_aligned()),
+        PaneRole::Unfocused | PaneRole::Preview => {
+            block.border_style(Style::default().fg(Color::DarkGray))
+        }
+    }
+}
+
+/// Render an empty preview pane with a hint message.
+fn render_empty_preview(frame: &mut Frame, area: Rect, message: &str) {
+    let lines = pad_top_lines_center(vec![Line::from(message.italic().dim())], area, true);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::bordered().border_style(Style::default().fg(Color::DarkGray)))
+            .centered(),
+        area,
+    );
+}
+
+/// Build the metadata header lines (link, authors, publish date) shown above a post.
+fn post_header_lines(item: &FeedItem) -> Vec<Line<'_>> {
+    let mut lines: Vec<Line> = Vec::new();
+
+    if let Some(link) = &item.link {
+        lines.push(Line::from(vec![
+            "Link: ".fg(Color::Yellow),
+            Span::styled(
+                link.as_str(),
+                Style::default().underlined().underline_color(Color::Cyan),
+            ),
+        ]));
+    }
+
+    if !item.authors.is_empty() {
+        lines.push(Line::from(vec![
+            "Author".fg(Color::Yellow),
+            if item.authors.len() > 1 { "s" } else { "" }.fg(Color::Yellow),
+            ": ".fg(Color::Yellow),
+            item.authors.join(", ").into(),
+        ]));
+    }
+
+    if let Some(published) = item.published {
+        lines.push(Line::from(vec![
+            "Published: ".fg(Color::Yellow),
+            format_date(published).into(),
+        ]));
+    }
+
+    if !item.categories.is_empty() {
+        lines.push(Line::from(vec![
+            "Categories: ".fg(Color::Yellow),
+            item.categories.join(", ").into(),
+        ]));
+    }
+
+    lines
+}
+
+/// Format a numbered reference as a footnote line, e.g. `[1] text <url>`.
+fn reference_line(number: usize, reference: Reference) -> Line<'static> {
+    let mut spans = vec![Span::styled(
+        format!("[{}] ", number),
+        rich_text::reference_marker_style(),
+    )];
+    if reference.kind == ReferenceKind::Image {
+        spans.push("(image) ".dim());
+    }
+    if !reference.text.is_empty() {
+        spans.push(Span::raw(format!("{} ", reference.text)));
+    }
+    spans.push(Span::styled(
+        reference.url,
+        Style::default().underlined().underline_color(Color::Cyan),
+    ));
+    Line::from(spans)
+}
+
+/// Copy text to the system clipboard using the OSC 52 terminal escape sequence, which is
+/// supported by most modern terminal emulators (and works over SSH).
+fn copy_to_clipboard(text: &str) {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout();
+    let _ = write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text));
+    let _ = stdout.flush();
 }
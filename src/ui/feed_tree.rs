@@ -0,0 +1,186 @@
+/*!
+The tree of folders and feeds shown on the Feeds page.
+
+* Folders come before feeds at each level, sorted by name; feeds keep the order of the feeds
+  list (the current sort order).
+* The contents of collapsed folders are left out, but still count towards the unread count
+  shown on the folder.
+*/
+use crate::db::{Feed, Folder};
+
+/// A row of the feeds list.
+#[derive(Debug, Clone)]
+pub enum FeedRow {
+    /// A folder.
+    Folder {
+        folder: Folder,
+        /// Nesting level, 0 for top-level rows.
+        depth: usize,
+        /// Number of unread items of the feeds in the folder and its subfolders.
+        unread_count: usize,
+    },
+    /// A feed.
+    Feed {
+        feed: Feed,
+        /// Nesting level, 0 for top-level rows.
+        depth: usize,
+    },
+}
+
+impl FeedRow {
+    /// The feed of a feed row.
+    pub fn feed(&self) -> Option<&Feed> {
+        match self {
+            FeedRow::Feed { feed, .. } => Some(feed),
+            FeedRow::Folder { .. } => None,
+        }
+    }
+}
+
+/// Arrange feeds under their folders. Feeds in folders that don't exist are shown at the top
+/// level. With `hide_empty`, folders without any of the feeds are left out, for when the feeds
+/// list is filtered.
+pub fn feed_rows(folders: &[Folder], feeds: &[Feed], hide_empty: bool) -> Vec<FeedRow> {
+    let tree = Tree {
+        folders,
+        feeds,
+        hide_empty,
+    };
+    let mut rows = Vec::new();
+    tree.push_contents(None, 0, &mut rows);
+    rows
+}
+
+/// Folders and feeds being arranged into rows.
+struct Tree<'a> {
+    folders: &'a [Folder],
+    feeds: &'a [Feed],
+    hide_empty: bool,
+}
+
+impl Tree<'_> {
+    /// Push the rows of the contents of a folder, or of the top level.
+    fn push_contents(&self, parent_id: Option<usize>, depth: usize, rows: &mut Vec<FeedRow>) {
+        for folder in self.folders.iter().filter(|f| f.parent_id == parent_id) {
+            let (feed_count, unread_count) = self.totals(folder.id);
+            if self.hide_empty && feed_count == 0 {
+                continue;
+            }
+            rows.push(FeedRow::Folder {
+                folder: folder.clone(),
+                depth,
+                unread_count,
+            });
+            if !folder.is_collapsed {
+                self.push_contents(Some(folder.id), depth + 1, rows);
+            }
+        }
+        for feed in self.feeds.iter().filter(|f| self.parent_of(f) == parent_id) {
+            rows.push(FeedRow::Feed {
+                feed: feed.clone(),
+                depth,
+            });
+        }
+    }
+
+    /// The folder a feed is shown in.
+    fn parent_of(&self, feed: &Feed) -> Option<usize> {
+        feed.folder_id
+            .filter(|id| self.folders.iter().any(|f| f.id == *id))
+    }
+
+    /// The number of feeds and unread items in a folder and its subfolders.
+    fn totals(&self, folder_id: usize) -> (usize, usize) {
+        let mut totals = self
+            .feeds
+            .iter()
+            .filter(|f| self.parent_of(f) == Some(folder_id))
+            .fold((0, 0), |(feeds, unread), f| {
+                (feeds + 1, unread + f.unread_count)
+            });
+        for child in self
+            .folders
+            .iter()
+            .filter(|f| f.parent_id == Some(folder_id))
+        {
+            let (feeds, unread) = self.totals(child.id);
+            totals.0 += feeds;
+            totals.1 += unread;
+        }
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folder(id: usize, name: &str, parent_id: Option<usize>, is_collapsed: bool) -> Folder {
+        Folder {
+            id,
+            name: name.to_string(),
+            parent_id,
+            is_collapsed,
+        }
+    }
+
+    fn feed(id: usize, folder_id: Option<usize>, unread_count: usize) -> Feed {
+        Feed {
+            id,
+            url: format!("https://example.com/{}", id),
+            title: None,
+            created_at: 0,
+            unread_count,
+            last_updated: None,
+            folder_id,
+            is_paused: false,
+            is_gone: false,
+        }
+    }
+
+    /// Describe rows as `<depth>:<folder name>(<unread count>)` or `<depth>:<feed ID>`.
+    fn describe(rows: &[FeedRow]) -> Vec<String> {
+        rows.iter()
+            .map(|row| match row {
+                FeedRow::Folder {
+                    folder,
+                    depth,
+                    unread_count,
+                } => format!("{}:{}({})", depth, folder.name, unread_count),
+                FeedRow::Feed { feed, depth } => format!("{}:{}", depth, feed.id),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_feed_rows_nest_folders() {
+        let folders = [
+            folder(1, "Tech", None, false),
+            folder(2, "Rust", Some(1), false),
+            folder(3, "News", None, true),
+            folder(4, "Empty", None, false),
+        ];
+        let feeds = [
+            feed(10, None, 1),
+            feed(11, Some(1), 2),
+            feed(12, Some(2), 3),
+            feed(13, Some(3), 4),
+            feed(14, Some(99), 0),
+        ];
+
+        assert_eq!(
+            describe(&feed_rows(&folders, &feeds, false)),
+            vec![
+                "0:Tech(5)",
+                "1:Rust(3)",
+                "2:12",
+                "1:11",
+                "0:News(4)",
+                "0:Empty(0)",
+                "0:10",
+                "0:14",
+            ]
+        );
+        assert!(!describe(&feed_rows(&folders, &feeds, true)).contains(&"0:Empty(0)".to_string()));
+    }
+}
@@ -0,0 +1,185 @@
+/*!
+Image pipeline for rendering images inline in the post view.
+
+* Images are downloaded once and stored in an on-disk cache keyed by a hash of their URL.
+* Rendering uses `ratatui-image`, which picks the sixel, kitty or iTerm2 graphics protocol
+  when the terminal supports one and falls back to half-block Unicode rendering otherwise.
+*/
+use anyhow::{Context, Result};
+use image::{DynamicImage, ImageReader};
+use ratatui_image::{picker::Picker, protocol::StatefulProtocol};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use tokio::task::JoinSet;
+
+use crate::{client::FeedClient, dedup::fnv1a_hash};
+
+/// An on-disk cache of downloaded images.
+#[derive(Debug, Clone)]
+pub struct ImageCache {
+    /// Directory holding the cached image files.
+    dir: PathBuf,
+}
+
+impl ImageCache {
+    /// Create a new image cache in the given directory. The directory is created lazily.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The path in the cache for an image URL.
+    pub fn path_for(&self, url: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}", fnv1a_hash(url.as_bytes())))
+    }
+
+    /// Return the cached image for a URL, downloading it into the cache if needed.
+    pub async fn fetch(&self, client: &FeedClient, url: &str) -> Result<DynamicImage> {
+        let path = self.path_for(url);
+        if !path.exists() {
+            let bytes = client
+                .get_bytes(url)
+                .await
+                .with_context(|| format!("failed to fetch image from {}", url))?;
+            fs::create_dir_all(&self.dir)?;
+            fs::write(&path, &bytes)
+                .with_context(|| format!("failed to cache image at {}", path.display()))?;
+        }
+        decode_image(&path)
+    }
+}
+
+/// Decode an image file, guessing the format from its contents.
+fn decode_image(path: &Path) -> Result<DynamicImage> {
+    ImageReader::open(path)?
+        .with_guessed_format()?
+        .decode()
+        .with_context(|| format!("failed to decode image at {}", path.display()))
+}
+
+/// Resolve a possibly-relative image `src` against the link of the post it appears in.
+pub fn resolve_image_url(src: &str, base: Option<&str>) -> Option<String> {
+    match url::Url::parse(src) {
+        Ok(url) => Some(url.to_string()),
+        Err(url::ParseError::RelativeUrlWithoutBase) => base
+            .and_then(|b| url::Url::parse(b).ok())
+            .and_then(|b| b.join(src).ok())
+            .map(|u| u.to_string()),
+        Err(_) => None,
+    }
+}
+
+/// Inline image state for the post view.
+pub struct InlineImages {
+    /// Picks the graphics protocol supported by the terminal.
+    picker: Picker,
+    /// On-disk cache of downloaded images.
+    cache: ImageCache,
+    /// Client to download images with.
+    client: FeedClient,
+    /// Maximum number of terminal rows an image may take up.
+    pub max_height: u16,
+    /// Images of the current post ready to render, keyed by their `src` in the post HTML.
+    pub loaded: HashMap<String, StatefulProtocol>,
+    /// Images of the current post still being downloaded, by their `src` in the post HTML.
+    pub pending: HashSet<String>,
+    /// Downloads of the current post's images. Replacing it aborts those of the previous post.
+    downloads: JoinSet<(String, Result<DynamicImage>)>,
+}
+
+impl InlineImages {
+    /// Create the inline image state. Must be called after entering the alternate screen but
+    /// before reading terminal events, since it queries the terminal for graphics support.
+    pub fn new(cache: ImageCache, client: FeedClient, max_height: u16) -> Self {
+        let picker = Picker::from_query_stdio().unwrap_or_else(|_| Picker::from_fontsize((8, 16)));
+        Self {
+            picker,
+            cache,
+            client,
+            max_height,
+            loaded: HashMap::new(),
+            pending: HashSet::new(),
+            downloads: JoinSet::new(),
+        }
+    }
+
+    /// Start downloading (or loading from cache) the images of a post in the background,
+    /// given as `(src, resolved URL)` pairs, replacing the images of the previously loaded post.
+    ///
+    /// Images show up in [`Self::loaded`] as [`Self::poll`] picks them up. Images that fail to
+    /// download or decode are left out so that the post view can fall back to their alt text.
+    pub fn load(&mut self, sources: Vec<(String, String)>) {
+        self.loaded.clear();
+        self.pending.clear();
+        self.downloads = JoinSet::new();
+        for (src, url) in sources {
+            if !self.pending.insert(src.clone()) {
+                continue;
+            }
+            let cache = self.cache.clone();
+            let client = self.client.clone();
+            self.downloads
+                .spawn(async move { (src, cache.fetch(&client, &url).await) });
+        }
+    }
+
+    /// Pick up the images that finished downloading. Returns whether any did.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Some(result) = self.downloads.try_join_next() {
+            changed = true;
+            let Ok((src, image)) = result else {
+                continue;
+            };
+            self.pending.remove(&src);
+            if let Ok(image) = image {
+                self.loaded
+                    .insert(src, self.picker.new_resize_protocol(image));
+            }
+        }
+        // A panicked download can't say which image it was for
+        if self.downloads.is_empty() {
+            self.pending.clear();
+        }
+        changed
+    }
+
+    /// Whether images of the current post are still being downloaded.
+    pub fn is_loading(&self) -> bool {
+        !self.downloads.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_relative_image_url() {
+        assert_eq!(
+            resolve_image_url("/img/a.png", Some("https://example.com/posts/1")),
+            Some("https://example.com/img/a.png".to_string())
+        );
+        assert_eq!(
+            resolve_image_url("https://cdn.example.com/b.png", None),
+            Some("https://cdn.example.com/b.png".to_string())
+        );
+        assert_eq!(resolve_image_url("a.png", None), None);
+    }
+
+    #[test]
+    fn test_cache_path_is_stable() {
+        let cache = ImageCache::new("/tmp/ferrofeed-test");
+        assert_eq!(
+            cache.path_for("https://example.com/a.png"),
+            cache.path_for("https://example.com/a.png")
+        );
+        assert_ne!(
+            cache.path_for("https://example.com/a.png"),
+            cache.path_for("https://example.com/b.png")
+        );
+    }
+}
@@ -6,6 +6,12 @@ use ratatui::{
     text::Line,
 };
 
+use crate::{
+    client::FeedMetadata,
+    db::{Feed, Folder},
+    ui::rich_text::Reference,
+};
+
 /// Represents potential popups
 #[derive(Debug, Clone)]
 pub enum PopupState {
@@ -15,6 +21,80 @@ pub enum PopupState {
     AddFeed { input: String },
     /// Confirmation popup for deleting a feed.
     DeleteFeed { feed_url: String },
+    /// Input popup for moving a feed to a folder.
+    MoveFeed {
+        feed: Feed,
+        /// `/`-separated path of the destination folder.
+        input: String,
+        /// Paths of the existing folders.
+        folders: Vec<String>,
+    },
+    /// Form popup for editing the properties of a feed.
+    EditFeed {
+        feed_id: usize,
+        url: String,
+        /// Custom display title, empty for the title given by the feed.
+        title: String,
+        is_paused: bool,
+        /// Whether to show desktop notifications for new items.
+        notify: bool,
+        /// The focused field.
+        field: EditFeedField,
+        /// Error from saving the last submitted properties.
+        error: Option<String>,
+    },
+    /// Confirmation popup for deleting a folder.
+    DeleteFolder { folder: Folder },
+    /// Information about a feed and its site.
+    FeedInfo {
+        feed: Feed,
+        metadata: Box<FeedMetadata>,
+    },
+    /// Input popup for filtering the items list.
+    Filter {
+        input: String,
+        /// Error from parsing the last submitted filter.
+        error: Option<String>,
+    },
+    /// Link hint mode listing the numbered links/images of the current post.
+    LinkHints {
+        references: Vec<Reference>,
+        /// Digits typed so far to jump to a reference number.
+        input: String,
+        /// Index of the highlighted reference.
+        selected: usize,
+    },
+}
+
+/// A field of the [`PopupState::EditFeed`] form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditFeedField {
+    Url,
+    Title,
+    Paused,
+    Notify,
+}
+
+impl EditFeedField {
+    /// The next field, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            EditFeedField::Url => EditFeedField::Title,
+            EditFeedField::Title => EditFeedField::Paused,
+            EditFeedField::Paused => EditFeedField::Notify,
+            EditFeedField::Notify => EditFeedField::Url,
+        }
+    }
+
+    /// The previous field, wrapping around.
+    pub fn prev(self) -> Self {
+        match self {
+            EditFeedField::Url => EditFeedField::Notify,
+            EditFeedField::Title => EditFeedField::Url,
+            EditFeedField::Paused => EditFeedField::Title,
+            EditFeedField::Notify => EditFeedField::Paused,
+        }
+    }
 }
 
 /// Helper function to create a centered rect using up certain percentage of the available rect
@@ -8,28 +8,486 @@ use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span},
 };
+use regex::Regex;
+use std::sync::OnceLock;
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::SyntaxSet,
+};
+use unicode_width::UnicodeWidthChar;
+
+/// A line of text as rendered by `html2text`.
+type RichLine = TaggedLine<Vec<RichAnnotation>>;
+
+/// The narrowest width text is rendered at, below which `html2text` can't lay out tables.
+const MIN_RENDER_WIDTH: usize = 20;
+
+/// The `syntect` theme used to highlight code blocks.
+const CODE_THEME: &str = "base16-ocean.dark";
+
+/// The kind of a [`Reference`] collected from rendered HTML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// A hyperlink (`<a href>`).
+    Link,
+    /// An image source (`<img src>`).
+    Image,
+}
+
+/// A footnote-style reference to a link or image found in rendered HTML.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// Whether the reference is a link or an image.
+    pub kind: ReferenceKind,
+    /// The target URL of the link or the image source.
+    pub url: String,
+    /// The anchor text of the link or the alt text of the image.
+    pub text: String,
+    /// Index of the line where the reference (first) ends.
+    pub line: usize,
+}
+
+/// Rendered rich text along with the numbered references it contains.
+#[derive(Debug, Clone, Default)]
+pub struct RichDocument {
+    /// The styled lines of text, with `[n]` markers after each link/image.
+    pub lines: Vec<Line<'static>>,
+    /// The collected references; reference `n` in the text is at index `n - 1`.
+    pub references: Vec<Reference>,
+    /// Whether each line in `lines` is part of a preformatted block.
+    pub preformatted: Vec<bool>,
+}
 
 /// Adapter for `html2text` to `ratatui` rich text.
 ///
 /// This is a simple adapter to convert the rich annotations from `html2text` to
-/// `ratatui` rich text.
-pub fn html_to_rich_text(html: &str) -> Result<Vec<Line<'_>>> {
+/// `ratatui` rich text. Every link and image is also collected into a numbered
+/// footnote-style [`Reference`], and its position in the text is marked with `[n]`.
+/// Repeated links to the same target share a single reference number.
+///
+/// Text is wrapped to `width` columns, except for preformatted blocks which keep their
+/// whitespace and are never wrapped. Headings, blockquotes, list markers and table borders
+/// are styled, and code blocks with a `language-*` class are syntax highlighted.
+pub fn html_to_rich_text(html: &str, width: usize) -> Result<RichDocument> {
     let html = decode_html_entities(html);
-    let tagged_lines = html2text::from_read_rich(html.as_bytes(), usize::MAX)
-        .context("failed to get html2text RichAnnotations")?;
-    Ok(tagged_lines.into_iter().map(tagged_line_to_line).collect())
-}
+    let mut tagged_lines = render_tagged_lines(&html, width.max(MIN_RENDER_WIDTH))?;
+    if tagged_lines.iter().any(is_preformatted) {
+        // Swap in the preformatted blocks from an unwrapped rendering
+        let unwrapped = render_tagged_lines(&html, usize::MAX)?;
+        tagged_lines = unwrap_preformatted_blocks(tagged_lines, unwrapped);
+    }
+    let languages = code_block_languages(&html);
+
+    let mut references: Vec<Reference> = Vec::new();
+    let mut lines: Vec<Vec<Span<'static>>> = Vec::new();
+    // The code block index of each line, or `None` if the line isn't preformatted
+    let mut line_blocks: Vec<Option<usize>> = Vec::new();
+    let mut block_count = 0;
+    // The reference currently being rendered and the (line, span) position just past its
+    // last span, which is where its marker is inserted once the reference ends.
+    let mut open: Option<(Reference, usize, usize)> = None;
 
-/// Convert a [`TaggedLine`] to a [`Line`].
-fn tagged_line_to_line(tagged_line: TaggedLine<Vec<RichAnnotation>>) -> Line<'static> {
-    let spans: Vec<Span> = tagged_line
-        .tagged_strings()
-        .map(|tagged_str| {
+    for tagged_line in tagged_lines {
+        if is_preformatted(&tagged_line) {
+            if !matches!(line_blocks.last(), Some(Some(_))) {
+                block_count += 1;
+            }
+            line_blocks.push(Some(block_count - 1));
+        } else {
+            line_blocks.push(None);
+        }
+
+        let mut spans = Vec::new();
+        for tagged_str in tagged_line.tagged_strings() {
+            let reference = annotations_to_reference(&tagged_str.tag, &tagged_str.s);
+            match (&mut open, reference) {
+                (Some((current, line_idx, span_idx)), Some(next))
+                    if current.kind == next.kind && current.url == next.url =>
+                {
+                    current.text.push_str(&next.text);
+                    *line_idx = lines.len();
+                    *span_idx = spans.len() + 1;
+                }
+                (_, next) => {
+                    if let Some(closed) = open.take() {
+                        close_reference(closed, &mut references, &mut lines, &mut spans);
+                    }
+                    open = next.map(|r| (r, lines.len(), spans.len() + 1));
+                }
+            }
             let style = annotations_to_style(&tagged_str.tag);
-            Span::styled(tagged_str.s.to_string(), style)
+            spans.push(Span::styled(tagged_str.s.to_string(), style));
+        }
+        lines.push(spans);
+    }
+    if let Some(closed) = open.take() {
+        let mut trailing = Vec::new();
+        close_reference(closed, &mut references, &mut lines, &mut trailing);
+    }
+
+    let preformatted = line_blocks.iter().map(Option::is_some).collect();
+
+    // Style block-level elements and highlight code blocks
+    let mut highlighter: Option<(usize, HighlightLines)> = None;
+    let lines = lines
+        .into_iter()
+        .zip(line_blocks)
+        .map(|(spans, block)| match block {
+            Some(block) => {
+                if highlighter.as_ref().is_none_or(|(b, _)| *b != block) {
+                    highlighter = languages
+                        .get(block)
+                        .and_then(|lang| lang.as_deref())
+                        .and_then(|lang| syntax_set().find_syntax_by_token(lang))
+                        .map(|syntax| (block, HighlightLines::new(syntax, code_theme())));
+                }
+                match &mut highlighter {
+                    Some((_, h)) => Line::from(highlight_code_line(h, spans)),
+                    None => Line::from(spans),
+                }
+            }
+            None => Line::from(decorate_line(spans)),
         })
         .collect();
-    Line::from(spans)
+
+    Ok(RichDocument {
+        lines,
+        references,
+        preformatted,
+    })
+}
+
+/// Render HTML to `html2text` tagged lines at the given width.
+fn render_tagged_lines(html: &str, width: usize) -> Result<Vec<RichLine>> {
+    html2text::config::rich()
+        .allow_width_overflow()
+        .lines_from_read(html.as_bytes(), width)
+        .context("failed to get html2text RichAnnotations")
+}
+
+/// Whether a line is part of a preformatted (`<pre>`) block.
+fn is_preformatted(line: &RichLine) -> bool {
+    line.tagged_strings().any(|ts| {
+        ts.tag
+            .iter()
+            .any(|ann| matches!(ann, RichAnnotation::Preformat(_)))
+    })
+}
+
+/// Split lines into alternating runs of non-preformatted and preformatted lines.
+fn split_preformatted_runs(lines: Vec<RichLine>) -> Vec<(bool, Vec<RichLine>)> {
+    let mut runs: Vec<(bool, Vec<RichLine>)> = Vec::new();
+    for line in lines {
+        let pre = is_preformatted(&line);
+        match runs.last_mut() {
+            Some((run_pre, run)) if *run_pre == pre => run.push(line),
+            _ => runs.push((pre, vec![line])),
+        }
+    }
+    runs
+}
+
+/// Replace the (wrapped) preformatted blocks of `wrapped` with the same blocks from
+/// `unwrapped`, so that code keeps its original line structure.
+///
+/// If the two renderings don't have the same number of blocks, `wrapped` is returned as-is.
+fn unwrap_preformatted_blocks(wrapped: Vec<RichLine>, unwrapped: Vec<RichLine>) -> Vec<RichLine> {
+    let mut unwrapped_blocks: Vec<Vec<RichLine>> = split_preformatted_runs(unwrapped)
+        .into_iter()
+        .filter_map(|(pre, run)| pre.then_some(run))
+        .collect();
+    let wrapped_runs = split_preformatted_runs(wrapped);
+    let wrapped_block_count = wrapped_runs.iter().filter(|(pre, _)| *pre).count();
+    if wrapped_block_count != unwrapped_blocks.len() {
+        return wrapped_runs.into_iter().flat_map(|(_, run)| run).collect();
+    }
+
+    unwrapped_blocks.reverse();
+    wrapped_runs
+        .into_iter()
+        .flat_map(|(pre, run)| match pre {
+            true => unwrapped_blocks.pop().unwrap_or(run),
+            false => run,
+        })
+        .collect()
+}
+
+/// Find the language of each `<pre>` block in the HTML, in document order, from a
+/// `language-*` or `lang-*` class on the `<pre>` element or its inner `<code>` element.
+fn code_block_languages(html: &str) -> Vec<Option<String>> {
+    static PRE_RE: OnceLock<Regex> = OnceLock::new();
+    static LANG_RE: OnceLock<Regex> = OnceLock::new();
+    let pre_re = PRE_RE.get_or_init(|| {
+        Regex::new(r"(?is)<pre\b([^>]*)>\s*(?:<code\b([^>]*)>)?").expect("valid regex")
+    });
+    let lang_re = LANG_RE.get_or_init(|| {
+        Regex::new(r#"(?i)class\s*=\s*["'][^"']*\b(?:language|lang)-([\w+#-]+)"#)
+            .expect("valid regex")
+    });
+
+    pre_re
+        .captures_iter(html)
+        .map(|caps| {
+            [caps.get(1), caps.get(2)]
+                .into_iter()
+                .flatten()
+                .find_map(|attrs| lang_re.captures(attrs.as_str()))
+                .map(|c| c[1].to_lowercase())
+        })
+        .collect()
+}
+
+/// The default `syntect` syntax definitions, loaded once.
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// The `syntect` theme for code blocks, loaded once.
+fn code_theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        ThemeSet::load_defaults()
+            .themes
+            .remove(CODE_THEME)
+            .expect("default syntect theme is missing")
+    })
+}
+
+/// Syntax highlight a single line of a code block. Falls back to the unhighlighted spans if
+/// highlighting fails.
+fn highlight_code_line(
+    highlighter: &mut HighlightLines,
+    spans: Vec<Span<'static>>,
+) -> Vec<Span<'static>> {
+    let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+    let text = format!("{}\n", text);
+    match highlighter.highlight_line(&text, syntax_set()) {
+        Ok(ranges) => ranges
+            .into_iter()
+            .map(|(style, s)| {
+                let fg = style.foreground;
+                Span::styled(
+                    s.trim_end_matches('\n').to_string(),
+                    Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b)),
+                )
+            })
+            .filter(|span| !span.content.is_empty())
+            .collect(),
+        Err(_) => spans,
+    }
+}
+
+/// Style the block-level markup `html2text` renders as line prefixes: headings (`# `),
+/// blockquotes (`> `), list markers (`* `, `1. `) and table borders.
+fn decorate_line(spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+    static HEADING_RE: OnceLock<Regex> = OnceLock::new();
+    static LIST_RE: OnceLock<Regex> = OnceLock::new();
+    let heading_re = HEADING_RE.get_or_init(|| Regex::new(r"^(#{1,6}) ").expect("valid regex"));
+    let list_re = LIST_RE.get_or_init(|| Regex::new(r"^(\s*)(\* |\d+\. )").expect("valid regex"));
+
+    let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+
+    if let Some(caps) = heading_re.captures(&text) {
+        let level = caps[1].len();
+        let (marker, rest) = split_spans_at(spans, level + 1);
+        let style = match level {
+            1 => Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            2 => Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            _ => Style::default().add_modifier(Modifier::BOLD),
+        };
+        let mut out = vec![Span::styled(marker, Style::default().fg(Color::DarkGray))];
+        out.extend(rest.into_iter().map(|s| s.patch_style(style)));
+        return out;
+    }
+
+    let mut out = Vec::new();
+    let mut spans = spans;
+    let mut text = text.as_str();
+
+    // Blockquotes, possibly nested
+    let quote_depth = text
+        .as_bytes()
+        .chunks(2)
+        .take_while(|chunk| *chunk == b"> ")
+        .count();
+    if quote_depth > 0 {
+        let (_, rest) = split_spans_at(spans, quote_depth * 2);
+        out.push(Span::styled(
+            "▎ ".repeat(quote_depth),
+            Style::default().fg(Color::DarkGray),
+        ));
+        spans = rest
+            .into_iter()
+            .map(|s| s.patch_style(Style::default().add_modifier(Modifier::ITALIC)))
+            .collect();
+        text = &text[quote_depth * 2..];
+    }
+
+    // List markers
+    if let Some(caps) = list_re.captures(text) {
+        let indent = caps[1].to_string();
+        let marker = match &caps[2] {
+            "* " => "• ".to_string(),
+            numbered => numbered.to_string(),
+        };
+        let (_, rest) = split_spans_at(spans, caps[0].len());
+        out.push(Span::raw(indent));
+        out.push(Span::styled(marker, Style::default().fg(Color::Yellow)));
+        spans = rest;
+    }
+
+    // Table borders
+    for span in spans {
+        if span.content.chars().any(is_box_drawing) {
+            out.extend(split_box_drawing(span));
+        } else {
+            out.push(span);
+        }
+    }
+    out
+}
+
+/// Whether a character is a Unicode box-drawing character, as used for table borders.
+fn is_box_drawing(c: char) -> bool {
+    ('\u{2500}'..='\u{257F}').contains(&c)
+}
+
+/// Split a span into runs of box-drawing characters, which are dimmed, and other text.
+fn split_box_drawing(span: Span<'static>) -> Vec<Span<'static>> {
+    let border_style = span.style.fg(Color::DarkGray);
+    let mut out: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_is_border = false;
+    for c in span.content.chars() {
+        if is_box_drawing(c) != run_is_border && !run.is_empty() {
+            let style = if run_is_border {
+                border_style
+            } else {
+                span.style
+            };
+            out.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_is_border = is_box_drawing(c);
+        run.push(c);
+    }
+    if !run.is_empty() {
+        let style = if run_is_border {
+            border_style
+        } else {
+            span.style
+        };
+        out.push(Span::styled(run, style));
+    }
+    out
+}
+
+/// Split spans after the first `n` bytes of their combined text, returning the text of the
+/// prefix and the remaining spans. `n` must fall on a character boundary.
+fn split_spans_at(spans: Vec<Span<'static>>, mut n: usize) -> (String, Vec<Span<'static>>) {
+    let mut prefix = String::new();
+    let mut rest = Vec::new();
+    for span in spans {
+        if n == 0 {
+            rest.push(span);
+        } else if span.content.len() <= n {
+            n -= span.content.len();
+            prefix.push_str(&span.content);
+        } else {
+            let (head, tail) = span.content.split_at(n);
+            prefix.push_str(head);
+            rest.push(Span::styled(tail.to_string(), span.style));
+            n = 0;
+        }
+    }
+    (prefix, rest)
+}
+
+/// Wrap a line to `width` columns, breaking between characters.
+///
+/// Used for lines that are not rendered by `html2text`, such as the post metadata header.
+pub fn wrap_line(line: Line<'_>, width: usize) -> Vec<Line<'_>> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut current: Vec<Span> = Vec::new();
+    let mut current_width = 0;
+    for span in line.spans {
+        let mut chunk = String::new();
+        for c in span.content.chars() {
+            let w = c.width().unwrap_or(0);
+            if current_width + w > width && current_width > 0 {
+                if !chunk.is_empty() {
+                    current.push(Span::styled(std::mem::take(&mut chunk), span.style));
+                }
+                lines.push(Line::from(std::mem::take(&mut current)));
+                current_width = 0;
+            }
+            chunk.push(c);
+            current_width += w;
+        }
+        if !chunk.is_empty() {
+            current.push(Span::styled(chunk, span.style));
+        }
+    }
+    lines.push(Line::from(current));
+    lines
+}
+
+/// Register a finished reference and insert its `[n]` marker after its last span.
+///
+/// `current_line` holds the spans of the line still being built, which has not yet been
+/// pushed to `lines`.
+fn close_reference(
+    (reference, line_idx, span_idx): (Reference, usize, usize),
+    references: &mut Vec<Reference>,
+    lines: &mut [Vec<Span<'static>>],
+    current_line: &mut Vec<Span<'static>>,
+) {
+    let number = match references
+        .iter()
+        .position(|r| r.kind == reference.kind && r.url == reference.url)
+    {
+        Some(idx) => idx + 1,
+        None => {
+            let mut reference = reference;
+            reference.text = reference.text.trim().to_string();
+            reference.line = line_idx;
+            references.push(reference);
+            references.len()
+        }
+    };
+    let marker = Span::styled(format!("[{}]", number), reference_marker_style());
+    let target = lines.get_mut(line_idx).unwrap_or(current_line);
+    target.insert(span_idx.min(target.len()), marker);
+}
+
+/// Style for the `[n]` markers placed after links and images.
+pub fn reference_marker_style() -> Style {
+    Style::default().fg(Color::Cyan).add_modifier(Modifier::DIM)
+}
+
+/// Extract the link or image target, if any, from a set of annotations.
+fn annotations_to_reference(annotations: &[RichAnnotation], text: &str) -> Option<Reference> {
+    annotations.iter().rev().find_map(|ann| match ann {
+        RichAnnotation::Link(url) => Some(Reference {
+            kind: ReferenceKind::Link,
+            url: url.clone(),
+            text: text.to_string(),
+            line: 0,
+        }),
+        RichAnnotation::Image(src) => Some(Reference {
+            kind: ReferenceKind::Image,
+            url: src.clone(),
+            text: text.to_string(),
+            line: 0,
+        }),
+        _ => None,
+    })
 }
 
 /// Convert and combine a slice of [`RichAnnotation`] to a [`Style`].
@@ -63,7 +521,7 @@ mod tests {
             "Hello",
             Style::default().add_modifier(Modifier::BOLD),
         )])];
-        assert_eq!(html_to_rich_text(html).unwrap(), expected);
+        assert_eq!(html_to_rich_text(html, 80).unwrap().lines, expected);
     }
 
     #[test]
@@ -73,17 +531,131 @@ mod tests {
             "Hello",
             Style::default().add_modifier(Modifier::ITALIC),
         )])];
-        assert_eq!(html_to_rich_text(html).unwrap(), expected);
+        assert_eq!(html_to_rich_text(html, 80).unwrap().lines, expected);
     }
 
     #[test]
     fn test_double_encoded_html() {
         let double_encoded = r#"&lt;p&gt;This is &lt;strong&gt;bold&lt;/strong&gt; text&lt;/p&gt;"#;
-        let lines = html_to_rich_text(double_encoded).unwrap();
+        let lines = html_to_rich_text(double_encoded, 80).unwrap().lines;
         assert!(!lines.is_empty());
 
         // The text should not contain &lt; or &gt;
         let text = format!("{:?}", lines);
         assert!(!text.contains("&lt;"));
     }
+
+    #[test]
+    fn test_links_are_numbered_references() {
+        let html = r#"<p>See <a href="https://a.example">this</a> and
+            <a href="https://b.example">that</a>, or <a href="https://a.example">this again</a>.</p>"#;
+        let doc = html_to_rich_text(html, 80).unwrap();
+
+        let urls: Vec<&str> = doc.references.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://a.example", "https://b.example"]);
+        assert_eq!(doc.references[0].text, "this");
+
+        let text: String = doc
+            .lines
+            .iter()
+            .flat_map(|l| l.spans.iter().map(|s| s.content.to_string()))
+            .collect();
+        assert!(text.contains("this[1]"));
+        assert!(text.contains("that[2]"));
+        assert!(text.contains("this again[1]"));
+    }
+
+    #[test]
+    fn test_images_are_collected_as_references() {
+        let html = r#"<p><img src="https://example.com/diagram.png" alt="Diagram"></p>"#;
+        let doc = html_to_rich_text(html, 80).unwrap();
+        assert_eq!(
+            doc.references,
+            vec![Reference {
+                kind: ReferenceKind::Image,
+                url: "https://example.com/diagram.png".to_string(),
+                text: "Diagram".to_string(),
+                line: 0,
+            }]
+        );
+    }
+
+    /// Concatenate the text of a line's spans.
+    fn line_text(line: &Line) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_text_is_wrapped_to_width() {
+        let html = "<p>The quick brown fox jumps over the lazy dog, again and again.</p>";
+        let doc = html_to_rich_text(html, 20).unwrap();
+        assert!(doc.lines.len() > 1);
+        assert!(doc.lines.iter().all(|l| l.width() <= 20));
+    }
+
+    #[test]
+    fn test_preformatted_blocks_are_not_wrapped() {
+        let html = "<pre><code>let x = some_function_with_a_very_long_name(argument_one);\n    x\n</code></pre>";
+        let doc = html_to_rich_text(html, 20).unwrap();
+        let texts: Vec<String> = doc.lines.iter().map(line_text).collect();
+        assert!(
+            texts.contains(
+                &"let x = some_function_with_a_very_long_name(argument_one);".to_string()
+            )
+        );
+        assert!(texts.contains(&"    x".to_string()));
+    }
+
+    #[test]
+    fn test_code_block_languages() {
+        let html = r#"<pre><code class="language-rust">fn main() {}</code></pre>
+            <pre>plain</pre>
+            <pre class="lang-python"><code>pass</code></pre>"#;
+        assert_eq!(
+            code_block_languages(html),
+            vec![Some("rust".to_string()), None, Some("python".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_code_block_is_highlighted() {
+        let html = r#"<pre><code class="language-rust">fn main() {}</code></pre>"#;
+        let doc = html_to_rich_text(html, 80).unwrap();
+        let line = doc
+            .lines
+            .iter()
+            .find(|l| line_text(l) == "fn main() {}")
+            .unwrap();
+        assert!(line.spans.len() > 1);
+        assert!(
+            line.spans
+                .iter()
+                .all(|s| matches!(s.style.fg, Some(Color::Rgb(..))))
+        );
+    }
+
+    #[test]
+    fn test_block_elements_are_decorated() {
+        let html = "<h2>Heading</h2><blockquote><p>Quoted</p></blockquote><ul><li>Item</li></ul>";
+        let doc = html_to_rich_text(html, 80).unwrap();
+        let texts: Vec<String> = doc.lines.iter().map(line_text).collect();
+        assert!(texts.contains(&"## Heading".to_string()));
+        assert!(texts.contains(&"▎ Quoted".to_string()));
+        assert!(texts.contains(&"• Item".to_string()));
+
+        let heading = doc
+            .lines
+            .iter()
+            .find(|l| line_text(l) == "## Heading")
+            .unwrap();
+        assert_eq!(heading.spans[1].style.fg, Some(Color::Cyan));
+    }
+
+    #[test]
+    fn test_wrap_line() {
+        let line = Line::from(vec![Span::raw("Link: "), Span::raw("https://example.com")]);
+        let wrapped = wrap_line(line, 10);
+        let texts: Vec<String> = wrapped.iter().map(line_text).collect();
+        assert_eq!(texts, vec!["Link: http", "s://exampl", "e.com"]);
+    }
 }
@@ -1,8 +1,17 @@
 //! Integration tests for CLI commands.
 
 use assert_cmd::Command;
+use std::path::{Path, PathBuf};
 
-use ferrofeed::{commands, db::Db};
+use ferrofeed::{
+    client::{FeedClient, ParsedFeedItem},
+    commands,
+    config::{HttpConfig, SyncConfig},
+    db::Db,
+    hooks::HookSet,
+    output::OutputFormat,
+    rules::RuleSet,
+};
 
 /// Create a test database. ":memory:" is used for in-memory database that is volatile and
 /// will be deleted when the process exits.
@@ -23,6 +32,40 @@ fn run_ferrofeed_command(args: &[&str]) -> assert_cmd::assert::Assert {
     cmd.assert()
 }
 
+/// Create a temporary home directory with a database and a config file using it, so that
+/// commands run in it don't read or change any files of the user. Returns the directory and
+/// the database.
+fn create_test_home(name: &str) -> (PathBuf, Db) {
+    let dir = std::env::temp_dir().join(format!("ferrofeed-cli-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let db_path = dir.join("ferrofeed.db");
+    let db = Db::open(db_path.to_str().unwrap()).expect("failed to create test db");
+    db.init_feed_table().expect("failed to init feed table");
+    db.init_feed_item_table()
+        .expect("failed to init feed_item table");
+    std::fs::write(
+        dir.join("config.toml"),
+        format!(
+            "database_path = {:?}\n\n[sync]\nallow_background_sync = false\nschedule_mins = 60\n",
+            db_path.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    (dir, db)
+}
+
+/// Run the `ferrofeed` binary with the config of a home directory made by
+/// [`create_test_home`], and the rules, hooks and auth files in it.
+fn run_ferrofeed_command_in(home: &Path, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("ferrofeed").expect("Failed to find binary");
+    cmd.env("HOME", home)
+        .arg("-c")
+        .arg(home.join("config.toml"))
+        .args(args);
+    cmd.assert()
+}
+
 #[test]
 fn test_run_help() {
     let help_args_options = vec!["-h", "--help"];
@@ -34,7 +77,7 @@ fn test_run_help() {
 #[test]
 fn test_list_feeds_empty() {
     let db = create_test_db();
-    let result = commands::list_feeds(&db);
+    let result = commands::list_feeds(&db, OutputFormat::Text);
     assert!(result.is_ok());
 }
 
@@ -44,7 +87,9 @@ fn test_list_feeds_with_data() {
     db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
         .expect("failed to add feed");
 
-    let result = commands::list_feeds(&db);
+    let result = commands::list_feeds(&db, OutputFormat::Text);
+    assert!(result.is_ok());
+    let result = commands::list_feeds(&db, OutputFormat::Json);
     assert!(result.is_ok());
 }
 
@@ -54,7 +99,15 @@ async fn test_remove_feed_success() {
     db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
         .expect("failed to add feed");
 
-    let result = commands::remove_feed(&db, "https://example.com/feed.xml").await;
+    let result = commands::remove_feed(
+        &db,
+        "https://example.com/feed.xml",
+        &FeedClient::new(&HttpConfig::default()).unwrap(),
+        &SyncConfig::default(),
+        &RuleSet::default(),
+        &HookSet::default(),
+    )
+    .await;
     assert!(result.is_ok());
 
     let feeds = db.list_feeds().expect("failed to list feeds");
@@ -64,10 +117,120 @@ async fn test_remove_feed_success() {
 #[tokio::test]
 async fn test_remove_feed_not_found() {
     let db = create_test_db();
-    let result = commands::remove_feed(&db, "https://nonexistent.com/feed.xml").await;
+    let result = commands::remove_feed(
+        &db,
+        "https://nonexistent.com/feed.xml",
+        &FeedClient::new(&HttpConfig::default()).unwrap(),
+        &SyncConfig::default(),
+        &RuleSet::default(),
+        &HookSet::default(),
+    )
+    .await;
     assert!(result.is_ok());
 }
 
 // Note: add_feed and sync_feeds tests would require mocking HTTP requests
 // or using a test server, which is more complex. For now, we test the
 // database operations they rely on.
+
+#[test]
+fn test_search_by_category() {
+    let (dir, db) = create_test_home("search");
+    db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
+        .expect("failed to add feed");
+    let feed_id = db.list_feeds().expect("failed to list feeds")[0].id;
+    for (title, categories) in [
+        ("Tokio", &["Rust", "async"][..]),
+        ("Goroutines", &["Go", "async"]),
+        ("Uncategorized", &[]),
+    ] {
+        db.add_feed_item(
+            feed_id,
+            &ParsedFeedItem {
+                title: Some(title.to_string()),
+                link: Some(format!("https://example.com/{}", title)),
+                categories: categories.iter().map(|c| c.to_string()).collect(),
+                ..Default::default()
+            },
+        )
+        .expect("failed to add item");
+    }
+    drop(db);
+
+    let search = |args: &[&str]| -> Vec<String> {
+        let args = [&["--format", "json", "search"], args].concat();
+        let output = run_ferrofeed_command_in(&dir, &args).success();
+        let items: serde_json::Value =
+            serde_json::from_slice(&output.get_output().stdout).expect("search output is JSON");
+        let mut titles: Vec<String> = items
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["title"].as_str().unwrap().to_string())
+            .collect();
+        titles.sort();
+        titles
+    };
+    assert_eq!(search(&["--category", "RUST"]), ["Tokio"]);
+    assert_eq!(search(&["--category", "async"]), ["Goroutines", "Tokio"]);
+    assert_eq!(
+        search(&["goroutines", "--category", "async"]),
+        ["Goroutines"]
+    );
+    assert!(search(&["tokio", "--category", "go"]).is_empty());
+    assert!(search(&["--category", "python"]).is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_edit_feed() {
+    let db = create_test_db();
+    db.add_feed("https://example.com/feed.xml", Some("Test Feed"))
+        .expect("failed to add feed");
+
+    let result = commands::edit_feed(
+        &db,
+        "https://example.com/feed.xml",
+        Some("https://example.com/atom.xml"),
+        Some("Renamed"),
+        Some(true),
+        Some(true),
+    );
+    assert!(result.is_ok());
+
+    let feeds = db.list_feeds().expect("failed to list feeds");
+    assert_eq!(feeds[0].url, "https://example.com/atom.xml");
+    assert_eq!(feeds[0].title.as_deref(), Some("Renamed"));
+    assert!(feeds[0].is_paused);
+    assert!(db.get_feed_settings(feeds[0].id).unwrap().notify);
+}
+
+#[test]
+fn test_machine_readable_output() {
+    let (dir, db) = create_test_home("format");
+    // Nothing listens on the discard port, so syncing the feed fails
+    db.add_feed("http://127.0.0.1:9/feed.xml", Some("Down"))
+        .expect("failed to add feed");
+    drop(db);
+
+    let list = run_ferrofeed_command_in(&dir, &["--format", "json", "list"]).success();
+    let feeds: serde_json::Value =
+        serde_json::from_slice(&list.get_output().stdout).expect("list output is JSON");
+    assert_eq!(feeds[0]["url"], "http://127.0.0.1:9/feed.xml");
+    assert_eq!(feeds[0]["title"], "Down");
+
+    // CSV output has a header even without items
+    run_ferrofeed_command_in(&dir, &["items", "--format", "csv"])
+        .success()
+        .stdout("id,feed_id,feed,title,link,authors,published,read,starred,tags,categories\n");
+
+    // A sync in which a feed failed exits with 2, after printing the result of each feed
+    let sync = run_ferrofeed_command_in(&dir, &["--format", "ndjson", "sync"]).code(2);
+    let result: serde_json::Value =
+        serde_json::from_slice(&sync.get_output().stdout).expect("sync output is NDJSON");
+    assert_eq!(result["status"], "failed");
+    assert_eq!(result["new_items"], 0);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}